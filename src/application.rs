@@ -21,17 +21,18 @@ use adw::subclass::prelude::*;
 use gettextrs::gettext;
 use gtk::{gio, glib};
 
-use crate::client::MQTTyClient;
+use crate::client::{MQTTyClient, MQTTyClientParams};
 use crate::config;
 use crate::display_mode::{MQTTyDisplayMode, MQTTyDisplayModeIface};
 use crate::gsettings::MQTTySettingConnection;
 use crate::main_window::MQTTyWindow;
 use crate::pages::{MQTTyAddConnPage, MQTTyAllConnPage, MQTTyBasePage, MQTTyPanelPage};
 use crate::widgets::{
-    MQTTyAddConnCard, MQTTyBaseCard, MQTTyConnCard, MQTTyEditConnListBox, MQTTyKeyValueRow,
-    MQTTyMessageRow, MQTTyPublishAuthTab, MQTTyPublishBodyTab, MQTTyPublishGeneralTab,
-    MQTTyPublishUserPropsTab, MQTTyPublishView, MQTTySourceView, MQTTySubscribeAuthTab,
-    MQTTySubscribeGeneralTab, MQTTySubscribeView, MQTTySubscribeViewNotebook,
+    MQTTyAddConnCard, MQTTyBaseCard, MQTTyConnCard, MQTTyConnStateIndicator, MQTTyEditConnListBox,
+    MQTTyKeyValueRow, MQTTyMessageRow, MQTTyPublishAuthTab, MQTTyPublishBodyTab,
+    MQTTyPublishGeneralTab, MQTTyPublishUserPropsTab, MQTTyPublishView, MQTTySourceView,
+    MQTTySubscribeAuthTab, MQTTySubscribeGeneralTab, MQTTySubscribeView,
+    MQTTySubscribeViewNotebook, MQTTyTlsGroup, MQTTyWebSocketGroup,
 };
 
 mod imp {
@@ -75,9 +76,12 @@ mod imp {
             MQTTyBaseCard::static_type();
             MQTTyAddConnCard::static_type();
             MQTTyConnCard::static_type();
+            MQTTyConnStateIndicator::static_type();
             MQTTyEditConnListBox::static_type();
             MQTTySourceView::static_type();
             MQTTyKeyValueRow::static_type();
+            MQTTyTlsGroup::static_type();
+            MQTTyWebSocketGroup::static_type();
 
             MQTTyPublishView::static_type();
             MQTTyPublishGeneralTab::static_type();
@@ -191,6 +195,11 @@ impl MQTTyApplication {
 
     pub fn settings_delete_n_connection(&self, n: u32) {
         let conns = self.settings_connections();
+
+        if let Some(conn) = self.settings_n_connection(n) {
+            glib::spawn_future_local(async move { conn.delete_secrets().await });
+        }
+
         conns.remove(n);
     }
 
@@ -198,37 +207,166 @@ impl MQTTyApplication {
         &self.imp().clients
     }
 
+    /// Returns the long-lived client owned by the application for the given connection
+    /// profile, publish and subscribe tabs bound to the same profile share this client.
+    ///
+    /// The client is not connected on creation, use MQTTyClient::ensure_connected() before
+    /// using it.
+    pub fn connection_client(&self, conn: &MQTTySettingConnection) -> Option<MQTTyClient> {
+        let n = self.settings_connections().find(conn)?;
+
+        self.clients().borrow().get(n as usize).cloned()
+    }
+
+    /// Picks the client a tab connecting with the given parameters reuses: the client of
+    /// its profile, so that it's shared with other tabs, or else the client owned by the
+    /// tab. None if neither was created with those parameters, the tab then creates a new
+    /// one of its own
+    pub fn resolve_client(
+        &self,
+        profile: Option<&MQTTySettingConnection>,
+        owned: Option<MQTTyClient>,
+        params: &MQTTyClientParams,
+    ) -> Option<MQTTyClient> {
+        profile
+            .and_then(|profile| self.connection_client(profile))
+            .filter(|client| params.matches(client))
+            .or_else(|| owned.filter(|client| params.matches(client)))
+    }
+
     /// We are only requesting the GSettings on startup to prevent infinite recursion,
     /// e.g. app.settings_connections()::items-changed it's emitted, it is saved to
     /// external GSettings, GSettings::changed it's emitted, app.settings_connections() gets
     /// updated with external settings,
     /// app.settings_connections()::items-changed it's emitted again, etc.
+    ///
+    /// Connections are added once their passwords are read from the keyring
     fn setup_settings(&self) {
-        let settings = self.settings();
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = app)]
+            self,
+            async move {
+                let external_conns = app.load_settings_connections();
+
+                let mut moved_secrets = false;
+                for conn in external_conns.iter() {
+                    moved_secrets |= conn.load_secrets().await;
+                }
 
-        let external_conns = settings.get::<Vec<MQTTySettingConnection>>("connections");
+                app.setup_settings_connections(&external_conns);
 
+                // Plain text passwords moved to the keyring are removed from GSettings
+                if moved_secrets {
+                    app.save_settings_connections();
+                }
+            }
+        ));
+    }
+
+    fn setup_settings_connections(&self, external_conns: &[MQTTySettingConnection]) {
         let app_conns = self.settings_connections();
 
-        app_conns.extend_from_slice(&external_conns);
+        app_conns.extend_from_slice(external_conns);
+
+        self.clients()
+            .borrow_mut()
+            .extend(external_conns.iter().map(MQTTyClient::from));
+
+        // Keep clients index mapped to the connections, a modified connection is spliced
+        // into the list, so its client gets recreated with the new parameters
+        let clients = self.clients().clone();
+        app_conns.connect_items_changed(move |list, pos, removed, added| {
+            let new_clients = (pos..pos + added)
+                .filter_map(|i| list.item(i).and_downcast::<MQTTySettingConnection>())
+                .map(|conn| MQTTyClient::from(&conn))
+                .collect::<Vec<_>>();
+
+            let old_clients = clients
+                .borrow_mut()
+                .splice(pos as usize..(pos + removed) as usize, new_clients)
+                .collect::<Vec<_>>();
+
+            for client in old_clients {
+                client.disconnect_in_background();
+            }
+        });
 
         // Save settings to external GSettings when connections change
         app_conns.connect_items_changed(glib::clone!(
-            #[strong]
-            settings,
-            move |list: &gio::ListStore, _pos, _rem, _add| {
-                let conns: Vec<MQTTySettingConnection> = list
-                    .iter::<MQTTySettingConnection>()
-                    .filter_map(|i| i.ok())
-                    .collect();
-
-                if let Err(e) = settings.set("connections", &conns) {
-                    tracing::error!("Failed to save connections to settings: {}", e);
-                }
+            #[weak(rename_to = app)]
+            self,
+            move |list: &gio::ListStore, pos, _rem, added| {
+                app.save_settings_connections();
+
+                // Passwords of new or modified connections go to the keyring apart, the
+                // connections are saved again if they had to fall back to plain text
+                let conns = (pos..pos + added)
+                    .filter_map(|i| list.item(i).and_downcast::<MQTTySettingConnection>())
+                    .collect::<Vec<_>>();
+
+                glib::spawn_future_local(glib::clone!(
+                    #[weak]
+                    app,
+                    async move {
+                        let mut changed = false;
+                        for conn in conns.iter() {
+                            changed |= conn.save_secrets().await;
+                        }
+
+                        if changed {
+                            app.save_settings_connections();
+                        }
+                    }
+                ));
             }
         ));
     }
 
+    fn save_settings_connections(&self) {
+        let conns: Vec<MQTTySettingConnection> = self
+            .settings_connections()
+            .iter::<MQTTySettingConnection>()
+            .filter_map(|i| i.ok())
+            .collect();
+
+        if let Err(e) = self.settings().set("profiles", &conns) {
+            tracing::error!("Failed to save connections to settings: {}", e);
+        }
+    }
+
+    /// Reads the saved profiles, migrating them from the legacy "connections" key the
+    /// first time
+    fn load_settings_connections(&self) -> Vec<MQTTySettingConnection> {
+        let settings = self.settings();
+
+        if settings.user_value("profiles").is_some() || settings.user_value("connections").is_none()
+        {
+            return settings.get::<Vec<MQTTySettingConnection>>("profiles");
+        }
+
+        let legacy_conns = settings.value("connections");
+
+        let conns = legacy_conns
+            .iter()
+            .filter_map(|conn| MQTTySettingConnection::from_legacy(&conn))
+            .collect::<Vec<_>>();
+
+        // Legacy keys are only cleared once the profiles are safely saved
+        if let Err(e) = settings.set("profiles", &conns) {
+            tracing::error!("Failed to migrate connections to profiles: {}", e);
+            return conns;
+        }
+
+        settings.reset("connections");
+
+        tracing::info!(
+            "Migrated {} connections to the profiles format",
+            conns.len()
+        );
+
+        conns
+    }
+
     fn setup_gactions(&self) {
         // Quit
         let action_quit = gio::ActionEntry::builder("quit")
@@ -14,17 +14,29 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod message;
+mod response;
+mod socket;
+mod topic;
 
-pub use message::MQTTyClientMessage;
+pub use message::{MQTTyClientMessage, PAYLOAD_ENCODING_BASE64, PAYLOAD_ENCODING_UTF8};
+pub use response::{MQTTyClientError, MQTTyConnectionInfo, MQTTyReason};
+pub use topic::{shared_subscription, topic_matches_filter};
 
 use std::cell::{Cell, OnceCell, RefCell};
-use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use futures::future::{LocalBoxFuture, Shared};
+use futures::FutureExt;
+use gettextrs::gettext;
 use gtk::glib;
 use gtk::glib::subclass::Signal;
 
+use crate::gsettings::MQTTySettingConnection;
+
 #[derive(Default, Clone, Copy, glib::Enum, PartialEq)]
 #[enum_type(name = "MQTTyClientVersion")]
 pub enum MQTTyClientVersion {
@@ -33,7 +45,7 @@ pub enum MQTTyClientVersion {
     V5,
 }
 
-#[derive(Default, Clone, Copy, glib::Enum)]
+#[derive(Default, Clone, Copy, Debug, glib::Enum, PartialEq)]
 #[enum_type(name = "MQTTyClientQos")]
 pub enum MQTTyClientQos {
     #[default]
@@ -42,13 +54,344 @@ pub enum MQTTyClientQos {
     Qos2,
 }
 
-/// TLS configuration options
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Copy, glib::Enum, PartialEq)]
+#[enum_type(name = "MQTTyClientState")]
+pub enum MQTTyClientState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Connected,
+    /// Connection was lost and the client is waiting to retry, see "reconnecting" signal
+    Reconnecting,
+    /// Connection was lost and automatic reconnect is disabled
+    ConnectionLost,
+}
+
+/// What the client does with received messages while its buffer is full, that is,
+/// while the main loop can't keep up with the incoming rate
+#[derive(Default, Clone, Copy, Debug, glib::Enum, PartialEq)]
+#[enum_type(name = "MQTTyDropPolicy")]
+pub enum MQTTyDropPolicy {
+    /// Stop reading from the network until there is room, the broker ends up holding
+    /// the messages back
+    #[default]
+    Block,
+    /// Discard the message just received
+    DropNewest,
+    /// Discard the oldest buffered message to make room for the new one
+    DropOldest,
+}
+
+impl From<u32> for MQTTyDropPolicy {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::DropNewest,
+            2 => Self::DropOldest,
+            _ => Self::Block,
+        }
+    }
+}
+
+/// Received messages are handed to the main loop in batches of up to this size, the
+/// main loop gets to draw and handle input between batches
+const MESSAGE_BATCH_SIZE: usize = 256;
+
+/// Whether the broker sends retained messages when subscribing, MQTT v5 only
+#[derive(Default, Clone, Copy, Debug, glib::Enum, PartialEq)]
+#[enum_type(name = "MQTTyRetainHandling")]
+pub enum MQTTyRetainHandling {
+    #[default]
+    SendOnSubscribe,
+    /// Only if the subscription didn't exist already
+    SendOnNewSubscription,
+    DontSend,
+}
+
+impl From<u32> for MQTTyRetainHandling {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::SendOnNewSubscription,
+            2 => Self::DontSend,
+            _ => Self::SendOnSubscribe,
+        }
+    }
+}
+
+/// Options of a single topic filter subscription, everything but the QoS is MQTT v5 only
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct SubscribeOptions {
+    pub qos: MQTTyClientQos,
+    /// Don't receive the messages published by this same client
+    pub no_local: bool,
+    /// Keep the retain flag of forwarded messages, instead of clearing it
+    pub retain_as_published: bool,
+    pub retain_handling: MQTTyRetainHandling,
+    /// Sent back in every message matching the subscription, 0 for none
+    pub subscription_identifier: u32,
+}
+
+/// Topic filter subscribed through a client, several tabs sharing the client may hold
+/// the same filter, so it's only unsubscribed when the last holder releases it
+#[derive(Clone, Debug)]
+pub struct Subscription {
+    pub topic: String,
+    /// Options of the last SUBSCRIBE sent, the ones the broker applies
+    pub options: SubscribeOptions,
+    /// Options each holder subscribed with, in the order they subscribed
+    pub holders: Vec<SubscribeOptions>,
+}
+
+/// Reason code sent in the DISCONNECT packet, MQTT v5 only
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum MQTTyDisconnectReason {
+    /// The connection is closed on purpose, the broker discards the will
+    #[default]
+    Normal,
+    /// The connection is closed on purpose, but the broker publishes the will anyway
+    WithWill,
+}
+
+/// Lowest TLS version accepted from the server, paho passes it to OpenSSL as the minimum
+/// protocol version (as the only one with OpenSSL older than 1.1). Automatic lets the
+/// TLS library negotiate it
+#[derive(Default, Clone, Copy, glib::Enum, PartialEq)]
+#[enum_type(name = "MQTTyTlsVersion")]
+pub enum MQTTyTlsVersion {
+    #[default]
+    Automatic,
+    Tls1_0,
+    Tls1_1,
+    Tls1_2,
+}
+
+impl From<u32> for MQTTyTlsVersion {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Tls1_0,
+            2 => Self::Tls1_1,
+            3 => Self::Tls1_2,
+            _ => Self::Automatic,
+        }
+    }
+}
+
+/// Transport of the connection, given by the scheme of the URL
+///
+/// WebSocket transports can be tried against a local mosquitto with a listener like:
+///
+/// ```text
+/// listener 8080
+/// protocol websockets
+/// allow_anonymous true
+/// ```
+///
+/// and the URL `ws://localhost:8080`, any path is accepted by mosquitto
+#[derive(Default, Clone, Copy, glib::Enum, PartialEq)]
+#[enum_type(name = "MQTTyTransport")]
+pub enum MQTTyTransport {
+    #[default]
+    Tcp,
+    Tls,
+    WebSocket,
+    SecureWebSocket,
+}
+
+impl MQTTyTransport {
+    pub fn from_url(url: &str) -> Self {
+        let Some((scheme, _)) = url.split_once("://") else {
+            return Self::Tcp;
+        };
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "ssl" | "mqtts" => Self::Tls,
+            "ws" => Self::WebSocket,
+            "wss" => Self::SecureWebSocket,
+            _ => Self::Tcp,
+        }
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Tls => "ssl",
+            Self::WebSocket => "ws",
+            Self::SecureWebSocket => "wss",
+        }
+    }
+
+    pub fn is_websocket(&self) -> bool {
+        matches!(self, Self::WebSocket | Self::SecureWebSocket)
+    }
+
+    /// Replaces the scheme of the URL with the one of this transport, a URL without
+    /// scheme gets one
+    pub fn apply_to_url(&self, url: &str) -> String {
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+
+        format!("{}://{}", self.scheme(), rest)
+    }
+}
+
+impl From<u32> for MQTTyTransport {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Tls,
+            2 => Self::WebSocket,
+            3 => Self::SecureWebSocket,
+            _ => Self::Tcp,
+        }
+    }
+}
+
+/// TLS and WebSocket configuration options
+///
+/// There's no server name (SNI) option, paho sends the host of the URL it connects to
+/// as server name, or none for IP addresses, and its SSL options have no field to send
+/// another one
+#[derive(Clone, PartialEq)]
 pub struct TlsOptions {
     pub enabled: bool,
     pub ca_cert_path: Option<String>,
     pub client_cert_path: Option<String>,
     pub client_key_path: Option<String>,
+    /// Passphrase of an encrypted client key
+    pub key_passphrase: Option<String>,
+    /// Verify the server certificate against the CA certificates
+    pub verify_server: bool,
+    /// Verify that the server certificate matches the host of the URL
+    pub verify_hostname: bool,
+    /// ALPN protocols offered to the server, in order of preference
+    pub alpn: Vec<String>,
+    pub tls_version: MQTTyTlsVersion,
+    /// OpenSSL cipher list, None for the library defaults
+    pub ciphers: Option<String>,
+    /// Path of the WebSocket endpoint, replaces the path of the URL
+    pub ws_path: Option<String>,
+    /// HTTP headers of the WebSocket handshake, e.g. authentication tokens
+    pub http_headers: Vec<(String, String)>,
+    /// Proxies are only used by WebSocket transports
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ca_cert_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+            key_passphrase: None,
+            verify_server: true,
+            verify_hostname: true,
+            alpn: Vec::new(),
+            tls_version: MQTTyTlsVersion::Automatic,
+            ciphers: None,
+            ws_path: None,
+            http_headers: Vec::new(),
+            http_proxy: None,
+            https_proxy: None,
+        }
+    }
+}
+
+impl TlsOptions {
+    /// Parses a comma separated list of ALPN protocols, as the user writes it
+    pub fn parse_alpn(alpn: &str) -> Vec<String> {
+        alpn.split(',')
+            .map(str::trim)
+            .filter(|proto| !proto.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Parses a semicolon separated list of "Name: value" headers, as the user writes it
+    pub fn parse_http_headers(headers: &str) -> Vec<(String, String)> {
+        headers
+            .split(';')
+            .filter_map(|header| header.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .filter(|(name, _)| !name.is_empty())
+            .collect()
+    }
+
+    /// Inverse of parse_http_headers()
+    pub fn format_http_headers(headers: &[(String, String)]) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// None for an empty string, text fields of the UI are left empty for options that
+/// aren't set
+pub fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// Connection parameters a tab wants its client created with, see
+/// MQTTyApplication::resolve_client()
+pub struct MQTTyClientParams {
+    pub url: String,
+    pub mqtt_version: MQTTyClientVersion,
+    pub username: String,
+    pub password: String,
+    pub tls_options: TlsOptions,
+}
+
+impl MQTTyClientParams {
+    /// Whether the client was created with these parameters
+    pub fn matches(&self, client: &MQTTyClient) -> bool {
+        client.matches(&self.url, self.mqtt_version, &self.username, &self.password)
+            && client.tls_options() == self.tls_options
+    }
+
+    pub fn new_client(&self) -> MQTTyClient {
+        let client = MQTTyClient::new(&self.url, self.mqtt_version, &self.username, &self.password);
+        client.set_tls_options(self.tls_options.clone());
+        client
+    }
+}
+
+/// Port of the URL, or the default one of its transport
+fn broker_port(url: &str, transport: MQTTyTransport) -> u16 {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+    let authority = url[authority_start..].split('/').next().unwrap_or_default();
+
+    authority
+        .rsplit_once(':')
+        .and_then(|(_, port)| port.parse().ok())
+        .unwrap_or(match transport {
+            MQTTyTransport::Tcp => 1883,
+            MQTTyTransport::Tls => 8883,
+            MQTTyTransport::WebSocket => 80,
+            MQTTyTransport::SecureWebSocket => 443,
+        })
+}
+
+/// URL of a WebSocket endpoint with its path replaced
+fn websocket_url(url: &str, path: &str) -> String {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+
+    let base = match url[authority_start..].find('/') {
+        Some(i) => &url[..authority_start + i],
+        None => url,
+    };
+
+    format!("{}/{}", base, path.trim_start_matches('/'))
+}
+
+/// Connection events coming from paho threads, redirected to the main loop
+enum ClientEvent {
+    ConnectionLost,
+    /// Server sent a DISCONNECT packet (MQTT v5 only), carries the reason code
+    Disconnected(MQTTyReason),
 }
 
 mod imp {
@@ -70,9 +413,95 @@ mod imp {
         #[property(get, construct_only)]
         password: RefCell<String>,
 
+        /// Empty to let paho generate a random one
+        #[property(get, construct_only)]
+        client_id: RefCell<String>,
+
+        /// Clean session in MQTT v3.x
+        #[property(get, construct_only)]
+        clean_start: Cell<bool>,
+
+        /// Seconds, MQTT v5 only
+        #[property(get, construct_only)]
+        session_expiry_interval: Cell<u32>,
+
+        /// Seconds, 0 disables keep alive
+        #[property(get, construct_only)]
+        keep_alive: Cell<u32>,
+
+        /// MQTT v5 only, 0 leaves it to the broker default
+        #[property(get, construct_only)]
+        receive_maximum: Cell<u32>,
+
+        /// MQTT v5 only, 0 means no limit
+        #[property(get, construct_only)]
+        maximum_packet_size: Cell<u32>,
+
+        /// Last Will and Testament, published by the broker when the connection is lost
+        /// without a clean disconnection
+        #[property(get, construct_only, nullable)]
+        will: RefCell<Option<MQTTyClientMessage>>,
+
+        /// Seconds the broker waits before publishing the will, MQTT v5 only
+        #[property(get, construct_only)]
+        will_delay_interval: Cell<u32>,
+
+        /// Received messages waiting for the main loop, beyond this drop_policy applies
+        #[property(get, construct_only, minimum = 1, default_value = 1000)]
+        message_buffer_size: Cell<u32>,
+
+        #[property(get, construct_only, builder(MQTTyDropPolicy::Block))]
+        drop_policy: Cell<MQTTyDropPolicy>,
+
+        /// Received messages discarded because of drop_policy, updated once per batch
+        #[property(get)]
+        dropped_messages: Cell<u64>,
+
+        /// Counted from the paho threads, dropped_messages follows it
+        dropped: Arc<AtomicU64>,
+
+        #[property(get, builder(MQTTyClientState::Disconnected))]
+        state: Cell<MQTTyClientState>,
+
+        /// Whether to reconnect by ourselves when the connection is lost
+        #[property(get, set)]
+        auto_reconnect: Cell<bool>,
+
+        /// Seconds to wait before the first reconnection attempt, the delay is doubled on
+        /// every failed attempt, up to reconnect_max_delay
+        #[property(get, set, minimum = 1)]
+        reconnect_min_delay: Cell<u32>,
+
+        #[property(get, set, minimum = 1)]
+        reconnect_max_delay: Cell<u32>,
+
+        /// Set while the reconnection loop is running, clearing it stops the loop
+        reconnecting: Cell<bool>,
+
+        /// Topic filters subscribed through this client, they are subscribed again after
+        /// reconnecting, since the broker may have discarded the session
+        pub subscriptions: RefCell<Vec<Subscription>>,
+
         client: OnceCell<paho::AsyncClient>,
 
+        /// Inode of the socket of the current connection, None if it couldn't be told
+        /// apart from the others, see drop_connection()
+        socket: Cell<Option<u64>>,
+
+        /// Set by drop_connection() until the next connect_client(), so that the
+        /// connection lost on purpose is not reconnected
+        dropping: Cell<bool>,
+
+        /// CONNACK of the current connection, None while not connected
+        #[property(get, nullable)]
+        connection_info: RefCell<Option<MQTTyConnectionInfo>>,
+
         pub tls_options: RefCell<TlsOptions>,
+
+        /// Connection attempt in progress, shared by every caller of ensure_connected(),
+        /// so that tabs using the same client don't race each other with several CONNECTs
+        pub connecting:
+            RefCell<Option<Shared<LocalBoxFuture<'static, Result<(), MQTTyClientError>>>>>,
     }
 
     #[glib::object_subclass]
@@ -93,6 +522,7 @@ mod imp {
 
             let client = match paho::CreateOptionsBuilder::new()
                 .server_uri(obj.url())
+                .client_id(obj.client_id())
                 .create_client()
             {
                 Err(e) => {
@@ -107,51 +537,113 @@ mod imp {
                 Ok(c) => c,
             };
 
-            // Receiving message signal and redirecting it to Object signal emission
-            let (message_tx, message_rx) = async_channel::bounded(1);
+            // Receiving message signal and redirecting it to Object signal emission, paho
+            // calls back from its network thread, so received messages are buffered until
+            // the main loop takes them
+            let (message_tx, message_rx) =
+                async_channel::bounded(obj.message_buffer_size() as usize);
 
-            client.set_message_callback(move |_, msg| {
-                let msg = msg.unwrap();
-                let _ = message_tx.send_blocking(msg);
-            });
+            let drop_policy = obj.drop_policy();
+            let dropped = self.dropped.clone();
 
-            glib::spawn_future_local(glib::clone!(
-                #[weak]
-                obj,
-                async move {
-                    loop {
-                        let Ok(msg) = message_rx.recv().await else {
-                            return;
-                        };
+            client.set_message_callback(move |_, msg| {
+                let Some(msg) = msg else {
+                    return;
+                };
+
+                let discarded = match drop_policy {
+                    MQTTyDropPolicy::Block => {
+                        let _ = message_tx.send_blocking(msg);
+                        false
+                    }
+                    MQTTyDropPolicy::DropNewest => {
+                        matches!(message_tx.try_send(msg), Err(e) if e.is_full())
+                    }
+                    MQTTyDropPolicy::DropOldest => {
+                        matches!(message_tx.force_send(msg), Ok(Some(_)))
+                    }
+                };
 
-                        println!("{:?}", msg);
+                if discarded {
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            });
 
-                        let out_msg = MQTTyClientMessage::new();
+            let obj_weak = obj.downgrade();
+            glib::spawn_future_local(async move {
+                while let Ok(msg) = message_rx.recv().await {
+                    let batch = std::iter::once(msg)
+                        .chain(std::iter::from_fn(|| message_rx.try_recv().ok()))
+                        .take(MESSAGE_BATCH_SIZE)
+                        .collect::<Vec<_>>();
 
-                        let props = msg.properties();
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
 
-                        out_msg.set_topic(msg.topic());
-                        out_msg.set_qos(MQTTyClientQos::from(msg.qos()));
-                        out_msg.set_body(msg.payload());
-                        out_msg.set_mqtt_version(obj.mqtt_version());
-                        out_msg.set_content_type(props.get_string(paho::PropertyCode::ContentType));
-                        out_msg.set_retained(msg.retained());
-                        out_msg
-                            .set_user_properties(props.user_iter().collect::<Vec<_>>().as_slice());
+                    let mqtt_version = obj.mqtt_version();
 
+                    for msg in batch {
+                        let out_msg = received_message(&msg, mqtt_version);
                         obj.emit_by_name::<()>("message", &[&out_msg]);
                     }
+
+                    obj.imp().sync_dropped_messages();
+
+                    drop(obj);
+
+                    // Lower priority than drawing, so that a flood of messages doesn't
+                    // freeze the window
+                    glib::timeout_future_with_priority(
+                        glib::Priority::DEFAULT_IDLE,
+                        Duration::ZERO,
+                    )
+                    .await;
                 }
-            ));
+            });
+
+            // Connection lifecycle callbacks, they are called from paho threads too
+            let (event_tx, event_rx) = async_channel::unbounded();
+
+            let lost_tx = event_tx.clone();
+            client.set_connection_lost_callback(move |_| {
+                let _ = lost_tx.send_blocking(ClientEvent::ConnectionLost);
+            });
+
+            client.set_disconnected_callback(move |_, props, code| {
+                let _ = event_tx.send_blocking(ClientEvent::Disconnected(reason(code, &props)));
+            });
+
+            let obj_weak = obj.downgrade();
+            glib::spawn_future_local(async move {
+                while let Ok(event) = event_rx.recv().await {
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
+                    obj.imp().handle_event(event);
+                }
+            });
 
             self.client.set(client).ok().unwrap();
         }
 
         fn signals() -> &'static [Signal] {
             static SIGNALS: LazyLock<Vec<Signal>> = LazyLock::new(|| {
-                vec![Signal::builder("message")
-                    .param_types([MQTTyClientMessage::static_type()])
-                    .build()]
+                vec![
+                    Signal::builder("message")
+                        .param_types([MQTTyClientMessage::static_type()])
+                        .build(),
+                    Signal::builder("connected").build(),
+                    // Reason of the disconnection, empty if it was requested by us
+                    Signal::builder("disconnected")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("connection-lost").build(),
+                    // Attempt number and seconds to wait before attempting
+                    Signal::builder("reconnecting")
+                        .param_types([u32::static_type(), u32::static_type()])
+                        .build(),
+                ]
             });
             &*SIGNALS
         }
@@ -162,9 +654,183 @@ mod imp {
             self.client.get().unwrap()
         }
 
-        pub async fn connect_client(&self) -> Result<(), String> {
+        fn set_state(&self, state: MQTTyClientState) {
+            if state != MQTTyClientState::Connected {
+                self.set_connection_info(None);
+            }
+
+            if self.state.replace(state) != state {
+                self.obj().notify_state();
+            }
+        }
+
+        fn set_connection_info(&self, info: Option<MQTTyConnectionInfo>) {
+            if *self.connection_info.borrow() != info {
+                self.connection_info.replace(info);
+                self.obj().notify_connection_info();
+            }
+        }
+
+        pub fn is_connected(&self) -> bool {
+            self.client().is_connected()
+        }
+
+        pub async fn connect_client(&self) -> Result<MQTTyConnectionInfo, MQTTyClientError> {
             let client = self.client();
 
+            let connect_opts = self.connect_options()?;
+
+            // A manual connection takes over any pending reconnection
+            self.reconnecting.set(false);
+
+            self.set_state(MQTTyClientState::Connecting);
+
+            self.dropping.set(false);
+
+            let sockets = socket::sockets();
+
+            match client.connect(Some(connect_opts)).await {
+                Ok(response) => {
+                    self.socket
+                        .set(socket::opened_since(&sockets, self.peer_port()));
+                    let info = connection_info(&response);
+                    self.on_connected(info.clone()).await;
+                    Ok(info)
+                }
+                Err(e) => {
+                    self.set_state(MQTTyClientState::Disconnected);
+                    Err(e.into())
+                }
+            }
+        }
+
+        async fn on_connected(&self, info: MQTTyConnectionInfo) {
+            let obj = self.obj();
+
+            self.set_state(MQTTyClientState::Connected);
+            self.set_connection_info(Some(info));
+
+            obj.emit_by_name::<()>("connected", &[]);
+
+            let subscriptions = self.subscriptions.borrow().clone();
+
+            for Subscription { topic, options, .. } in subscriptions {
+                if let Err(e) = self.send_subscribe(&topic, &options).await {
+                    tracing::warn!("Failed to subscribe again to {}: {}", topic, e);
+                }
+            }
+        }
+
+        fn sync_dropped_messages(&self) {
+            let dropped = self.dropped.load(Ordering::Relaxed);
+
+            if self.dropped_messages.replace(dropped) != dropped {
+                self.obj().notify_dropped_messages();
+            }
+        }
+
+        fn handle_event(&self, event: ClientEvent) {
+            let obj = self.obj();
+
+            match event {
+                ClientEvent::ConnectionLost => {
+                    tracing::warn!("Connection to {} lost", obj.url());
+                    obj.emit_by_name::<()>("connection-lost", &[]);
+                }
+                ClientEvent::Disconnected(reason) => {
+                    tracing::warn!("Disconnected by {}: {}", obj.url(), reason);
+                    obj.emit_by_name::<()>("disconnected", &[&reason.to_string()]);
+                }
+            }
+
+            self.socket.set(None);
+
+            if obj.auto_reconnect() && !self.dropping.get() {
+                self.start_reconnect();
+            } else {
+                self.set_state(MQTTyClientState::ConnectionLost);
+            }
+        }
+
+        fn start_reconnect(&self) {
+            // Paho may notify both a server disconnection and a connection lost
+            if self.reconnecting.replace(true) {
+                return;
+            }
+
+            let obj_weak = self.obj().downgrade();
+
+            glib::spawn_future_local(async move {
+                let mut attempt: u32 = 0;
+                let mut delay: u32 = 0;
+
+                loop {
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
+                    let imp = obj.imp();
+
+                    if !imp.reconnecting.get() {
+                        return;
+                    }
+
+                    attempt += 1;
+                    delay = if attempt == 1 {
+                        obj.reconnect_min_delay()
+                    } else {
+                        (delay * 2).min(obj.reconnect_max_delay())
+                    };
+
+                    imp.set_state(MQTTyClientState::Reconnecting);
+                    obj.emit_by_name::<()>("reconnecting", &[&attempt, &delay]);
+
+                    // Do not keep the client alive while waiting
+                    drop(obj);
+
+                    glib::timeout_future_seconds(delay).await;
+
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
+                    let imp = obj.imp();
+
+                    if !imp.reconnecting.get() {
+                        return;
+                    }
+
+                    let sockets = socket::sockets();
+
+                    match imp.client().reconnect().await {
+                        Ok(response) => {
+                            imp.socket
+                                .set(socket::opened_since(&sockets, imp.peer_port()));
+                            imp.reconnecting.set(false);
+                            imp.on_connected(connection_info(&response)).await;
+                            return;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Reconnection attempt {} failed: {}", attempt, e);
+                        }
+                    }
+                }
+            });
+        }
+
+        /// Port the socket of the connection is open to, None if it goes through a
+        /// proxy, whose port isn't known
+        fn peer_port(&self) -> Option<u16> {
+            let obj = self.obj();
+            let tls = self.tls_options.borrow();
+
+            let transport = MQTTyTransport::from_url(&obj.url());
+            if transport.is_websocket() && (tls.http_proxy.is_some() || tls.https_proxy.is_some()) {
+                return None;
+            }
+
+            Some(broker_port(&obj.url(), transport))
+        }
+
+        fn connect_options(&self) -> Result<paho::ConnectOptions, MQTTyClientError> {
             let obj = self.obj();
             let tls = self.tls_options.borrow();
 
@@ -174,67 +840,365 @@ mod imp {
 
                 if let Some(ref ca_path) = tls.ca_cert_path {
                     if !ca_path.is_empty() {
-                        ssl_builder.trust_store(ca_path).map_err(|e| e.to_string())?;
+                        ssl_builder.trust_store(ca_path).map_err(invalid_options)?;
                     }
                 }
 
                 if let Some(ref cert_path) = tls.client_cert_path {
                     if !cert_path.is_empty() {
-                        ssl_builder.key_store(cert_path).map_err(|e| e.to_string())?;
+                        ssl_builder.key_store(cert_path).map_err(invalid_options)?;
                     }
                 }
 
                 if let Some(ref key_path) = tls.client_key_path {
                     if !key_path.is_empty() {
-                        ssl_builder.private_key(key_path).map_err(|e| e.to_string())?;
+                        ssl_builder.private_key(key_path).map_err(invalid_options)?;
                     }
                 }
 
+                if let Some(ref passphrase) = tls.key_passphrase {
+                    ssl_builder.private_key_password(passphrase);
+                }
+
+                if let Some(ref ciphers) = tls.ciphers {
+                    ssl_builder.enabled_cipher_suites(ciphers);
+                }
+
+                if !tls.alpn.is_empty() {
+                    ssl_builder
+                        .alpn_protos(&tls.alpn.iter().map(String::as_str).collect::<Vec<_>>());
+                }
+
+                ssl_builder
+                    .enable_server_cert_auth(tls.verify_server)
+                    .verify(tls.verify_hostname)
+                    .ssl_version(tls.tls_version.into());
+
                 ssl_builder.finalize()
             } else {
                 paho::SslOptions::default()
             };
 
-            client
-                .connect(Some(
-                    paho::ConnectOptionsBuilder::with_mqtt_version(obj.mqtt_version())
-                        .user_name(obj.username())
-                        .password(obj.password())
-                        .ssl_options(ssl_opts)
+            let mut builder = paho::ConnectOptionsBuilder::with_mqtt_version(obj.mqtt_version());
+
+            builder
+                .user_name(obj.username())
+                .password(obj.password())
+                .ssl_options(ssl_opts)
+                .keep_alive_interval(Duration::from_secs(obj.keep_alive().into()));
+
+            if MQTTyTransport::from_url(&obj.url()).is_websocket() {
+                if let Some(ref path) = tls.ws_path {
+                    builder.server_uris(&[websocket_url(&obj.url(), path)]);
+                }
+
+                if !tls.http_headers.is_empty() {
+                    builder.http_headers(&tls.http_headers);
+                }
+
+                if let Some(ref proxy) = tls.http_proxy {
+                    builder.http_proxy(proxy.as_str());
+                }
+
+                if let Some(ref proxy) = tls.https_proxy {
+                    builder.https_proxy(proxy.as_str());
+                }
+            }
+
+            if let Some(will) = self.will_message()? {
+                builder.will_message(will);
+            }
+
+            match obj.mqtt_version() {
+                MQTTyClientVersion::V3X => {
+                    builder.clean_session(obj.clean_start());
+                }
+                MQTTyClientVersion::V5 => {
+                    builder
+                        .clean_start(obj.clean_start())
+                        .properties(self.connect_properties()?);
+                }
+            }
+
+            Ok(builder.finalize())
+        }
+
+        fn will_message(&self) -> Result<Option<paho::Message>, MQTTyClientError> {
+            let obj = self.obj();
+
+            let Some(will) = obj.will() else {
+                return Ok(None);
+            };
+
+            if obj.mqtt_version() == MQTTyClientVersion::V3X {
+                return Ok(Some(
+                    paho::MessageBuilder::new()
+                        .topic(will.topic())
+                        .qos(paho::QoS::from(will.qos()))
+                        .retained(will.retained())
+                        .payload(will.body())
                         .finalize(),
-                ))
+                ));
+            }
+
+            let mut props = paho::Message::from(&will).properties().clone();
+
+            if obj.will_delay_interval() > 0 {
+                props
+                    .push_int(
+                        paho::PropertyCode::WillDelayInterval,
+                        obj.will_delay_interval() as i32,
+                    )
+                    .map_err(invalid_options)?;
+            }
+
+            Ok(Some(
+                paho::MessageBuilder::new()
+                    .topic(will.topic())
+                    .qos(paho::QoS::from(will.qos()))
+                    .retained(will.retained())
+                    .payload(will.body())
+                    .properties(props)
+                    .finalize(),
+            ))
+        }
+
+        /// MQTT v5 CONNECT properties, zero valued options are left out so that the
+        /// broker applies its defaults
+        fn connect_properties(&self) -> Result<paho::Properties, MQTTyClientError> {
+            let obj = self.obj();
+
+            let mut props = paho::Properties::new();
+
+            let int_props = [
+                (
+                    paho::PropertyCode::SessionExpiryInterval,
+                    obj.session_expiry_interval(),
+                ),
+                (paho::PropertyCode::ReceiveMaximum, obj.receive_maximum()),
+                (
+                    paho::PropertyCode::MaximumPacketSize,
+                    obj.maximum_packet_size(),
+                ),
+            ];
+
+            for (code, value) in int_props {
+                if value == 0 {
+                    continue;
+                }
+
+                // Paho takes the raw value, a u32 above i32::MAX keeps its bits
+                props
+                    .push_int(code, value as i32)
+                    .map_err(invalid_options)?;
+            }
+
+            Ok(props)
+        }
+
+        pub async fn disconnect_client(
+            &self,
+            reason: MQTTyDisconnectReason,
+        ) -> Result<(), MQTTyClientError> {
+            let obj = self.obj();
+
+            // Stop reconnecting, this disconnection is wanted
+            self.reconnecting.set(false);
+
+            // v3.x DISCONNECT packets carry no reason code
+            let opts = match obj.mqtt_version() {
+                MQTTyClientVersion::V3X => None,
+                MQTTyClientVersion::V5 => Some(
+                    paho::DisconnectOptionsBuilder::new()
+                        .reason_code(reason.into())
+                        .finalize(),
+                ),
+            };
+
+            let ret = self
+                .client()
+                .disconnect(opts)
                 .await
-                .map(|res| println!("CONNECTION SERVER RESPONSE: {res:?}"))
-                .map_err(|e| e.to_string())
+                .map(|_| ())
+                .map_err(MQTTyClientError::from);
+
+            self.set_state(MQTTyClientState::Disconnected);
+
+            obj.emit_by_name::<()>("disconnected", &[&String::new()]);
+
+            ret
         }
 
-        pub async fn disconnect_client(&self) -> Result<(), String> {
-            let client = self.client();
+        /// Closes the connection without a DISCONNECT packet, the broker finds it lost
+        /// and publishes the will, as if the network failed
+        pub fn drop_connection(&self) -> Result<(), MQTTyClientError> {
+            let Some(inode) = self.socket.get() else {
+                return Err(MQTTyClientError::Connection(gettext(
+                    "The socket of the connection could not be found",
+                )));
+            };
 
-            client
-                .disconnect(None)
+            socket::shutdown(inode).map_err(|e| MQTTyClientError::Connection(e.to_string()))?;
+
+            self.socket.set(None);
+            self.dropping.set(true);
+
+            Ok(())
+        }
+
+        /// Ends the connection asking the broker to publish the will anyway, only MQTT
+        /// v5 has a way to do this
+        pub async fn disconnect_with_will(&self) -> Result<(), MQTTyClientError> {
+            if self.obj().mqtt_version() != MQTTyClientVersion::V5 {
+                return Err(MQTTyClientError::RequiresV5(gettext(
+                    "Disconnecting with the will requires MQTT v5, v3.x always discards the will on disconnection",
+                )));
+            }
+
+            self.disconnect_client(MQTTyDisconnectReason::WithWill)
                 .await
-                .map(|res| println!("DISCONNECTION SERVER RESPONSE: {res:?}"))
-                .map_err(|e| e.to_string())
         }
 
-        pub async fn publish(&self, message: &MQTTyClientMessage) -> Result<(), String> {
+        pub async fn publish(&self, message: &MQTTyClientMessage) -> Result<(), MQTTyClientError> {
             let client = self.client();
 
             client
                 .publish(paho::Message::from(message))
                 .await
-                .map_err(|e| e.to_string())
+                .map_err(MQTTyClientError::from)
         }
 
-        pub async fn subscribe(&self, topic: &str, qos: MQTTyClientQos) -> Result<(), String> {
+        pub async fn subscribe(
+            &self,
+            topic: &str,
+            options: &SubscribeOptions,
+        ) -> Result<MQTTyReason, MQTTyClientError> {
+            let reason = self.send_subscribe(topic, options).await?;
+
+            let mut subscriptions = self.subscriptions.borrow_mut();
+            match subscriptions.iter_mut().find(|s| s.topic == topic) {
+                Some(subscription) => {
+                    subscription.options = options.clone();
+                    subscription.holders.push(options.clone());
+                }
+                None => subscriptions.push(Subscription {
+                    topic: topic.to_string(),
+                    options: options.clone(),
+                    holders: vec![options.clone()],
+                }),
+            }
+
+            Ok(reason)
+        }
+
+        /// Releases the holder of the subscription that subscribed with these options,
+        /// the UNSUBSCRIBE packet is only sent once nobody else holds it. Otherwise, if
+        /// the broker was applying the options of the released holder, the filter is
+        /// subscribed again with the options of the last remaining one
+        ///
+        /// Returns the UNSUBACK reason, None if the packet was not sent
+        pub async fn unsubscribe(
+            &self,
+            topic: &str,
+            options: &SubscribeOptions,
+        ) -> Result<Option<MQTTyReason>, MQTTyClientError> {
+            let remaining = {
+                let mut subscriptions = self.subscriptions.borrow_mut();
+
+                let Some(pos) = subscriptions.iter().position(|s| s.topic == topic) else {
+                    return Ok(None);
+                };
+
+                let subscription = &mut subscriptions[pos];
+
+                // Holders always release with the options they subscribed with, the
+                // last one is released if there's no such holder anyway
+                let holder = subscription
+                    .holders
+                    .iter()
+                    .rposition(|holder| holder == options)
+                    .unwrap_or(subscription.holders.len() - 1);
+                subscription.holders.remove(holder);
+
+                match subscription.holders.last() {
+                    Some(last) if *last == subscription.options => return Ok(None),
+                    Some(last) => {
+                        subscription.options = last.clone();
+                        Some(last.clone())
+                    }
+                    None => {
+                        subscriptions.remove(pos);
+                        None
+                    }
+                }
+            };
+
+            // Without a connection there is nothing to change, the filter is
+            // subscribed with the remaining options when reconnecting
+            if let Some(options) = remaining {
+                if self.is_connected() {
+                    self.send_subscribe(topic, &options).await?;
+                }
+
+                return Ok(None);
+            }
+
+            // Without a connection there is nothing to unsubscribe from, and it won't
+            // be subscribed again when reconnecting
+            if !self.is_connected() {
+                return Ok(None);
+            }
+
+            let response = self.client().unsubscribe(topic).await?;
+
+            let code = response
+                .unsubscribe_response()
+                .unwrap_or(paho::ReasonCode::Success);
+
+            ack(reason(code, response.properties())).map(Some)
+        }
+
+        /// Every filter is subscribed with its own SUBSCRIBE packet, since the
+        /// subscription identifier is a property of the whole packet
+        ///
+        /// Returns the SUBACK reason, a rejected filter is an error
+        async fn send_subscribe(
+            &self,
+            topic: &str,
+            options: &SubscribeOptions,
+        ) -> Result<MQTTyReason, MQTTyClientError> {
             let client = self.client();
 
-            client
-                .subscribe(topic, qos)
-                .await
-                .map(|res| println!("SUBSCRIPTION SERVER RESPONSE: {res:?}"))
-                .map_err(|e| e.to_string())
+            let token = match self.obj().mqtt_version() {
+                MQTTyClientVersion::V3X => client.subscribe(topic, options.qos),
+                MQTTyClientVersion::V5 => {
+                    let mut props = paho::Properties::new();
+
+                    if options.subscription_identifier > 0 {
+                        props
+                            .push_int(
+                                paho::PropertyCode::SubscriptionIdentifier,
+                                options.subscription_identifier as i32,
+                            )
+                            .map_err(invalid_options)?;
+                    }
+
+                    client.subscribe_with_options(
+                        topic,
+                        options.qos,
+                        paho::SubscribeOptions::from(options),
+                        props,
+                    )
+                }
+            };
+
+            let response = token.await?;
+
+            let code = response
+                .subscribe_response()
+                .unwrap_or(paho::ReasonCode::Success);
+
+            ack(reason(code, response.properties()))
         }
     }
 }
@@ -242,7 +1206,7 @@ mod imp {
 glib::wrapper! {
     /// This Object works as an inteface, in case the underlying MQTT library changes,
     /// also, we are using it so that we can emit signals like "connected",
-    /// "connection-error" and "message"
+    /// "disconnected", "connection-lost", "reconnecting" and "message"
     pub struct MQTTyClient(ObjectSubclass<imp::MQTTyClient>);
 }
 
@@ -253,28 +1217,153 @@ impl MQTTyClient {
         username: &str,
         password: &str,
     ) -> Self {
+        Self::builder(url, mqtt_version, username, password).build()
+    }
+
+    /// Builder with the mandatory properties and the defaults set, the rest of the
+    /// construct only properties can be added before building
+    fn builder(
+        url: &str,
+        mqtt_version: MQTTyClientVersion,
+        username: &str,
+        password: &str,
+    ) -> glib::object::ObjectBuilder<'static, Self> {
         glib::Object::builder()
             .property("url", url)
             .property("mqtt_version", mqtt_version)
             .property("username", username)
             .property("password", password)
-            .build()
+            .property("clean_start", true)
+            .property("keep_alive", 60u32)
+            .property("auto_reconnect", true)
+            .property("reconnect_min_delay", 1u32)
+            .property("reconnect_max_delay", 60u32)
     }
 
-    pub async fn connect_client(&self) -> Result<(), String> {
+    /// Returns what the broker told about the connection, it's kept in the
+    /// "connection-info" property too while connected
+    pub async fn connect_client(&self) -> Result<MQTTyConnectionInfo, MQTTyClientError> {
         self.imp().connect_client().await
     }
 
-    pub async fn disconnect_client(&self) -> Result<(), String> {
-        self.imp().disconnect_client().await
+    /// Connects the client unless it is already connected, callers arriving while a
+    /// connection attempt is in progress wait for that same attempt
+    pub async fn ensure_connected(&self) -> Result<(), MQTTyClientError> {
+        let imp = self.imp();
+
+        if imp.is_connected() && self.state() == MQTTyClientState::Connected {
+            return Ok(());
+        }
+
+        let pending = imp.connecting.borrow().clone();
+
+        let connecting = match pending {
+            Some(connecting) => connecting,
+            None => {
+                let this = self.clone();
+                let connecting = async move {
+                    let ret = this.connect_client().await.map(|_| ());
+
+                    // Cleared by the attempt itself, once, whoever is waiting for it
+                    this.imp().connecting.replace(None);
+
+                    ret
+                }
+                .boxed_local()
+                .shared();
+                imp.connecting.replace(Some(connecting.clone()));
+                connecting
+            }
+        };
+
+        connecting.await
+    }
+
+    /// Whether this client was created with the given connection parameters, tabs use it
+    /// to decide if they can keep reusing a client or need a new one
+    pub fn matches(
+        &self,
+        url: &str,
+        mqtt_version: MQTTyClientVersion,
+        username: &str,
+        password: &str,
+    ) -> bool {
+        self.url() == url
+            && self.mqtt_version() == mqtt_version
+            && self.username() == username
+            && self.password() == password
+    }
+
+    pub async fn disconnect_client(&self) -> Result<(), MQTTyClientError> {
+        self.imp()
+            .disconnect_client(MQTTyDisconnectReason::Normal)
+            .await
     }
 
-    pub async fn publish(&self, message: &MQTTyClientMessage) -> Result<(), String> {
+    /// Disconnects telling the broker why, the reason is only sent with MQTT v5
+    pub async fn disconnect_with_reason(
+        &self,
+        reason: MQTTyDisconnectReason,
+    ) -> Result<(), MQTTyClientError> {
+        self.imp().disconnect_client(reason).await
+    }
+
+    /// Disconnects the client without waiting for the result, meant for owners that are
+    /// being torn down and cannot await
+    pub fn disconnect_in_background(&self) {
+        if self.state() == MQTTyClientState::Disconnected {
+            return;
+        }
+
+        glib::spawn_future_local(glib::clone!(
+            #[strong(rename_to = this)]
+            self,
+            async move {
+                if let Err(e) = this.disconnect_client().await {
+                    tracing::warn!("Failed to disconnect client {}: {}", this.url(), e);
+                }
+            }
+        ));
+    }
+
+    /// Drops the connection abruptly, for testing how other clients react to this one
+    /// going offline. The client is left in the ConnectionLost state, it is not
+    /// reconnected automatically
+    pub fn drop_connection(&self) -> Result<(), MQTTyClientError> {
+        self.imp().drop_connection()
+    }
+
+    /// Whether the socket of the connection is known, drop_connection() fails otherwise
+    pub fn can_drop_connection(&self) -> bool {
+        self.imp().socket.get().is_some()
+    }
+
+    /// Disconnects cleanly but making the broker publish the will, MQTT v5 only
+    pub async fn disconnect_with_will(&self) -> Result<(), MQTTyClientError> {
+        self.imp().disconnect_with_will().await
+    }
+
+    pub async fn publish(&self, message: &MQTTyClientMessage) -> Result<(), MQTTyClientError> {
         self.imp().publish(message).await
     }
 
-    pub async fn subscribe(&self, topic: &str, qos: MQTTyClientQos) -> Result<(), String> {
-        self.imp().subscribe(topic, qos).await
+    /// Returns the SUBACK reason, a rejected filter is an error
+    pub async fn subscribe(
+        &self,
+        topic: &str,
+        options: &SubscribeOptions,
+    ) -> Result<MQTTyReason, MQTTyClientError> {
+        self.imp().subscribe(topic, options).await
+    }
+
+    /// Releases the subscription made with these options, returns the UNSUBACK
+    /// reason, None while other holders of the same filter keep it subscribed
+    pub async fn unsubscribe(
+        &self,
+        topic: &str,
+        options: &SubscribeOptions,
+    ) -> Result<Option<MQTTyReason>, MQTTyClientError> {
+        self.imp().unsubscribe(topic, options).await
     }
 
     pub fn connect_message(
@@ -288,9 +1377,79 @@ impl MQTTyClient {
         )
     }
 
+    pub fn tls_options(&self) -> TlsOptions {
+        self.imp().tls_options.borrow().clone()
+    }
+
     pub fn set_tls_options(&self, options: TlsOptions) {
         self.imp().tls_options.replace(options);
     }
+
+    pub fn connect_connected(&self, cb: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "connected",
+            false,
+            glib::closure_local!(move |o: &Self| cb(o)),
+        )
+    }
+
+    pub fn connect_disconnected(
+        &self,
+        cb: impl Fn(&Self, &str) + 'static,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "disconnected",
+            false,
+            glib::closure_local!(move |o: &Self, reason: &str| cb(o, reason)),
+        )
+    }
+
+    pub fn connect_connection_lost(&self, cb: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "connection-lost",
+            false,
+            glib::closure_local!(move |o: &Self| cb(o)),
+        )
+    }
+
+    pub fn connect_reconnecting(
+        &self,
+        cb: impl Fn(&Self, u32, u32) + 'static,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "reconnecting",
+            false,
+            glib::closure_local!(move |o: &Self, attempt: u32, delay: u32| cb(o, attempt, delay)),
+        )
+    }
+}
+
+// Helper for instantiating the long-lived client of a saved connection profile
+impl From<&MQTTySettingConnection> for MQTTyClient {
+    fn from(value: &MQTTySettingConnection) -> Self {
+        let client: Self = Self::builder(
+            &value.url(),
+            value.mqtt_version(),
+            &value.username(),
+            &value.password(),
+        )
+        .property("client_id", value.client_id())
+        .property("clean_start", value.clean_start())
+        .property("session_expiry_interval", value.session_expiry_interval())
+        .property("keep_alive", value.keep_alive())
+        .property("receive_maximum", value.receive_maximum())
+        .property("maximum_packet_size", value.maximum_packet_size())
+        .property("auto_reconnect", value.auto_reconnect())
+        .property("message_buffer_size", value.message_buffer_size())
+        .property("drop_policy", value.drop_policy())
+        .property("will", value.will())
+        .property("will_delay_interval", value.will_delay_interval())
+        .build();
+
+        client.set_tls_options(value.tls_options());
+
+        client
+    }
 }
 
 /*
@@ -307,6 +1466,39 @@ impl From<&MQTTyClientMessage> for paho::Message {
                 .unwrap();
         }
 
+        if value.message_expiry_interval() > 0 {
+            props
+                .push_int(
+                    paho::PropertyCode::MessageExpiryInterval,
+                    value.message_expiry_interval() as i32,
+                )
+                .unwrap();
+        }
+
+        if let Some(response_topic) = value.response_topic() {
+            props
+                .push_string(paho::PropertyCode::ResponseTopic, &response_topic)
+                .unwrap();
+        }
+
+        if let Some(correlation_data) = value.correlation_data() {
+            props
+                .push_binary(paho::PropertyCode::CorrelationData, correlation_data)
+                .unwrap();
+        }
+
+        if value.payload_format_utf8() {
+            props
+                .push_int(paho::PropertyCode::PayloadFormatIndicator, 1)
+                .unwrap();
+        }
+
+        if value.topic_alias() > 0 {
+            props
+                .push_int(paho::PropertyCode::TopicAlias, value.topic_alias() as i32)
+                .unwrap();
+        }
+
         for (key, value) in value.user_properties().into_iter() {
             props
                 .push_string_pair(paho::PropertyCode::UserProperty, &key, &value)
@@ -325,6 +1517,127 @@ impl From<&MQTTyClientMessage> for paho::Message {
     }
 }
 
+/// Reason code of a broker answer along with the reason string of its properties,
+/// which v3.x answers never carry
+fn reason(code: paho::ReasonCode, props: &paho::Properties) -> MQTTyReason {
+    MQTTyReason {
+        code: code as u8,
+        description: code.to_string(),
+        reason_string: props.get_string(paho::PropertyCode::ReasonString),
+    }
+}
+
+/// Turns a failure reason code of an acknowledgement into an error
+fn ack(reason: MQTTyReason) -> Result<MQTTyReason, MQTTyClientError> {
+    if reason.is_error() {
+        Err(MQTTyClientError::Rejected(reason))
+    } else {
+        Ok(reason)
+    }
+}
+
+fn invalid_options(e: paho::Error) -> MQTTyClientError {
+    MQTTyClientError::InvalidOptions(e.to_string())
+}
+
+fn connection_info(response: &paho::ServerResponse) -> MQTTyConnectionInfo {
+    let props = response.properties();
+
+    let int = |code| props.get_int(code).map(|value| value as u32);
+    let flag = |code| props.get_int(code).map(|value| value != 0);
+
+    let (server_uri, session_present) = response
+        .connect_response()
+        .map(|connack| (connack.server_uri, connack.session_present))
+        .unwrap_or_default();
+
+    MQTTyConnectionInfo {
+        server_uri,
+        session_present,
+        reason: reason(response.reason_code(), props),
+        // Paho keeps the typo of the C library
+        assigned_client_id: props.get_string(paho::PropertyCode::AssignedClientIdentifer),
+        server_keep_alive: int(paho::PropertyCode::ServerKeepAlive),
+        session_expiry_interval: int(paho::PropertyCode::SessionExpiryInterval),
+        receive_maximum: int(paho::PropertyCode::ReceiveMaximum),
+        maximum_packet_size: int(paho::PropertyCode::MaximumPacketSize),
+        topic_alias_maximum: int(paho::PropertyCode::TopicAliasMaximum),
+        maximum_qos: int(paho::PropertyCode::MaximumQos),
+        retain_available: flag(paho::PropertyCode::RetainAvailable),
+        wildcard_subscription_available: flag(paho::PropertyCode::WildcardSubscriptionAvailable),
+        subscription_identifiers_available: flag(
+            paho::PropertyCode::SubscriptionIdentifiersAvailable,
+        ),
+        shared_subscription_available: flag(paho::PropertyCode::SharedSubscriptionAvailable),
+        response_information: props.get_string(paho::PropertyCode::ResponseInformation),
+        server_reference: props.get_string(paho::PropertyCode::ServerReference),
+        user_properties: props.user_iter().collect(),
+    }
+}
+
+impl From<paho::Error> for MQTTyClientError {
+    fn from(value: paho::Error) -> Self {
+        match &value {
+            paho::Error::ReasonCode(code) => Self::Rejected(MQTTyReason {
+                code: *code as u8,
+                description: code.to_string(),
+                reason_string: None,
+            }),
+            // v3.x CONNACK return codes
+            paho::Error::ConnectReturn(code) => {
+                Self::Rejected(MQTTyReason::from_connect_return(*code as u8))
+            }
+            _ => Self::Connection(value.to_string()),
+        }
+    }
+}
+
+/// Converts a message received by paho, the MQTT version cannot be known from the
+/// paho::Message itself, see the note below
+fn received_message(msg: &paho::Message, mqtt_version: MQTTyClientVersion) -> MQTTyClientMessage {
+    let props = msg.properties();
+
+    let out_msg = MQTTyClientMessage::new();
+
+    out_msg.set_topic(msg.topic());
+    out_msg.set_qos(MQTTyClientQos::from(msg.qos()));
+    out_msg.set_body(msg.payload());
+    out_msg.set_mqtt_version(mqtt_version);
+    out_msg.set_retained(msg.retained());
+    out_msg.set_received_at(glib::DateTime::now_local().ok());
+    out_msg.set_content_type(props.get_string(paho::PropertyCode::ContentType));
+    out_msg.set_message_expiry_interval(
+        props
+            .get_int(paho::PropertyCode::MessageExpiryInterval)
+            .unwrap_or_default() as u32,
+    );
+    out_msg.set_response_topic(props.get_string(paho::PropertyCode::ResponseTopic));
+    out_msg.set_correlation_data(
+        props
+            .get_binary(paho::PropertyCode::CorrelationData)
+            .as_deref(),
+    );
+    out_msg.set_payload_format_utf8(
+        props.get_int(paho::PropertyCode::PayloadFormatIndicator) == Some(1),
+    );
+    out_msg.set_topic_alias(
+        props
+            .get_int(paho::PropertyCode::TopicAlias)
+            .unwrap_or_default() as u32,
+    );
+
+    // A message matching several subscriptions carries all of their identifiers
+    let subscription_identifiers = (0..)
+        .map_while(|i| props.get_int_at(paho::PropertyCode::SubscriptionIdentifier, i))
+        .map(|id| id as u32)
+        .collect::<Vec<_>>();
+    out_msg.set_subscription_identifiers(&subscription_identifiers);
+
+    out_msg.set_user_properties(props.user_iter().collect::<Vec<_>>().as_slice());
+
+    out_msg
+}
+
 // // NOTE:
 // //
 // // Cannot implement this trait succesfully for paho crate, since there is no way
@@ -374,6 +1687,17 @@ impl From<paho::MqttVersion> for MQTTyClientVersion {
     }
 }
 
+impl From<MQTTyTlsVersion> for paho::SslVersion {
+    fn from(value: MQTTyTlsVersion) -> Self {
+        match value {
+            MQTTyTlsVersion::Automatic => paho::SslVersion::Default,
+            MQTTyTlsVersion::Tls1_0 => paho::SslVersion::Tls_1_0,
+            MQTTyTlsVersion::Tls1_1 => paho::SslVersion::Tls_1_1,
+            MQTTyTlsVersion::Tls1_2 => paho::SslVersion::Tls_1_2,
+        }
+    }
+}
+
 impl From<MQTTyClientQos> for paho::QoS {
     fn from(value: MQTTyClientQos) -> Self {
         match value {
@@ -384,6 +1708,31 @@ impl From<MQTTyClientQos> for paho::QoS {
     }
 }
 
+impl From<MQTTyDisconnectReason> for paho::ReasonCode {
+    fn from(value: MQTTyDisconnectReason) -> Self {
+        match value {
+            MQTTyDisconnectReason::Normal => paho::ReasonCode::NormalDisconnection,
+            MQTTyDisconnectReason::WithWill => paho::ReasonCode::DisconnectWithWillMessage,
+        }
+    }
+}
+
+impl From<&SubscribeOptions> for paho::SubscribeOptions {
+    fn from(value: &SubscribeOptions) -> Self {
+        let retain_handling = match value.retain_handling {
+            MQTTyRetainHandling::SendOnSubscribe => paho::RetainHandling::SendRetainedOnSubscribe,
+            MQTTyRetainHandling::SendOnNewSubscription => paho::RetainHandling::SendRetainedOnNew,
+            MQTTyRetainHandling::DontSend => paho::RetainHandling::DontSendRetained,
+        };
+
+        paho::SubscribeOptionsBuilder::new()
+            .no_local(value.no_local)
+            .retain_as_published(value.retain_as_published)
+            .retain_handling(retain_handling)
+            .finalize()
+    }
+}
+
 impl From<paho::QoS> for MQTTyClientQos {
     fn from(value: paho::QoS) -> Self {
         match value {
@@ -21,6 +21,10 @@ use gtk::glib;
 
 use crate::client::{MQTTyClientQos, MQTTyClientVersion};
 
+/// Encodings of a body as text, see MQTTyClientMessage::encoded_body()
+pub const PAYLOAD_ENCODING_UTF8: &str = "utf-8";
+pub const PAYLOAD_ENCODING_BASE64: &str = "base64";
+
 mod imp {
 
     use super::*;
@@ -40,11 +44,41 @@ mod imp {
         #[property(get, set)]
         retained: Cell<bool>,
 
+        /// Local time the message arrived at, None for messages not received from a
+        /// broker
+        #[property(get, set, nullable)]
+        received_at: RefCell<Option<glib::DateTime>>,
+
+        /// Member of the share group that received the message, starting at 1, 0
+        /// outside of share groups
+        #[property(get, set)]
+        share_member: Cell<u32>,
+
         #[property(get, set, nullable)]
         content_type: RefCell<Option<String>>,
 
+        /// Seconds until the broker discards the message, 0 for no expiry (MQTT v5 only)
+        #[property(get, set)]
+        message_expiry_interval: Cell<u32>,
+
+        #[property(get, set, nullable)]
+        response_topic: RefCell<Option<String>>,
+
+        /// Whether the body is UTF-8 encoded character data (MQTT v5 only)
+        #[property(get, set)]
+        payload_format_utf8: Cell<bool>,
+
+        /// 0 for no alias (MQTT v5 only)
+        #[property(get, set, maximum = u16::MAX as u32)]
+        topic_alias: Cell<u32>,
+
         pub user_properties: RefCell<Vec<(String, String)>>,
 
+        pub correlation_data: RefCell<Option<Vec<u8>>>,
+
+        /// Identifiers of the subscriptions matching a received message (MQTT v5 only)
+        pub subscription_identifiers: RefCell<Vec<u32>>,
+
         pub body: RefCell<Vec<u8>>,
     }
 
@@ -65,9 +99,8 @@ glib::wrapper! {
     /// This type works as a model, that carries all of the data related to a
     /// publish/subscribed MQTT message
     ///
-    /// Implements serialization and deserialization of MQTT messages
-    ///
-    /// TODO: add serde functions
+    /// Implements serialization and deserialization of MQTT messages, see to_json() and
+    /// from_json()
     pub struct MQTTyClientMessage(ObjectSubclass<imp::MQTTyClientMessage>);
 }
 
@@ -86,6 +119,182 @@ impl MQTTyClientMessage {
         v.extend_from_slice(body);
     }
 
+    /// Approximate memory taken by the message, for capping the amount of kept
+    /// messages
+    pub fn size(&self) -> usize {
+        let imp = self.imp();
+
+        let user_properties = imp
+            .user_properties
+            .borrow()
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum::<usize>();
+
+        let correlation_data = imp.correlation_data.borrow().as_ref().map_or(0, Vec::len);
+
+        std::mem::size_of::<imp::MQTTyClientMessage>()
+            + self.topic().len()
+            + imp.body.borrow().len()
+            + correlation_data
+            + user_properties
+    }
+
+    /// JSON object with every field of the message, the payload is kept as text unless
+    /// it's binary, see encoded_body(), other binary fields are base64 encoded,
+    /// absent optional fields are null
+    pub fn to_json(&self) -> serde_json::Value {
+        let base64 = |data: &[u8]| glib::base64_encode(data).to_string();
+
+        let (payload, payload_encoding) = self.encoded_body();
+
+        serde_json::json!({
+            "received_at": self
+                .received_at()
+                .and_then(|received_at| received_at.format_iso8601().ok())
+                .map(|received_at| received_at.to_string()),
+            "topic": self.topic(),
+            "share_member": self.share_member(),
+            "payload": payload,
+            "payload_encoding": payload_encoding,
+            "qos": match self.qos() {
+                MQTTyClientQos::Qos0 => 0,
+                MQTTyClientQos::Qos1 => 1,
+                MQTTyClientQos::Qos2 => 2,
+            },
+            "retain": self.retained(),
+            "mqtt_version": match self.mqtt_version() {
+                MQTTyClientVersion::V3X => 3,
+                MQTTyClientVersion::V5 => 5,
+            },
+            "content_type": self.content_type(),
+            "message_expiry_interval": self.message_expiry_interval(),
+            "response_topic": self.response_topic(),
+            "correlation_data": self.correlation_data().as_deref().map(base64),
+            "payload_format_utf8": self.payload_format_utf8(),
+            "topic_alias": self.topic_alias(),
+            "subscription_identifiers": self.subscription_identifiers(),
+            "user_properties": self.user_properties(),
+        })
+    }
+
+    /// Counterpart of to_json(), only the topic is required, missing fields take their
+    /// default value. The payload is base64 encoded unless payload_encoding says
+    /// otherwise, as it was always the case before the field was added
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let object = value.as_object()?;
+
+        let string = |key: &str| object.get(key).and_then(|v| v.as_str());
+        let uint = |key: &str| {
+            object
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .and_then(|v| u32::try_from(v).ok())
+        };
+        let boolean = |key: &str| object.get(key).and_then(|v| v.as_bool());
+        let base64 = |key: &str| string(key).map(glib::base64_decode);
+
+        let message = Self::new();
+
+        message.set_topic(string("topic")?);
+        let payload = string("payload").unwrap_or_default();
+        let payload_encoding = string("payload_encoding").unwrap_or(PAYLOAD_ENCODING_BASE64);
+        message.set_body(&Self::decode_body(payload, payload_encoding)?);
+        message.set_qos(match uint("qos") {
+            Some(1) => MQTTyClientQos::Qos1,
+            Some(2) => MQTTyClientQos::Qos2,
+            _ => MQTTyClientQos::Qos0,
+        });
+        message.set_retained(boolean("retain").unwrap_or_default());
+        message.set_mqtt_version(match uint("mqtt_version") {
+            Some(5) => MQTTyClientVersion::V5,
+            _ => MQTTyClientVersion::V3X,
+        });
+        message.set_received_at(
+            string("received_at").and_then(|t| glib::DateTime::from_iso8601(t, None).ok()),
+        );
+        message.set_share_member(uint("share_member").unwrap_or_default());
+        message.set_content_type(string("content_type").map(str::to_string));
+        message.set_message_expiry_interval(uint("message_expiry_interval").unwrap_or_default());
+        message.set_response_topic(string("response_topic").map(str::to_string));
+        message.set_correlation_data(base64("correlation_data").as_deref());
+        message.set_payload_format_utf8(boolean("payload_format_utf8").unwrap_or_default());
+        message.set_topic_alias(uint("topic_alias").unwrap_or_default().min(u16::MAX as u32));
+
+        if let Some(ids) = object
+            .get("subscription_identifiers")
+            .and_then(|v| v.as_array())
+        {
+            let ids = ids
+                .iter()
+                .filter_map(|id| id.as_u64())
+                .filter_map(|id| u32::try_from(id).ok())
+                .collect::<Vec<_>>();
+            message.set_subscription_identifiers(&ids);
+        }
+
+        if let Some(props) = object.get("user_properties").and_then(|v| v.as_array()) {
+            let props = props
+                .iter()
+                .filter_map(|prop| {
+                    let pair = prop.as_array()?;
+                    match pair.as_slice() {
+                        [key, value] => {
+                            Some((key.as_str()?.to_string(), value.as_str()?.to_string()))
+                        }
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            message.set_user_properties(&props);
+        }
+
+        Some(message)
+    }
+
+    /// Body as text along with its encoding, UTF-8 bodies are kept as they are, binary
+    /// ones are base64 encoded
+    pub fn encoded_body(&self) -> (String, &'static str) {
+        let body = self.imp().body.borrow();
+
+        match std::str::from_utf8(&body) {
+            Ok(text) => (text.to_string(), PAYLOAD_ENCODING_UTF8),
+            Err(_) => (
+                glib::base64_encode(&body).to_string(),
+                PAYLOAD_ENCODING_BASE64,
+            ),
+        }
+    }
+
+    /// Counterpart of encoded_body(), None for unknown encodings
+    pub fn decode_body(text: &str, encoding: &str) -> Option<Vec<u8>> {
+        match encoding {
+            PAYLOAD_ENCODING_UTF8 => Some(text.as_bytes().to_vec()),
+            PAYLOAD_ENCODING_BASE64 => Some(glib::base64_decode(text)),
+            _ => None,
+        }
+    }
+
+    pub fn correlation_data(&self) -> Option<Vec<u8>> {
+        self.imp().correlation_data.borrow().clone()
+    }
+
+    pub fn set_correlation_data(&self, correlation_data: Option<&[u8]>) {
+        self.imp()
+            .correlation_data
+            .replace(correlation_data.map(<[u8]>::to_vec));
+    }
+
+    pub fn subscription_identifiers(&self) -> Vec<u32> {
+        self.imp().subscription_identifiers.borrow().clone()
+    }
+
+    pub fn set_subscription_identifiers(&self, subscription_identifiers: &[u32]) {
+        let mut v = self.imp().subscription_identifiers.borrow_mut();
+        v.clear();
+        v.extend_from_slice(subscription_identifiers);
+    }
+
     pub fn user_properties(&self) -> Vec<(String, String)> {
         self.imp().user_properties.borrow().clone()
     }
@@ -96,3 +305,22 @@ impl MQTTyClientMessage {
         v.extend_from_slice(user_properties);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_keeps_the_share_member() {
+        let message = MQTTyClientMessage::new();
+        message.set_topic("sensors/1");
+        message.set_share_member(3);
+
+        let read = MQTTyClientMessage::from_json(&message.to_json()).unwrap();
+        assert_eq!(read.share_member(), 3);
+
+        // Recordings made before the field existed are outside of share groups
+        let read = MQTTyClientMessage::from_json(&serde_json::json!({ "topic": "t" })).unwrap();
+        assert_eq!(read.share_member(), 0);
+    }
+}
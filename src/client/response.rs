@@ -0,0 +1,255 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::glib;
+
+/// Reason code of a broker answer (CONNACK, SUBACK, UNSUBACK or DISCONNECT), along
+/// with the reason string the broker may attach to it (MQTT v5 only)
+///
+/// MQTT v3.x answers are mapped to the equivalent code, e.g. the granted QoS of a SUBACK
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MQTTyReason {
+    pub code: u8,
+    /// Name of the code, e.g. "Not authorized"
+    pub description: String,
+    pub reason_string: Option<String>,
+}
+
+impl MQTTyReason {
+    /// Reason of a refused MQTT v3.x connection, the CONNACK return code is mapped to
+    /// the MQTT v5 reason code of the same meaning
+    pub fn from_connect_return(code: u8) -> Self {
+        let (code, description) = match code {
+            1 => (0x84, gettext("Unsupported protocol version")),
+            2 => (0x85, gettext("Client identifier not valid")),
+            3 => (0x88, gettext("Server unavailable")),
+            4 => (0x86, gettext("Bad user name or password")),
+            5 => (0x87, gettext("Not authorized")),
+            _ => (0x80, gettext("Unspecified error")),
+        };
+
+        Self {
+            code,
+            description,
+            reason_string: None,
+        }
+    }
+
+    /// Codes from 0x80 upwards are failures
+    pub fn is_error(&self) -> bool {
+        self.code >= 0x80
+    }
+}
+
+impl fmt::Display for MQTTyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (0x{:02X})", self.description, self.code)?;
+
+        if let Some(reason_string) = &self.reason_string {
+            write!(f, ": {reason_string}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error of a client operation, the ones coming from the broker keep its reason
+#[derive(Clone, Debug, PartialEq)]
+pub enum MQTTyClientError {
+    /// The broker answered with a failure reason code, or refused a v3.x connection
+    Rejected(MQTTyReason),
+    /// The operation needs MQTT v5
+    RequiresV5(String),
+    /// The client options can't be used, e.g. an unreadable certificate
+    InvalidOptions(String),
+    /// The broker couldn't be reached, e.g. the client is not connected
+    Connection(String),
+}
+
+impl MQTTyClientError {
+    pub fn reason(&self) -> Option<&MQTTyReason> {
+        match self {
+            Self::Rejected(reason) => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MQTTyClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected(reason) => write!(f, "{}", reason),
+            Self::RequiresV5(e) | Self::InvalidOptions(e) | Self::Connection(e) => {
+                write!(f, "{}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MQTTyClientError {}
+
+/// What the broker told about the connection in its CONNACK
+///
+/// Everything but `session_present` and `reason` is only sent by MQTT v5 brokers,
+/// None means the broker left the property out, so the spec default applies
+#[derive(Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "MQTTyConnectionInfo", nullable)]
+pub struct MQTTyConnectionInfo {
+    pub server_uri: String,
+    /// Whether the broker resumed a previous session of this client ID
+    pub session_present: bool,
+    pub reason: MQTTyReason,
+    /// Client ID given by the broker, when it was left empty in the CONNECT
+    pub assigned_client_id: Option<String>,
+    /// Keep alive imposed by the broker, overrides the requested one
+    pub server_keep_alive: Option<u32>,
+    pub session_expiry_interval: Option<u32>,
+    pub receive_maximum: Option<u32>,
+    pub maximum_packet_size: Option<u32>,
+    pub topic_alias_maximum: Option<u32>,
+    /// Highest QoS accepted by the broker, 2 if absent
+    pub maximum_qos: Option<u32>,
+    pub retain_available: Option<bool>,
+    pub wildcard_subscription_available: Option<bool>,
+    pub subscription_identifiers_available: Option<bool>,
+    pub shared_subscription_available: Option<bool>,
+    pub response_information: Option<String>,
+    /// Another broker the client should use, sent along with a redirection
+    pub server_reference: Option<String>,
+    pub user_properties: Vec<(String, String)>,
+}
+
+impl MQTTyConnectionInfo {
+    /// Parameters the broker imposed on the client, worth telling the user right after
+    /// connecting
+    pub fn notices(&self) -> Vec<String> {
+        let mut notices = Vec::new();
+
+        if let Some(client_id) = &self.assigned_client_id {
+            notices.push(
+                formatx!(gettext("The broker assigned the client ID {}"), client_id).unwrap(),
+            );
+        }
+
+        if let Some(keep_alive) = self.server_keep_alive {
+            notices.push(
+                formatx!(
+                    gettext("The broker set the keep alive to {} seconds"),
+                    keep_alive
+                )
+                .unwrap(),
+            );
+        }
+
+        if let Some(maximum_qos) = self.maximum_qos {
+            notices.push(
+                formatx!(gettext("The broker only accepts up to QoS {}"), maximum_qos).unwrap(),
+            );
+        }
+
+        if self.retain_available == Some(false) {
+            notices.push(gettext("The broker doesn't support retained messages"));
+        }
+
+        if let Some(server_reference) = &self.server_reference {
+            notices
+                .push(formatx!(gettext("The broker suggests using {}"), server_reference).unwrap());
+        }
+
+        notices
+    }
+
+    /// Label and value of every field, absent ones show their default, meant for
+    /// displaying the info as rows
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let yes_no = |value: bool| if value { gettext("Yes") } else { gettext("No") };
+
+        let available = |value: Option<bool>| yes_no(value.unwrap_or(true));
+
+        let optional =
+            |value: Option<u32>, default: String| value.map(|v| v.to_string()).unwrap_or(default);
+
+        let mut rows = vec![
+            (gettext("Server"), self.server_uri.clone()),
+            (gettext("Reason"), self.reason.to_string()),
+            (gettext("Session Present"), yes_no(self.session_present)),
+            (
+                gettext("Assigned Client ID"),
+                self.assigned_client_id
+                    .clone()
+                    .unwrap_or_else(|| gettext("None")),
+            ),
+            (
+                gettext("Server Keep Alive"),
+                optional(self.server_keep_alive, gettext("As requested")),
+            ),
+            (
+                gettext("Session Expiry Interval"),
+                optional(self.session_expiry_interval, gettext("As requested")),
+            ),
+            (
+                gettext("Receive Maximum"),
+                optional(self.receive_maximum, String::from("65535")),
+            ),
+            (
+                gettext("Maximum Packet Size"),
+                optional(self.maximum_packet_size, gettext("Unlimited")),
+            ),
+            (
+                gettext("Topic Alias Maximum"),
+                optional(self.topic_alias_maximum, String::from("0")),
+            ),
+            (
+                gettext("Maximum QoS"),
+                optional(self.maximum_qos, String::from("2")),
+            ),
+            (
+                gettext("Retain Available"),
+                available(self.retain_available),
+            ),
+            (
+                gettext("Wildcard Subscriptions"),
+                available(self.wildcard_subscription_available),
+            ),
+            (
+                gettext("Subscription Identifiers"),
+                available(self.subscription_identifiers_available),
+            ),
+            (
+                gettext("Shared Subscriptions"),
+                available(self.shared_subscription_available),
+            ),
+        ];
+
+        if let Some(response_information) = &self.response_information {
+            rows.push((
+                gettext("Response Information"),
+                response_information.clone(),
+            ));
+        }
+
+        if let Some(server_reference) = &self.server_reference {
+            rows.push((gettext("Server Reference"), server_reference.clone()));
+        }
+
+        rows.extend(self.user_properties.iter().cloned());
+
+        rows
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::io;
+
+/// Inodes of the sockets held by the process
+pub fn sockets() -> HashSet<u64> {
+    imp::sockets().into_keys().collect()
+}
+
+/// Inode of the TCP socket to the given peer port opened since the snapshot was taken,
+/// any port if None. None if there isn't exactly one, e.g. when another client
+/// connected to the same port meanwhile
+pub fn opened_since(before: &HashSet<u64>, port: Option<u16>) -> Option<u64> {
+    let mut opened = imp::sockets()
+        .into_iter()
+        .filter(|(inode, fd)| {
+            !before.contains(inode)
+                && imp::peer_port(*fd).is_some_and(|p| port.is_none_or(|port| p == port))
+        })
+        .map(|(inode, _)| inode);
+
+    match (opened.next(), opened.next()) {
+        (Some(inode), None) => Some(inode),
+        _ => None,
+    }
+}
+
+/// Shuts the socket down in both directions, without sending anything
+///
+/// Paho has no way to close a connection without a DISCONNECT packet, nor does it
+/// tell which socket a connection uses, so the socket is shut down from under it,
+/// and paho finds the connection lost the next time it reads from it
+pub fn shutdown(inode: u64) -> io::Result<()> {
+    let fd = imp::sockets()
+        .get(&inode)
+        .copied()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+
+    imp::shutdown(fd)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::collections::HashMap;
+    use std::io;
+    use std::mem::ManuallyDrop;
+    use std::net::{Shutdown, TcpStream};
+    use std::os::fd::{FromRawFd, RawFd};
+
+    /// Descriptors of the sockets, by inode, as listed in /proc/self/fd
+    pub fn sockets() -> HashMap<u64, RawFd> {
+        let Ok(entries) = std::fs::read_dir("/proc/self/fd") else {
+            return HashMap::new();
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let fd = entry.file_name().to_str()?.parse::<RawFd>().ok()?;
+                let target = std::fs::read_link(entry.path()).ok()?;
+                let inode = target
+                    .to_str()?
+                    .strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .parse::<u64>()
+                    .ok()?;
+
+                Some((inode, fd))
+            })
+            .collect()
+    }
+
+    /// Borrows the descriptor as a TcpStream, it must not be closed since paho owns it
+    fn borrow_stream(fd: RawFd) -> ManuallyDrop<TcpStream> {
+        // SAFETY: the descriptor is open, and ManuallyDrop keeps it from being closed
+        ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(fd) })
+    }
+
+    /// Port of the TCP peer, None for other sockets, e.g. the D-Bus one
+    pub fn peer_port(fd: RawFd) -> Option<u16> {
+        borrow_stream(fd).peer_addr().ok().map(|addr| addr.port())
+    }
+
+    pub fn shutdown(fd: RawFd) -> io::Result<()> {
+        borrow_stream(fd).shutdown(Shutdown::Both)
+    }
+}
+
+/// There is no /proc to look the sockets up outside of Linux
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::collections::HashMap;
+    use std::io;
+
+    pub fn sockets() -> HashMap<u64, i32> {
+        HashMap::new()
+    }
+
+    pub fn peer_port(_fd: i32) -> Option<u16> {
+        None
+    }
+
+    pub fn shutdown(_fd: i32) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
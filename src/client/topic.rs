@@ -0,0 +1,107 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+/// Splits a shared subscription filter (`$share/group/filter`, MQTT v5) into the group
+/// name and the filter, None for regular filters
+pub fn shared_subscription(filter: &str) -> Option<(&str, &str)> {
+    filter
+        .strip_prefix("$share/")?
+        .split_once('/')
+        .filter(|(group, filter)| !group.is_empty() && !filter.is_empty())
+}
+
+/// Checks whether `topic` is matched by the subscription `filter`, following the MQTT
+/// wildcard rules: `+` matches exactly one level and `#` matches any number of levels,
+/// including the parent level.
+///
+/// Topics starting with `$` (e.g. `$SYS/...`) are not matched by filters starting with
+/// a wildcard, as mandated by the spec.
+///
+/// Shared subscription filters are matched by the filter after the group name.
+pub fn topic_matches_filter(filter: &str, topic: &str) -> bool {
+    let filter = shared_subscription(filter).map_or(filter, |(_, filter)| filter);
+
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(f), Some(t)) if f == t => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_level_wildcard() {
+        assert!(topic_matches_filter("#", "a"));
+        assert!(topic_matches_filter("#", "a/b/c"));
+        assert!(topic_matches_filter("#", "/a"));
+        assert!(topic_matches_filter("a/#", "a"));
+        assert!(topic_matches_filter("a/#", "a/b/c"));
+        assert!(!topic_matches_filter("a/#", "b/a"));
+    }
+
+    #[test]
+    fn single_level_wildcard_on_empty_levels() {
+        assert!(topic_matches_filter("+/+", "/"));
+        assert!(topic_matches_filter("a/+", "a/"));
+        assert!(topic_matches_filter("+/a", "/a"));
+        assert!(topic_matches_filter("a/+/c", "a//c"));
+        assert!(!topic_matches_filter("a/+", "a"));
+        assert!(!topic_matches_filter("a/+", "a/b/c"));
+    }
+
+    #[test]
+    fn dollar_topics_not_matched_by_leading_wildcards() {
+        assert!(!topic_matches_filter("#", "$SYS/broker/uptime"));
+        assert!(!topic_matches_filter(
+            "+/broker/uptime",
+            "$SYS/broker/uptime"
+        ));
+        assert!(topic_matches_filter("$SYS/#", "$SYS/broker/uptime"));
+        assert!(topic_matches_filter("$SYS/+/uptime", "$SYS/broker/uptime"));
+    }
+
+    #[test]
+    fn shared_subscription_filters() {
+        assert_eq!(
+            shared_subscription("$share/group/a/b"),
+            Some(("group", "a/b"))
+        );
+        assert_eq!(shared_subscription("$share/group/#"), Some(("group", "#")));
+        assert_eq!(shared_subscription("$share//a"), None);
+        assert_eq!(shared_subscription("$share/group/"), None);
+        assert_eq!(shared_subscription("$share/group"), None);
+        assert_eq!(shared_subscription("a/b"), None);
+
+        assert!(topic_matches_filter("$share/group/a/+", "a/b"));
+        assert!(!topic_matches_filter(
+            "$share/group/a/+",
+            "$share/group/a/b"
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::client::{MQTTyClientMessage, MQTTyClientQos};
+
+/// File formats messages can be exported to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MQTTyExportFormat {
+    /// timestamp, topic, qos, retain, payload and payload_encoding columns
+    Csv,
+    /// One MQTTyClientMessage::to_json() object per line, the same as recordings
+    JsonLines,
+}
+
+impl MQTTyExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Self::Csv),
+            "jsonl" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+        }
+    }
+
+    pub fn file_filter(&self) -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.add_suffix(self.extension());
+        filter.set_name(Some(&match self {
+            Self::Csv => gettext("CSV files"),
+            Self::JsonLines => gettext("JSON Lines files"),
+        }));
+        filter
+    }
+
+    /// Messages in the given order, one per line
+    pub fn format(&self, messages: &[MQTTyClientMessage]) -> String {
+        match self {
+            Self::Csv => messages_to_csv(messages),
+            Self::JsonLines => messages
+                .iter()
+                .map(|message| message.to_json().to_string() + "\n")
+                .collect(),
+        }
+    }
+}
+
+fn messages_to_csv(messages: &[MQTTyClientMessage]) -> String {
+    let mut csv = String::from("timestamp,topic,qos,retain,payload,payload_encoding\n");
+
+    for message in messages {
+        let timestamp = message
+            .received_at()
+            .and_then(|received_at| received_at.format_iso8601().ok())
+            .map(|received_at| received_at.to_string())
+            .unwrap_or_default();
+
+        let qos = match message.qos() {
+            MQTTyClientQos::Qos0 => "0",
+            MQTTyClientQos::Qos1 => "1",
+            MQTTyClientQos::Qos2 => "2",
+        };
+
+        let topic = message.topic();
+        let (payload, payload_encoding) = message.encoded_body();
+
+        let row = [
+            timestamp.as_str(),
+            topic.as_str(),
+            qos,
+            if message.retained() { "true" } else { "false" },
+            payload.as_str(),
+            payload_encoding,
+        ]
+        .map(csv_field)
+        .join(",");
+
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Quotes the field if it has separators, quotes or line breaks, as in RFC 4180
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Asks where to save the contents and writes them, returns the file written, None if
+/// the user dismissed the dialog
+pub async fn save_to_file(
+    parent: &impl IsA<gtk::Widget>,
+    title: &str,
+    initial_name: &str,
+    filter: Option<&gtk::FileFilter>,
+    contents: Vec<u8>,
+) -> Result<Option<gio::File>, glib::Error> {
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    if let Some(filter) = filter {
+        filters.append(filter);
+    }
+
+    let dialog = gtk::FileDialog::builder()
+        .title(title)
+        .initial_name(initial_name)
+        .filters(&filters)
+        .modal(true)
+        .build();
+
+    let window = parent.root().and_downcast::<gtk::Window>();
+
+    let Ok(file) = dialog.save_future(window.as_ref()).await else {
+        return Ok(None);
+    };
+
+    file.replace_contents_future(
+        contents,
+        None,
+        false,
+        gio::FileCreateFlags::REPLACE_DESTINATION,
+    )
+    .await
+    .map_err(|(_, e)| e)?;
+
+    Ok(Some(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field(""), "");
+        assert!(matches!(csv_field("plain"), Cow::Borrowed(_)));
+
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("line\r\nbreak"), "\"line\r\nbreak\"");
+    }
+}
@@ -13,13 +13,19 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use adw::subclass::prelude::*;
 use gtk::glib;
 use gtk::glib::variant::{FromVariant, StaticVariantType};
 use gtk::prelude::*;
 
+use crate::client::{
+    non_empty, MQTTyClientMessage, MQTTyClientQos, MQTTyClientVersion, MQTTyDropPolicy,
+    MQTTyTlsVersion, TlsOptions,
+};
+use crate::secrets::{self, MQTTySecretKind};
+
 mod imp {
 
     use super::*;
@@ -27,6 +33,10 @@ mod imp {
     #[derive(Default, glib::Properties)]
     #[properties(wrapper_type = super::MQTTySettingConnection)]
     pub struct MQTTySettingConnection {
+        /// Stable identifier, secrets of the profile are stored in the keyring under it
+        #[property(get, set)]
+        id: RefCell<String>,
+
         #[property(get, set)]
         name: RefCell<String>,
 
@@ -42,11 +52,119 @@ mod imp {
         #[property(get, set)]
         password: RefCell<String>,
 
+        /// Whether the password and the key passphrase are kept in the Secret Service,
+        /// they are only saved in plain text in GSettings as a fallback, when no keyring
+        /// is available
+        #[property(get)]
+        pub secrets_in_keyring: Cell<bool>,
+
+        #[property(get, set, builder(MQTTyClientVersion::V3X))]
+        mqtt_version: Cell<MQTTyClientVersion>,
+
+        #[property(get, set, builder(MQTTyClientQos::Qos0))]
+        qos: Cell<MQTTyClientQos>,
+
+        /// Empty to let the client library (or the broker in MQTT v5) pick one
+        #[property(get, set)]
+        client_id: RefCell<String>,
+
+        #[property(get, set)]
+        clean_start: Cell<bool>,
+
+        /// Seconds, MQTT v5 only
+        #[property(get, set)]
+        session_expiry_interval: Cell<u32>,
+
+        /// Seconds, 0 disables keep alive
+        #[property(get, set, maximum = u16::MAX as u32)]
+        keep_alive: Cell<u32>,
+
+        /// MQTT v5 only, 0 leaves it to the broker default
+        #[property(get, set, maximum = u16::MAX as u32)]
+        receive_maximum: Cell<u32>,
+
+        /// Bytes, MQTT v5 only, 0 means no limit
+        #[property(get, set)]
+        maximum_packet_size: Cell<u32>,
+
+        #[property(get, set)]
+        auto_reconnect: Cell<bool>,
+
+        /// Received messages waiting to be shown, beyond this drop_policy applies
+        #[property(get, set, minimum = 1)]
+        message_buffer_size: Cell<u32>,
+
+        #[property(get, set, builder(MQTTyDropPolicy::Block))]
+        drop_policy: Cell<MQTTyDropPolicy>,
+
+        /// Last Will and Testament, an empty topic disables it
+        #[property(get, set)]
+        will_topic: RefCell<String>,
+
+        #[property(get, set)]
+        will_payload: RefCell<String>,
+
+        #[property(get, set, builder(MQTTyClientQos::Qos0))]
+        will_qos: Cell<MQTTyClientQos>,
+
         #[property(get, set)]
-        mqtt_version: RefCell<String>,
+        will_retain: Cell<bool>,
 
+        /// Seconds, MQTT v5 only
         #[property(get, set)]
-        qos: RefCell<String>,
+        will_delay_interval: Cell<u32>,
+
+        /// MQTT v5 only, empty to leave it out
+        #[property(get, set)]
+        will_content_type: RefCell<String>,
+
+        #[property(get, set)]
+        enable_tls: Cell<bool>,
+
+        #[property(get, set)]
+        ca_cert_path: RefCell<String>,
+
+        #[property(get, set)]
+        client_cert_path: RefCell<String>,
+
+        #[property(get, set)]
+        client_key_path: RefCell<String>,
+
+        #[property(get, set)]
+        key_passphrase: RefCell<String>,
+
+        #[property(get, set)]
+        verify_server: Cell<bool>,
+
+        #[property(get, set)]
+        verify_hostname: Cell<bool>,
+
+        /// Comma separated ALPN protocols
+        #[property(get, set)]
+        alpn: RefCell<String>,
+
+        #[property(get, set, builder(MQTTyTlsVersion::Automatic))]
+        tls_version: Cell<MQTTyTlsVersion>,
+
+        #[property(get, set)]
+        ciphers: RefCell<String>,
+
+        #[property(get, set)]
+        ws_path: RefCell<String>,
+
+        /// Semicolon separated "Name: value" pairs
+        #[property(get, set)]
+        http_headers: RefCell<String>,
+
+        #[property(get, set)]
+        http_proxy: RefCell<String>,
+
+        #[property(get, set)]
+        https_proxy: RefCell<String>,
+
+        /// Dictionary this profile was read from, fields unknown to this version of the
+        /// app are kept in it and written back untouched
+        pub stored: RefCell<Option<glib::Variant>>,
     }
 
     #[glib::object_subclass]
@@ -73,10 +191,11 @@ impl MQTTySettingConnection {
         topic: &str,
         username: &str,
         password: &str,
-        mqtt_version: &str,
-        qos: &str,
+        mqtt_version: MQTTyClientVersion,
+        qos: MQTTyClientQos,
     ) -> Self {
-        glib::Object::builder()
+        let conn: Self = glib::Object::builder()
+            .property("id", glib::uuid_string_random())
             .property("name", name)
             .property("url", url)
             .property("topic", topic)
@@ -84,77 +203,533 @@ impl MQTTySettingConnection {
             .property("password", password)
             .property("mqtt_version", mqtt_version)
             .property("qos", qos)
-            .build()
+            .property("clean_start", true)
+            .property("keep_alive", 60u32)
+            .property("auto_reconnect", true)
+            .property("message_buffer_size", 1000u32)
+            .property("verify_server", true)
+            .property("verify_hostname", true)
+            .build();
+
+        // Optimistic, so that secrets never reach GSettings when the keyring works
+        conn.imp().secrets_in_keyring.set(true);
+
+        conn
     }
 
     /// Create a new connection profile with just URL and topic (for backwards compatibility)
     pub fn new_simple(url: &str, topic: &str) -> Self {
-        Self::new("", url, topic, "", "", "3", "0")
+        Self::new(
+            "",
+            url,
+            topic,
+            "",
+            "",
+            MQTTyClientVersion::V3X,
+            MQTTyClientQos::Qos0,
+        )
+    }
+
+    /// Reads a profile saved before PROFILE_VERSION 1, when the "connections" key held a
+    /// positional tuple of strings, the options it didn't hold keep their defaults
+    pub fn from_legacy(connection: &glib::Variant) -> Option<Self> {
+        let Some(tuple) = connection.get::<LegacyConnectionTuple>() else {
+            tracing::error!(
+                "Could not convert legacy connection with format '{}', expected '{}'",
+                connection.type_(),
+                LegacyConnectionTuple::static_variant_type()
+            );
+            return None;
+        };
+
+        let conn = Self::default();
+
+        conn.set_name(tuple.0);
+        conn.set_url(tuple.1);
+        conn.set_topic(tuple.2);
+        conn.set_username(tuple.3);
+        conn.set_password(tuple.4);
+        conn.set_mqtt_version(match tuple.5.as_str() {
+            "5" => MQTTyClientVersion::V5,
+            _ => MQTTyClientVersion::V3X,
+        });
+        conn.set_qos(qos_from_u32(tuple.6.parse().unwrap_or_default()));
+
+        // The password is still in plain text, it must be saved with the profile until
+        // load_secrets() moves it to the keyring, or it is lost without a keyring
+        conn.imp().secrets_in_keyring.set(false);
+
+        Some(conn)
+    }
+
+    /// Sets the fields present in the dictionary, missing or mistyped entries keep their
+    /// current value
+    fn load(&self, dict: &glib::VariantDict) {
+        fn lookup<T: FromVariant>(dict: &glib::VariantDict, key: &str) -> Option<T> {
+            dict.lookup::<T>(key)
+                .inspect_err(|e| tracing::warn!("Ignoring profile field '{}': {}", key, e))
+                .ok()
+                .flatten()
+        }
+
+        if let Some(v) = lookup(dict, "id") {
+            self.set_id(v);
+        }
+        if let Some(v) = lookup(dict, "name") {
+            self.set_name(v);
+        }
+        if let Some(v) = lookup(dict, "url") {
+            self.set_url(v);
+        }
+        if let Some(v) = lookup(dict, "topic") {
+            self.set_topic(v);
+        }
+        if let Some(v) = lookup(dict, "username") {
+            self.set_username(v);
+        }
+        if let Some(v) = lookup(dict, "password") {
+            self.set_password(v);
+        }
+        if let Some(v) = lookup::<u32>(dict, "mqtt-version") {
+            self.set_mqtt_version(match v {
+                5 => MQTTyClientVersion::V5,
+                _ => MQTTyClientVersion::V3X,
+            });
+        }
+        if let Some(v) = lookup(dict, "qos") {
+            self.set_qos(qos_from_u32(v));
+        }
+        if let Some(v) = lookup(dict, "client-id") {
+            self.set_client_id(v);
+        }
+        if let Some(v) = lookup(dict, "clean-start") {
+            self.set_clean_start(v);
+        }
+        if let Some(v) = lookup(dict, "session-expiry-interval") {
+            self.set_session_expiry_interval(v);
+        }
+        if let Some(v) = lookup::<u32>(dict, "keep-alive") {
+            self.set_keep_alive(v.min(u16::MAX.into()));
+        }
+        if let Some(v) = lookup::<u32>(dict, "receive-maximum") {
+            self.set_receive_maximum(v.min(u16::MAX.into()));
+        }
+        if let Some(v) = lookup(dict, "maximum-packet-size") {
+            self.set_maximum_packet_size(v);
+        }
+        if let Some(v) = lookup(dict, "auto-reconnect") {
+            self.set_auto_reconnect(v);
+        }
+        if let Some(v) = lookup::<u32>(dict, "message-buffer-size") {
+            self.set_message_buffer_size(v.max(1));
+        }
+        if let Some(v) = lookup::<u32>(dict, "message-drop-policy") {
+            self.set_drop_policy(MQTTyDropPolicy::from(v));
+        }
+        if let Some(v) = lookup(dict, "will-topic") {
+            self.set_will_topic(v);
+        }
+        if let Some(v) = lookup(dict, "will-payload") {
+            self.set_will_payload(v);
+        }
+        if let Some(v) = lookup(dict, "will-qos") {
+            self.set_will_qos(qos_from_u32(v));
+        }
+        if let Some(v) = lookup(dict, "will-retain") {
+            self.set_will_retain(v);
+        }
+        if let Some(v) = lookup(dict, "will-delay-interval") {
+            self.set_will_delay_interval(v);
+        }
+        if let Some(v) = lookup(dict, "will-content-type") {
+            self.set_will_content_type(v);
+        }
+        if let Some(v) = lookup(dict, "tls-enabled") {
+            self.set_enable_tls(v);
+        }
+        if let Some(v) = lookup(dict, "tls-ca-cert") {
+            self.set_ca_cert_path(v);
+        }
+        if let Some(v) = lookup(dict, "tls-client-cert") {
+            self.set_client_cert_path(v);
+        }
+        if let Some(v) = lookup(dict, "tls-client-key") {
+            self.set_client_key_path(v);
+        }
+        if let Some(v) = lookup(dict, "tls-key-passphrase") {
+            self.set_key_passphrase(v);
+        }
+        if let Some(v) = lookup(dict, "tls-verify-server") {
+            self.set_verify_server(v);
+        }
+        if let Some(v) = lookup(dict, "tls-verify-hostname") {
+            self.set_verify_hostname(v);
+        }
+        if let Some(v) = lookup::<Vec<String>>(dict, "tls-alpn") {
+            self.set_alpn(v.join(", "));
+        }
+        if let Some(v) = lookup::<u32>(dict, "tls-version") {
+            self.set_tls_version(MQTTyTlsVersion::from(v));
+        }
+        if let Some(v) = lookup(dict, "tls-ciphers") {
+            self.set_ciphers(v);
+        }
+        if let Some(v) = lookup(dict, "ws-path") {
+            self.set_ws_path(v);
+        }
+        if let Some(v) = lookup::<Vec<(String, String)>>(dict, "ws-headers") {
+            self.set_http_headers(TlsOptions::format_http_headers(&v));
+        }
+        if let Some(v) = lookup(dict, "http-proxy") {
+            self.set_http_proxy(v);
+        }
+        if let Some(v) = lookup(dict, "https-proxy") {
+            self.set_https_proxy(v);
+        }
+    }
+
+    /// Counterpart of load(), known fields are written over the dictionary. A profile
+    /// saved by a newer version of the app keeps its version, so that it isn't taken
+    /// for one of this format once its unknown fields are written back
+    fn store(&self, dict: &glib::VariantDict) {
+        let version = dict.lookup::<u32>("version").ok().flatten().unwrap_or(1);
+        dict.insert("version", version.max(PROFILE_VERSION));
+        dict.insert("id", self.id());
+        dict.insert("name", self.name());
+        dict.insert("url", self.url());
+        dict.insert("topic", self.topic());
+        dict.insert("username", self.username());
+        dict.insert(
+            "mqtt-version",
+            match self.mqtt_version() {
+                MQTTyClientVersion::V3X => 3u32,
+                MQTTyClientVersion::V5 => 5u32,
+            },
+        );
+        dict.insert("qos", qos_to_u32(self.qos()));
+        dict.insert("client-id", self.client_id());
+        dict.insert("clean-start", self.clean_start());
+        dict.insert("session-expiry-interval", self.session_expiry_interval());
+        dict.insert("keep-alive", self.keep_alive());
+        dict.insert("receive-maximum", self.receive_maximum());
+        dict.insert("maximum-packet-size", self.maximum_packet_size());
+        dict.insert("auto-reconnect", self.auto_reconnect());
+        dict.insert("message-buffer-size", self.message_buffer_size());
+        dict.insert("message-drop-policy", self.drop_policy() as u32);
+        dict.insert("will-topic", self.will_topic());
+        dict.insert("will-payload", self.will_payload());
+        dict.insert("will-qos", qos_to_u32(self.will_qos()));
+        dict.insert("will-retain", self.will_retain());
+        dict.insert("will-delay-interval", self.will_delay_interval());
+        dict.insert("will-content-type", self.will_content_type());
+        dict.insert("tls-enabled", self.enable_tls());
+        dict.insert("tls-ca-cert", self.ca_cert_path());
+        dict.insert("tls-client-cert", self.client_cert_path());
+        dict.insert("tls-client-key", self.client_key_path());
+        dict.insert("tls-verify-server", self.verify_server());
+        dict.insert("tls-verify-hostname", self.verify_hostname());
+        dict.insert("tls-alpn", TlsOptions::parse_alpn(&self.alpn()));
+        dict.insert("tls-version", self.tls_version() as u32);
+        dict.insert("tls-ciphers", self.ciphers());
+        dict.insert("ws-path", self.ws_path());
+        dict.insert(
+            "ws-headers",
+            TlsOptions::parse_http_headers(&self.http_headers()),
+        );
+        dict.insert("http-proxy", self.http_proxy());
+        dict.insert("https-proxy", self.https_proxy());
+
+        for (key, secret) in [
+            ("password", self.password()),
+            ("tls-key-passphrase", self.key_passphrase()),
+        ] {
+            if self.secrets_in_keyring() {
+                dict.remove(key);
+            } else {
+                dict.insert(key, secret);
+            }
+        }
+    }
+
+    /// TLS and WebSocket options of the profile client
+    pub fn tls_options(&self) -> TlsOptions {
+        let websocket = TlsOptions {
+            ws_path: non_empty(self.ws_path()),
+            http_headers: TlsOptions::parse_http_headers(&self.http_headers()),
+            http_proxy: non_empty(self.http_proxy()),
+            https_proxy: non_empty(self.https_proxy()),
+            ..Default::default()
+        };
+
+        if !self.enable_tls() {
+            return websocket;
+        }
+
+        TlsOptions {
+            enabled: true,
+            ca_cert_path: non_empty(self.ca_cert_path()),
+            client_cert_path: non_empty(self.client_cert_path()),
+            client_key_path: non_empty(self.client_key_path()),
+            key_passphrase: non_empty(self.key_passphrase()),
+            verify_server: self.verify_server(),
+            verify_hostname: self.verify_hostname(),
+            alpn: TlsOptions::parse_alpn(&self.alpn()),
+            tls_version: self.tls_version(),
+            ciphers: non_empty(self.ciphers()),
+            ..websocket
+        }
+    }
+
+    /// Secrets of the profile along with their current value
+    fn secrets(&self) -> [(MQTTySecretKind, String); 2] {
+        [
+            (MQTTySecretKind::Password, self.password()),
+            (MQTTySecretKind::KeyPassphrase, self.key_passphrase()),
+        ]
+    }
+
+    fn set_secret(&self, kind: MQTTySecretKind, secret: String) {
+        match kind {
+            MQTTySecretKind::Password => self.set_password(secret),
+            MQTTySecretKind::KeyPassphrase => self.set_key_passphrase(secret),
+        }
+    }
+
+    /// Fills the secrets from the keyring, secrets already loaded were stored in plain
+    /// text (by an older version or as a fallback), and they are moved to the keyring
+    /// instead
+    ///
+    /// Returns whether the profile has to be saved again
+    pub async fn load_secrets(&self) -> bool {
+        if self.secrets().iter().any(|(_, secret)| !secret.is_empty()) {
+            self.imp().secrets_in_keyring.set(false);
+
+            return self.save_secrets().await;
+        }
+
+        for (kind, _) in self.secrets() {
+            match secrets::lookup_secret(&self.id(), kind).await {
+                Ok(Some(secret)) => self.set_secret(kind, secret),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read secrets of '{}': {}", self.name(), e),
+            }
+        }
+
+        false
+    }
+
+    /// Stores the secrets in the keyring, falling back to GSettings if it fails
+    ///
+    /// Returns whether the profile has to be saved again
+    pub async fn save_secrets(&self) -> bool {
+        let mut ret = Ok(());
+
+        for (kind, secret) in self.secrets() {
+            let r = if secret.is_empty() {
+                secrets::delete_secret(&self.id(), kind).await
+            } else {
+                secrets::store_secret(&self.id(), &self.name(), kind, &secret).await
+            };
+
+            ret = ret.and(r);
+        }
+
+        let in_keyring = match ret {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!(
+                    "Keyring not available, the secrets of '{}' are stored in plain text: {}",
+                    self.name(),
+                    e
+                );
+                false
+            }
+        };
+
+        self.imp().secrets_in_keyring.replace(in_keyring) != in_keyring
+    }
+
+    /// Removes the secrets of a deleted profile from the keyring
+    pub async fn delete_secrets(&self) {
+        for (kind, _) in self.secrets() {
+            if let Err(e) = secrets::delete_secret(&self.id(), kind).await {
+                tracing::warn!("Failed to delete secrets of '{}': {}", self.name(), e);
+            }
+        }
+    }
+
+    /// Will message built from the will_* fields, None if the will is disabled
+    pub fn will(&self) -> Option<MQTTyClientMessage> {
+        if self.will_topic().is_empty() {
+            return None;
+        }
+
+        let will = MQTTyClientMessage::new();
+
+        will.set_topic(self.will_topic());
+        will.set_body(self.will_payload().as_bytes());
+        will.set_retained(self.will_retain());
+        will.set_qos(self.will_qos());
+
+        let content_type = self.will_content_type();
+        if !content_type.is_empty() {
+            will.set_content_type(Some(content_type.as_str()));
+        }
+
+        Some(will)
     }
 }
 
 impl Default for MQTTySettingConnection {
     fn default() -> Self {
-        Self::new("", "", "", "", "", "3", "0")
+        Self::new_simple("", "")
+    }
+}
+
+fn qos_from_u32(qos: u32) -> MQTTyClientQos {
+    match qos {
+        1 => MQTTyClientQos::Qos1,
+        2 => MQTTyClientQos::Qos2,
+        _ => MQTTyClientQos::Qos0,
+    }
+}
+
+fn qos_to_u32(qos: MQTTyClientQos) -> u32 {
+    match qos {
+        MQTTyClientQos::Qos0 => 0,
+        MQTTyClientQos::Qos1 => 1,
+        MQTTyClientQos::Qos2 => 2,
     }
 }
 
-const VARIANT_TYPE: &str = "(sssssss)";
+/// Version of the profile format, bump it when a field changes its meaning, and convert
+/// the older profiles in FromVariant::from_variant()
+const PROFILE_VERSION: u32 = 1;
 
+/// Profiles are saved as dictionaries, see the "profiles" key in the GSettings schema
+/// for the fields
 impl StaticVariantType for MQTTySettingConnection {
     fn static_variant_type() -> std::borrow::Cow<'static, gtk::glib::VariantTy> {
-        glib::VariantTy::new(VARIANT_TYPE).unwrap().into()
+        std::borrow::Cow::Borrowed(glib::VariantTy::VARDICT)
     }
 }
 
-/// Indexes mapping:
-/// - 0 <-> name: Connection profile name
-/// - 1 <-> url: URL connection
-/// - 2 <-> topic: MQTT topic
-/// - 3 <-> username: Authentication username
-/// - 4 <-> password: Authentication password
-/// - 5 <-> mqtt_version: MQTT version ("3" or "5")
-/// - 6 <-> qos: Quality of Service ("0", "1", or "2")
-type MQTTySettingConnectionTuple = (String, String, String, String, String, String, String);
-
 impl FromVariant for MQTTySettingConnection {
     fn from_variant(variant: &gtk::glib::Variant) -> Option<Self> {
-        let tuple = variant.get::<MQTTySettingConnectionTuple>();
-        if tuple.is_none() {
+        if !variant.is_type(glib::VariantTy::VARDICT) {
             tracing::error!(
                 "Could not convert from variant with format '{}', expected '{}'",
                 variant.type_(),
-                VARIANT_TYPE
+                glib::VariantTy::VARDICT
             );
+            return None;
         }
 
-        tuple.map(|tuple| tuple.into())
-    }
-}
+        let dict = glib::VariantDict::new(Some(variant));
+
+        let version = dict.lookup::<u32>("version").ok().flatten().unwrap_or(1);
+        if version > PROFILE_VERSION {
+            tracing::warn!(
+                "Connection profile was saved by a newer version of the app (format {}), \
+                 unknown fields are kept but ignored",
+                version
+            );
+        }
+
+        let conn = Self::default();
+        conn.load(&dict);
+        conn.imp().stored.replace(Some(variant.clone()));
 
-impl From<MQTTySettingConnectionTuple> for MQTTySettingConnection {
-    fn from(value: MQTTySettingConnectionTuple) -> Self {
-        Self::new(&value.0, &value.1, &value.2, &value.3, &value.4, &value.5, &value.6)
+        Some(conn)
     }
 }
 
-impl From<MQTTySettingConnection> for MQTTySettingConnectionTuple {
+impl From<MQTTySettingConnection> for glib::Variant {
     fn from(value: MQTTySettingConnection) -> Self {
-        (
-            value.name(),
-            value.url(),
-            value.topic(),
-            value.username(),
-            value.password(),
-            value.mqtt_version(),
-            value.qos(),
-        )
+        // Starting from the stored dictionary, so unknown fields survive
+        let dict = glib::VariantDict::new(value.imp().stored.borrow().as_ref());
+
+        value.store(&dict);
+
+        dict.end()
     }
 }
 
-impl From<MQTTySettingConnection> for glib::Variant {
-    fn from(value: MQTTySettingConnection) -> Self {
-        // Converts to tuple then to GVariant
-        Into::<MQTTySettingConnectionTuple>::into(value).into()
+/// Indexes mapping of the legacy "connections" key:
+/// - 0 <-> name: Connection profile name
+/// - 1 <-> url: URL connection
+/// - 2 <-> topic: MQTT topic
+/// - 3 <-> username: Authentication username
+/// - 4 <-> password: Authentication password
+/// - 5 <-> mqtt_version: MQTT version ("3" or "5")
+/// - 6 <-> qos: Quality of Service ("0", "1", or "2")
+type LegacyConnectionTuple = (String, String, String, String, String, String, String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_connection() -> glib::Variant {
+        let tuple: LegacyConnectionTuple = (
+            "Local".into(),
+            "tcp://localhost:1883".into(),
+            "test/#".into(),
+            "user".into(),
+            "secret".into(),
+            "5".into(),
+            "1".into(),
+        );
+
+        tuple.to_variant()
+    }
+
+    #[test]
+    fn legacy_migration_keeps_plain_text_password() {
+        let conn = MQTTySettingConnection::from_legacy(&legacy_connection()).unwrap();
+
+        assert_eq!(conn.password(), "secret");
+        assert!(conn.mqtt_version() == MQTTyClientVersion::V5);
+        assert_eq!(conn.qos(), MQTTyClientQos::Qos1);
+        assert!(!conn.secrets_in_keyring());
+
+        // Without a keyring, the password has to survive saving and reading the profile
+        let saved = glib::Variant::from(conn);
+        let dict = glib::VariantDict::new(Some(&saved));
+        assert_eq!(
+            dict.lookup::<String>("password").unwrap().as_deref(),
+            Some("secret")
+        );
+
+        let reloaded = MQTTySettingConnection::from_variant(&saved).unwrap();
+        assert_eq!(reloaded.password(), "secret");
+        assert_eq!(reloaded.username(), "user");
+    }
+
+    #[test]
+    fn newer_profile_keeps_unknown_fields_and_version() {
+        let dict = glib::VariantDict::new(None);
+        dict.insert("version", PROFILE_VERSION + 1);
+        dict.insert("name", "Remote");
+        dict.insert("url", "mqtts://broker.example.com:8883");
+        dict.insert("future-field", "kept");
+
+        let conn = MQTTySettingConnection::from_variant(&dict.end()).unwrap();
+        assert_eq!(conn.name(), "Remote");
+
+        conn.set_name("Renamed");
+
+        let saved = glib::VariantDict::new(Some(&glib::Variant::from(conn)));
+        assert_eq!(
+            saved.lookup::<u32>("version").unwrap(),
+            Some(PROFILE_VERSION + 1)
+        );
+        assert_eq!(
+            saved.lookup::<String>("future-field").unwrap().as_deref(),
+            Some("kept")
+        );
+        assert_eq!(
+            saved.lookup::<String>("name").unwrap().as_deref(),
+            Some("Renamed")
+        );
     }
 }
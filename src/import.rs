@@ -0,0 +1,354 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::client::{MQTTyClientMessage, PAYLOAD_ENCODING_UTF8};
+use crate::export::MQTTyExportFormat;
+
+/// Messages read from a file, along with the line numbers of the records that
+/// couldn't be read as messages
+pub struct MQTTyImport {
+    pub messages: Vec<MQTTyClientMessage>,
+    pub skipped: Vec<usize>,
+}
+
+/// Filters of the file dialog opening files to import, the same formats messages
+/// are exported to
+pub fn file_filters() -> gio::ListStore {
+    let all = gtk::FileFilter::new();
+    all.set_name(Some(&gettext("Messages")));
+
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&all);
+
+    for format in [MQTTyExportFormat::Csv, MQTTyExportFormat::JsonLines] {
+        all.add_suffix(format.extension());
+        filters.append(&format.file_filter());
+    }
+
+    filters
+}
+
+/// Reads the messages of a CSV or JSON Lines file, the format is picked by its
+/// extension, JSON Lines if it's not a CSV file
+///
+/// CSV files must start with a header naming its columns, only the topic column is
+/// required, see csv_record_to_json() for the rest
+pub async fn read_messages(file: &gio::File) -> Result<MQTTyImport, glib::Error> {
+    let (contents, _) = file.load_contents_future().await?;
+    let text = String::from_utf8_lossy(&contents);
+
+    let is_csv = file
+        .basename()
+        .and_then(|name| {
+            name.extension()
+                .map(|ext| ext.eq_ignore_ascii_case(MQTTyExportFormat::Csv.extension()))
+        })
+        .unwrap_or(false);
+
+    if is_csv {
+        read_csv(&text)
+    } else {
+        Ok(read_json_lines(&text))
+    }
+}
+
+fn read_json_lines(text: &str) -> MQTTyImport {
+    let mut import = MQTTyImport {
+        messages: vec![],
+        skipped: vec![],
+    };
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|value| MQTTyClientMessage::from_json(&value))
+        {
+            Some(message) => import.messages.push(message),
+            None => import.skipped.push(i + 1),
+        }
+    }
+
+    import
+}
+
+fn read_csv(text: &str) -> Result<MQTTyImport, glib::Error> {
+    let mut records = parse_csv(text).into_iter();
+
+    let Some((_, header)) = records.next() else {
+        return Ok(MQTTyImport {
+            messages: vec![],
+            skipped: vec![],
+        });
+    };
+
+    let header = header
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect::<Vec<_>>();
+
+    if !header.iter().any(|column| column == "topic") {
+        return Err(glib::Error::new(
+            gio::IOErrorEnum::InvalidData,
+            &gettext("The CSV header has no topic column"),
+        ));
+    }
+
+    let mut import = MQTTyImport {
+        messages: vec![],
+        skipped: vec![],
+    };
+
+    for (line, record) in records {
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        match csv_record_to_json(&header, &record)
+            .and_then(|value| MQTTyClientMessage::from_json(&value))
+        {
+            Some(message) => import.messages.push(message),
+            None => import.skipped.push(line),
+        }
+    }
+
+    Ok(import)
+}
+
+/// Turns a CSV record into the object MQTTyClientMessage::from_json() reads
+///
+/// Known columns are topic, payload, payload_encoding (UTF-8 if missing), qos (0, 1
+/// or 2), retain (true/false, 1/0 or yes/no) and user_properties, the latter as a
+/// JSON object or an array of [key, value] pairs, other columns are ignored. None if
+/// a field can't be read
+fn csv_record_to_json(header: &[String], record: &[String]) -> Option<serde_json::Value> {
+    let mut object = serde_json::Map::new();
+    object.insert("payload_encoding".into(), PAYLOAD_ENCODING_UTF8.into());
+
+    for (column, field) in header.iter().zip(record) {
+        let value = match column.as_str() {
+            "topic" | "payload" => serde_json::Value::from(field.as_str()),
+            "payload_encoding" if field.is_empty() => PAYLOAD_ENCODING_UTF8.into(),
+            "payload_encoding" => field.trim().to_lowercase().into(),
+            "qos" if field.trim().is_empty() => continue,
+            "qos" => match field.trim() {
+                qos @ ("0" | "1" | "2") => qos.parse::<u8>().ok()?.into(),
+                _ => return None,
+            },
+            "retain" => match field.trim().to_lowercase().as_str() {
+                "" | "false" | "0" | "no" => false.into(),
+                "true" | "1" | "yes" => true.into(),
+                _ => return None,
+            },
+            "user_properties" if field.trim().is_empty() => continue,
+            "user_properties" => match serde_json::from_str(field).ok()? {
+                serde_json::Value::Object(props) => props
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            serde_json::Value::String(value) => value,
+                            value => value.to_string(),
+                        };
+                        serde_json::json!([key, value])
+                    })
+                    .collect(),
+                props @ serde_json::Value::Array(_) => props,
+                _ => return None,
+            },
+            _ => continue,
+        };
+
+        object.insert(column.clone(), value);
+    }
+
+    Some(object.into())
+}
+
+/// Splits the text into records of fields as in RFC 4180, along with the line each
+/// record starts at, quoted fields may span several lines
+fn parse_csv(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = vec![];
+
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push(c);
+            }
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut record)));
+                line += 1;
+                record_line = line;
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::MQTTyClientQos;
+
+    fn fields(records: &[(usize, Vec<String>)]) -> Vec<Vec<&str>> {
+        records
+            .iter()
+            .map(|(_, record)| record.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    fn header(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|column| column.to_string()).collect()
+    }
+
+    fn record(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_csv_quoted_fields() {
+        let records = parse_csv("topic,payload\n\"a,b\",\"say \"\"hi\"\"\"\n\"\",x\n");
+
+        assert_eq!(
+            fields(&records),
+            [
+                vec!["topic", "payload"],
+                vec!["a,b", "say \"hi\""],
+                vec!["", "x"]
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_csv_line_breaks_inside_quotes() {
+        let records = parse_csv("topic,payload\r\nt,\"one\r\ntwo\nthree\"\r\nu,v\r\n");
+
+        assert_eq!(
+            fields(&records),
+            [
+                vec!["topic", "payload"],
+                vec!["t", "one\r\ntwo\nthree"],
+                vec!["u", "v"]
+            ]
+        );
+
+        // Records are numbered by the line they start at
+        let lines = records.iter().map(|(line, _)| *line).collect::<Vec<_>>();
+        assert_eq!(lines, [1, 2, 5]);
+    }
+
+    #[test]
+    fn parse_csv_empty_trailing_fields() {
+        let records = parse_csv("a,b,\nc,,\nd,");
+
+        assert_eq!(
+            fields(&records),
+            [vec!["a", "b", ""], vec!["c", "", ""], vec!["d", ""]]
+        );
+    }
+
+    #[test]
+    fn csv_record_to_json_fields() {
+        let header = header(&["topic", "payload", "qos", "retain", "unknown"]);
+
+        let value = csv_record_to_json(&header, &record(&["t", "hello", "2", "yes", "x"])).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "topic": "t",
+                "payload": "hello",
+                "payload_encoding": PAYLOAD_ENCODING_UTF8,
+                "qos": 2,
+                "retain": true,
+            })
+        );
+
+        // Empty trailing fields take the defaults
+        let value = csv_record_to_json(&header, &record(&["t", "", "", ""])).unwrap();
+        assert_eq!(value["payload"], "");
+        assert_eq!(value["retain"], false);
+        assert!(value.get("qos").is_none());
+
+        assert!(csv_record_to_json(&header, &record(&["t", "", "3", ""])).is_none());
+        assert!(csv_record_to_json(&header, &record(&["t", "", "0", "maybe"])).is_none());
+    }
+
+    #[test]
+    fn csv_export_import_round_trip() {
+        let message = |topic: &str, body: &[u8], qos, retained| {
+            let message = MQTTyClientMessage::new();
+            message.set_topic(topic);
+            message.set_body(body);
+            message.set_qos(qos);
+            message.set_retained(retained);
+            message
+        };
+
+        let messages = [
+            message("plain/topic", b"21.5", MQTTyClientQos::Qos0, false),
+            message("a,b", b"say \"hi\"", MQTTyClientQos::Qos1, true),
+            message("lines", b"one\r\ntwo\nthree", MQTTyClientQos::Qos2, false),
+            message("empty", b"", MQTTyClientQos::Qos0, true),
+            message("binary", &[0xff, 0x00, 0xfe], MQTTyClientQos::Qos1, false),
+        ];
+
+        let csv = MQTTyExportFormat::Csv.format(&messages);
+        let import = read_csv(&csv).unwrap();
+
+        assert!(import.skipped.is_empty());
+        assert_eq!(import.messages.len(), messages.len());
+
+        for (imported, message) in import.messages.iter().zip(&messages) {
+            assert_eq!(imported.topic(), message.topic());
+            assert_eq!(imported.body(), message.body());
+            assert_eq!(imported.qos(), message.qos());
+            assert_eq!(imported.retained(), message.retained());
+        }
+    }
+}
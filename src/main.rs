@@ -21,10 +21,14 @@ mod client;
 mod config;
 mod content_type;
 mod display_mode;
+mod export;
 mod gsettings;
+mod import;
 mod main_window;
 mod objects;
 mod pages;
+mod recording;
+mod secrets;
 mod subclass;
 mod toast;
 mod widgets;
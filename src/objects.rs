@@ -14,5 +14,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod key_value;
+mod message_store;
+mod topic_filter;
 
 pub use key_value::MQTTyKeyValue;
+pub use message_store::MQTTyMessageStore;
+pub use topic_filter::MQTTyTopicFilter;
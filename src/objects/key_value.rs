@@ -58,7 +58,6 @@ mod imp {
             let obj = self.obj();
 
             let changed_callback = |obj: &Self::Type| {
-                println!("key_value: {:?}", obj.imp());
                 obj.emit_by_name::<()>("changed", &[]);
             };
 
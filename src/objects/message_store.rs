@@ -0,0 +1,262 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::{gio, glib};
+
+use crate::client::MQTTyClientMessage;
+
+/// Pushed messages are shown at most once per frame
+const FLUSH_INTERVAL: Duration = Duration::from_millis(16);
+
+mod imp {
+
+    use super::*;
+
+    #[derive(glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyMessageStore)]
+    pub struct MQTTyMessageStore {
+        /// Newest first, along with the size of every message
+        pub messages: RefCell<VecDeque<(MQTTyClientMessage, u64)>>,
+
+        /// Messages pushed since the last flush, oldest first
+        pub pending: RefCell<Vec<MQTTyClientMessage>>,
+
+        pub flush_source: RefCell<Option<glib::SourceId>>,
+
+        /// 0 for no limit
+        #[property(get, set = Self::set_max_messages)]
+        max_messages: Cell<u32>,
+
+        /// MiB, 0 for no limit
+        #[property(get, set = Self::set_max_size)]
+        max_size: Cell<u32>,
+
+        /// Approximate memory taken by the kept messages
+        #[property(get)]
+        pub bytes: Cell<u64>,
+    }
+
+    impl Default for MQTTyMessageStore {
+        fn default() -> Self {
+            Self {
+                messages: Default::default(),
+                pending: Default::default(),
+                flush_source: Default::default(),
+                max_messages: Cell::new(10_000),
+                max_size: Cell::new(64),
+                bytes: Cell::new(0),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyMessageStore {
+        const NAME: &'static str = "MQTTyMessageStore";
+
+        type Type = super::MQTTyMessageStore;
+
+        type ParentType = glib::Object;
+
+        type Interfaces = (gio::ListModel,);
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyMessageStore {
+        fn dispose(&self) {
+            if let Some(source) = self.flush_source.take() {
+                source.remove();
+            }
+        }
+    }
+
+    impl ListModelImpl for MQTTyMessageStore {
+        fn item_type(&self) -> glib::Type {
+            MQTTyClientMessage::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.messages.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.messages
+                .borrow()
+                .get(position as usize)
+                .map(|(message, _)| message.clone().upcast())
+        }
+    }
+
+    impl MQTTyMessageStore {
+        fn set_max_messages(&self, max_messages: u32) {
+            self.max_messages.set(max_messages);
+            self.obj().evict();
+        }
+
+        fn set_max_size(&self, max_size: u32) {
+            self.max_size.set(max_size);
+            self.obj().evict();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// List of received messages, newest first, keeping only the most recent ones
+    /// within a count and a memory cap, the oldest messages are evicted first
+    ///
+    /// Messages are buffered and added in batches, so that high-rate topics don't
+    /// update the list more than once per frame
+    pub struct MQTTyMessageStore(ObjectSubclass<imp::MQTTyMessageStore>)
+        @implements gio::ListModel;
+}
+
+impl MQTTyMessageStore {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    /// Queues the message, it's added to the list on the next flush
+    pub fn push(&self, message: &MQTTyClientMessage) {
+        let imp = self.imp();
+
+        imp.pending.borrow_mut().push(message.clone());
+
+        if imp.flush_source.borrow().is_some() {
+            return;
+        }
+
+        let source = glib::timeout_add_local_once(
+            FLUSH_INTERVAL,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.imp().flush_source.take();
+                    obj.flush();
+                }
+            ),
+        );
+
+        imp.flush_source.replace(Some(source));
+    }
+
+    /// Adds the queued messages to the list right away
+    pub fn flush(&self) {
+        let imp = self.imp();
+
+        if let Some(source) = imp.flush_source.take() {
+            source.remove();
+        }
+
+        let pending = imp.pending.take();
+
+        if pending.is_empty() {
+            return;
+        }
+
+        // Messages that would be evicted by count right away never reach the list
+        let max_messages = self.max_messages() as usize;
+        let skipped = match max_messages {
+            0 => 0,
+            max => pending.len().saturating_sub(max),
+        };
+
+        let added = pending.len() - skipped;
+
+        {
+            let mut messages = imp.messages.borrow_mut();
+            let mut bytes = imp.bytes.get();
+
+            for message in pending.into_iter().skip(skipped) {
+                let size = message.size() as u64;
+                bytes += size;
+                messages.push_front((message, size));
+            }
+
+            imp.bytes.set(bytes);
+        }
+
+        self.items_changed(0, 0, added as u32);
+
+        self.evict();
+        self.notify_bytes();
+    }
+
+    pub fn clear(&self) {
+        let imp = self.imp();
+
+        if let Some(source) = imp.flush_source.take() {
+            source.remove();
+        }
+
+        imp.pending.borrow_mut().clear();
+
+        let removed = imp.messages.take().len() as u32;
+        imp.bytes.set(0);
+
+        if removed > 0 {
+            self.items_changed(0, removed, 0);
+        }
+
+        self.notify_bytes();
+    }
+
+    /// Drops the oldest messages until the list is within both caps
+    fn evict(&self) {
+        let imp = self.imp();
+
+        let max_messages = self.max_messages() as usize;
+        let max_bytes = self.max_size() as u64 * 1024 * 1024;
+
+        let (len, removed) = {
+            let mut messages = imp.messages.borrow_mut();
+            let mut bytes = imp.bytes.get();
+            let mut removed = 0;
+
+            while let Some((_, size)) = messages.back() {
+                let over_count = max_messages != 0 && messages.len() > max_messages;
+                let over_size = max_bytes != 0 && bytes > max_bytes;
+
+                if !over_count && !over_size {
+                    break;
+                }
+
+                bytes -= size;
+                messages.pop_back();
+                removed += 1;
+            }
+
+            imp.bytes.set(bytes);
+
+            (messages.len() as u32, removed)
+        };
+
+        if removed > 0 {
+            self.items_changed(len, removed, 0);
+            self.notify_bytes();
+        }
+    }
+}
+
+impl Default for MQTTyMessageStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
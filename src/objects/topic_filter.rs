@@ -0,0 +1,173 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, RefCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+
+use crate::client::{
+    MQTTyClientError, MQTTyClientQos, MQTTyReason, MQTTyRetainHandling, SubscribeOptions,
+};
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyTopicFilter)]
+    pub struct MQTTyTopicFilter {
+        #[property(get, set)]
+        topic_filter: RefCell<String>,
+
+        #[property(get, set, builder(Default::default()))]
+        qos: Cell<MQTTyClientQos>,
+
+        #[property(get, set)]
+        no_local: Cell<bool>,
+
+        #[property(get, set)]
+        retain_as_published: Cell<bool>,
+
+        #[property(get, set, builder(Default::default()))]
+        retain_handling: Cell<MQTTyRetainHandling>,
+
+        /// 0 for none, the maximum is the one of a variable byte integer
+        #[property(get, set, maximum = 268_435_455)]
+        subscription_identifier: Cell<u32>,
+
+        /// Filter granted by the last SUBACK, messages are matched against it, since
+        /// topic_filter may be edited afterwards
+        #[property(get, nullable)]
+        subscribed_filter: RefCell<Option<String>>,
+
+        /// Options subscribed_filter was subscribed with, needed to release it since
+        /// the options may be edited afterwards too
+        pub subscribed_options: RefCell<SubscribeOptions>,
+
+        /// SUBACK reason code description, empty until subscribed
+        #[property(get)]
+        status: RefCell<String>,
+
+        #[property(get)]
+        failed: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyTopicFilter {
+        const NAME: &'static str = "MQTTyTopicFilter";
+
+        type Type = super::MQTTyTopicFilter;
+
+        type ParentType = glib::Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyTopicFilter {}
+}
+
+glib::wrapper! {
+    /// A topic filter of a subscribe tab, along with its subscribe options and the
+    /// result of its subscription
+    pub struct MQTTyTopicFilter(ObjectSubclass<imp::MQTTyTopicFilter>);
+}
+
+impl MQTTyTopicFilter {
+    pub fn new(topic_filter: &str, qos: MQTTyClientQos) -> Self {
+        glib::Object::builder()
+            .property("topic_filter", topic_filter)
+            .property("qos", qos)
+            .build()
+    }
+
+    pub fn options(&self) -> SubscribeOptions {
+        SubscribeOptions {
+            qos: self.qos(),
+            no_local: self.no_local(),
+            retain_as_published: self.retain_as_published(),
+            retain_handling: self.retain_handling(),
+            subscription_identifier: self.subscription_identifier(),
+        }
+    }
+
+    pub fn subscribed_options(&self) -> SubscribeOptions {
+        self.imp().subscribed_options.borrow().clone()
+    }
+
+    /// Records the SUBACK of subscribing to `topic_filter` with `options`
+    pub fn set_suback(
+        &self,
+        topic_filter: &str,
+        options: &SubscribeOptions,
+        result: &Result<MQTTyReason, MQTTyClientError>,
+    ) {
+        let imp = self.imp();
+
+        imp.subscribed_options.replace(options.clone());
+
+        let (subscribed_filter, status, failed) = match result {
+            Ok(reason) => (Some(topic_filter.to_string()), reason.to_string(), false),
+            Err(e) => (None, e.to_string(), true),
+        };
+
+        imp.subscribed_filter.replace(subscribed_filter);
+        imp.status.replace(status);
+        imp.failed.set(failed);
+
+        self.notify_subscribed_filter();
+        self.notify_status();
+        self.notify_failed();
+    }
+
+    /// Records the UNSUBACK, the filter is no longer subscribed either way
+    pub fn set_unsuback(&self, result: &Result<Option<MQTTyReason>, MQTTyClientError>) {
+        let imp = self.imp();
+
+        let (status, failed) = match result {
+            Ok(Some(reason)) => (reason.to_string(), false),
+            Ok(None) => (gettext("Unsubscribed"), false),
+            Err(e) => (e.to_string(), true),
+        };
+
+        imp.subscribed_filter.replace(None);
+        imp.status.replace(status);
+        imp.failed.set(failed);
+
+        self.notify_subscribed_filter();
+        self.notify_status();
+        self.notify_failed();
+    }
+
+    /// Forgets the subscription, e.g. when the tab moves to another client
+    pub fn reset_suback(&self) {
+        let imp = self.imp();
+
+        imp.subscribed_filter.replace(None);
+        imp.status.replace(String::new());
+        imp.failed.set(false);
+
+        self.notify_subscribed_filter();
+        self.notify_status();
+        self.notify_failed();
+    }
+}
+
+impl Default for MQTTyTopicFilter {
+    fn default() -> Self {
+        Self::new("", MQTTyClientQos::Qos0)
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod replay;
+
+pub use replay::MQTTyReplay;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::client::MQTTyClientMessage;
+
+/// Recordings are JSON Lines files, one MQTTyClientMessage::to_json() object per line
+pub const RECORDING_EXTENSION: &str = "jsonl";
+
+/// Filters of the file dialogs saving and opening recordings
+pub fn file_filters() -> gio::ListStore {
+    let filter = gtk::FileFilter::new();
+    filter.add_suffix(RECORDING_EXTENSION);
+    filter.set_name(Some(&gettext("Recordings")));
+
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+    filters
+}
+
+/// Writes received messages to a recording file
+///
+/// Messages are written from a thread of its own, so that a slow disk doesn't hold
+/// the main loop back, the file is flushed whenever there is nothing left to write.
+/// Dropping the recorder finishes writing the queued messages and closes the file
+pub struct Recorder {
+    path: PathBuf,
+    tx: mpsc::Sender<String>,
+}
+
+impl Recorder {
+    /// Creates the file, a previous one is truncated since it was chosen to be replaced
+    ///
+    /// If writing fails, the recorder stops writing and on_error is called from the
+    /// main loop, messages recorded after that are dropped
+    pub fn create(
+        path: &Path,
+        on_error: impl FnOnce(std::io::Error) + 'static,
+    ) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+
+        let (tx, rx) = mpsc::channel::<String>();
+        let (error_tx, error_rx) = async_channel::bounded(1);
+
+        let thread_path = path.to_path_buf();
+        thread::spawn(move || {
+            if let Err(e) = write_lines(file, rx) {
+                tracing::error!("Failed to record to {}: {}", thread_path.display(), e);
+                let _ = error_tx.send_blocking(e);
+            }
+        });
+
+        glib::spawn_future_local(async move {
+            if let Ok(e) = error_rx.recv().await {
+                on_error(e);
+            }
+        });
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            tx,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, message: &MQTTyClientMessage) {
+        let _ = self.tx.send(message.to_json().to_string());
+    }
+}
+
+fn write_lines(file: File, rx: mpsc::Receiver<String>) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(file);
+
+    while let Ok(line) = rx.recv() {
+        writeln!(writer, "{line}")?;
+
+        for line in rx.try_iter() {
+            writeln!(writer, "{line}")?;
+        }
+
+        writer.flush()?;
+    }
+
+    writer.flush()
+}
+
+/// Reads every message of a recording, in the order they were recorded
+///
+/// Lines that aren't messages are skipped, e.g. the last one of a recording cut short
+pub async fn read_recording(file: &gio::File) -> Result<Vec<MQTTyClientMessage>, glib::Error> {
+    let (contents, _) = file.load_contents_future().await?;
+
+    let messages = String::from_utf8_lossy(&contents)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| {
+            let message = serde_json::from_str::<serde_json::Value>(line)
+                .ok()
+                .and_then(|value| MQTTyClientMessage::from_json(&value));
+
+            if message.is_none() {
+                tracing::warn!("Skipping line {} of the recording, not a message", i + 1);
+            }
+
+            message
+        })
+        .collect();
+
+    Ok(messages)
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+
+use crate::client::{topic_matches_filter, MQTTyClient, MQTTyClientError, MQTTyClientMessage};
+
+/// Longest uninterrupted wait between two messages, so that stop() takes effect soon
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+mod imp {
+
+    use super::*;
+
+    #[derive(glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyReplay)]
+    pub struct MQTTyReplay {
+        /// Multiplier of the original pace, 0 publishes without waiting
+        #[property(get, set, minimum = 0.0)]
+        speed: Cell<f64>,
+
+        /// Start over once the last message is published, until stopped
+        #[property(get, set)]
+        repeat: Cell<bool>,
+
+        /// Only topics matching this filter are published, empty for every topic
+        #[property(get, set)]
+        topic_filter: RefCell<String>,
+
+        /// Topics starting with this prefix get it replaced by new_topic_prefix
+        #[property(get, set)]
+        topic_prefix: RefCell<String>,
+
+        #[property(get, set)]
+        new_topic_prefix: RefCell<String>,
+
+        /// Publish retained messages with the retain flag, otherwise the broker would
+        /// keep the replayed messages in place of the ones it holds
+        #[property(get, set)]
+        keep_retain: Cell<bool>,
+
+        #[property(get)]
+        running: Cell<bool>,
+
+        /// Messages published in the current pass
+        #[property(get)]
+        published: Cell<u32>,
+
+        /// Messages of a pass, the ones left out by topic_filter are not counted
+        #[property(get)]
+        total: Cell<u32>,
+
+        pub stop_requested: Cell<bool>,
+    }
+
+    impl Default for MQTTyReplay {
+        fn default() -> Self {
+            Self {
+                speed: Cell::new(1.0),
+                repeat: Cell::new(false),
+                topic_filter: Default::default(),
+                topic_prefix: Default::default(),
+                new_topic_prefix: Default::default(),
+                keep_retain: Cell::new(false),
+                running: Cell::new(false),
+                published: Cell::new(0),
+                total: Cell::new(0),
+                stop_requested: Cell::new(false),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyReplay {
+        const NAME: &'static str = "MQTTyReplay";
+
+        type Type = super::MQTTyReplay;
+
+        type ParentType = glib::Object;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyReplay {}
+
+    impl MQTTyReplay {
+        pub fn set_running(&self, running: bool) {
+            if self.running.replace(running) != running {
+                self.obj().notify_running();
+            }
+        }
+
+        pub fn set_published(&self, published: u32) {
+            if self.published.replace(published) != published {
+                self.obj().notify_published();
+            }
+        }
+
+        pub fn set_total(&self, total: u32) {
+            if self.total.replace(total) != total {
+                self.obj().notify_total();
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Publishes recorded messages again, keeping the time between them as they were
+    /// received, scaled by the speed
+    pub struct MQTTyReplay(ObjectSubclass<imp::MQTTyReplay>);
+}
+
+impl MQTTyReplay {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    /// Publishes the messages in order through the client, returns the number of
+    /// messages published
+    ///
+    /// A failed publish ends the replay, the client must be connected already
+    pub async fn run(
+        &self,
+        client: &MQTTyClient,
+        messages: &[MQTTyClientMessage],
+    ) -> Result<u32, MQTTyClientError> {
+        let imp = self.imp();
+
+        let topic_filter = self.topic_filter();
+        let messages = messages
+            .iter()
+            .filter(|message| {
+                topic_filter.is_empty() || topic_matches_filter(&topic_filter, &message.topic())
+            })
+            .collect::<Vec<_>>();
+
+        imp.stop_requested.set(false);
+        imp.set_total(messages.len() as u32);
+        imp.set_running(true);
+
+        let ret = self.replay(client, &messages).await;
+
+        imp.set_running(false);
+
+        ret
+    }
+
+    /// Ends the replay before the next message is published
+    pub fn stop(&self) {
+        self.imp().stop_requested.set(true);
+    }
+
+    async fn replay(
+        &self,
+        client: &MQTTyClient,
+        messages: &[&MQTTyClientMessage],
+    ) -> Result<u32, MQTTyClientError> {
+        let imp = self.imp();
+
+        let mut count = 0;
+
+        loop {
+            imp.set_published(0);
+
+            // Every message is scheduled from the start of the pass, so that the time
+            // spent publishing doesn't pile up
+            let start = Instant::now();
+            let first = messages.first().and_then(|message| message.received_at());
+
+            for message in messages {
+                let deadline = start + self.offset(first.as_ref(), message);
+
+                if !self.wait_until(deadline).await {
+                    return Ok(count);
+                }
+
+                client.publish(&self.replayed(message)).await?;
+
+                count += 1;
+                imp.set_published(imp.published.get() + 1);
+            }
+
+            if !self.repeat() || messages.is_empty() {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Time between the first message of the pass and this one as received, scaled by
+    /// the speed
+    fn offset(&self, first: Option<&glib::DateTime>, message: &MQTTyClientMessage) -> Duration {
+        let speed = self.speed();
+
+        let (Some(first), Some(current)) = (first, message.received_at()) else {
+            return Duration::ZERO;
+        };
+
+        if speed <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        let micros = current.difference(first).as_microseconds().max(0);
+
+        Duration::from_micros(micros as u64).div_f64(speed)
+    }
+
+    /// Returns false if the replay was stopped in the meantime
+    async fn wait_until(&self, deadline: Instant) -> bool {
+        loop {
+            if self.imp().stop_requested.get() {
+                return false;
+            }
+
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return true;
+            }
+
+            glib::timeout_future(left.min(STOP_CHECK_INTERVAL)).await;
+        }
+    }
+
+    /// Copy of the message to publish, with its topic rewritten, what the broker
+    /// attached when delivering it is left out
+    fn replayed(&self, message: &MQTTyClientMessage) -> MQTTyClientMessage {
+        let topic = message.topic();
+        let topic = match topic.strip_prefix(self.topic_prefix().as_str()) {
+            Some(rest) => format!("{}{}", self.new_topic_prefix(), rest),
+            None => topic,
+        };
+
+        let replayed = MQTTyClientMessage::new();
+
+        replayed.set_topic(topic);
+        replayed.set_body(&message.body());
+        replayed.set_qos(message.qos());
+        replayed.set_mqtt_version(message.mqtt_version());
+        replayed.set_retained(message.retained() && self.keep_retain());
+        replayed.set_content_type(message.content_type());
+        replayed.set_message_expiry_interval(message.message_expiry_interval());
+        replayed.set_response_topic(message.response_topic());
+        replayed.set_correlation_data(message.correlation_data().as_deref());
+        replayed.set_payload_format_utf8(message.payload_format_utf8());
+        replayed.set_user_properties(&message.user_properties());
+
+        replayed
+    }
+}
+
+impl Default for MQTTyReplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
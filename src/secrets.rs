@@ -0,0 +1,179 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use formatx::formatx;
+use gettextrs::gettext;
+
+use crate::config;
+
+/// Secrets of a connection profile that are kept in the freedesktop Secret Service
+/// instead of GSettings, every kind is a different item in the keyring
+#[derive(Clone, Copy)]
+pub enum MQTTySecretKind {
+    Password,
+    /// Passphrase of the TLS client key
+    KeyPassphrase,
+}
+
+impl MQTTySecretKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Password => "password",
+            Self::KeyPassphrase => "key-passphrase",
+        }
+    }
+
+    /// Label displayed by keyring managers like Seahorse
+    fn label(&self, profile_name: &str) -> String {
+        let label = match self {
+            Self::Password => gettext("MQTTy password for “{}”"),
+            Self::KeyPassphrase => gettext("MQTTy client key passphrase for “{}”"),
+        };
+
+        formatx!(label, profile_name).unwrap_or(label)
+    }
+}
+
+/// Attributes identifying the item of the secret in the keyring
+fn attributes(profile_id: &str, kind: MQTTySecretKind) -> HashMap<&str, &str> {
+    HashMap::from([
+        ("application", config::APP_ID),
+        ("profile", profile_id),
+        ("kind", kind.as_str()),
+    ])
+}
+
+/// Stores the secret, replacing the previous one
+pub async fn store_secret(
+    profile_id: &str,
+    profile_name: &str,
+    kind: MQTTySecretKind,
+    secret: &str,
+) -> Result<(), String> {
+    backend::store(
+        &attributes(profile_id, kind),
+        &kind.label(profile_name),
+        secret,
+    )
+    .await
+}
+
+/// Returns None if there is no secret of this kind for the profile
+pub async fn lookup_secret(
+    profile_id: &str,
+    kind: MQTTySecretKind,
+) -> Result<Option<String>, String> {
+    backend::lookup(&attributes(profile_id, kind)).await
+}
+
+pub async fn delete_secret(profile_id: &str, kind: MQTTySecretKind) -> Result<(), String> {
+    backend::delete(&attributes(profile_id, kind)).await
+}
+
+/// oo7 talks to the Secret Service over D-Bus, or to the secret portal when sandboxed,
+/// e.g. inside Flatpak
+///
+/// It can be tried headless with a keyring stand-in:
+///
+/// ```sh
+/// dbus-run-session -- sh -c 'echo -n "" | gnome-keyring-daemon --unlock && MQTTy'
+/// ```
+#[cfg(target_os = "linux")]
+mod backend {
+    use std::cell::OnceCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    thread_local! {
+        /// Opening the keyring sets up the D-Bus connection, or the portal session, so
+        /// it's done once and shared by every secret of every profile
+        static KEYRING: OnceCell<Rc<oo7::Keyring>> = const { OnceCell::new() };
+    }
+
+    async fn keyring() -> Result<Rc<oo7::Keyring>, String> {
+        if let Some(keyring) = KEYRING.with(|keyring| keyring.get().cloned()) {
+            return Ok(keyring);
+        }
+
+        let keyring = oo7::Keyring::new().await.map_err(|e| e.to_string())?;
+
+        // Another caller may have opened it while this one was waiting
+        Ok(KEYRING.with(|cell| cell.get_or_init(|| Rc::new(keyring)).clone()))
+    }
+
+    pub async fn store(
+        attributes: &HashMap<&str, &str>,
+        label: &str,
+        secret: &str,
+    ) -> Result<(), String> {
+        keyring()
+            .await?
+            .create_item(label, attributes, secret, true)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn lookup(attributes: &HashMap<&str, &str>) -> Result<Option<String>, String> {
+        let items = keyring()
+            .await?
+            .search_items(attributes)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let Some(item) = items.first() else {
+            return Ok(None);
+        };
+
+        let secret = item.secret().await.map_err(|e| e.to_string())?;
+
+        String::from_utf8(secret.as_bytes().to_vec())
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn delete(attributes: &HashMap<&str, &str>) -> Result<(), String> {
+        keyring()
+            .await?
+            .delete(attributes)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// There is no Secret Service outside of Linux, callers fall back to GSettings
+#[cfg(not(target_os = "linux"))]
+mod backend {
+    use std::collections::HashMap;
+
+    const UNAVAILABLE: &str = "Secret Service is not available on this platform";
+
+    pub async fn store(
+        _attributes: &HashMap<&str, &str>,
+        _label: &str,
+        _secret: &str,
+    ) -> Result<(), String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub async fn lookup(_attributes: &HashMap<&str, &str>) -> Result<Option<String>, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub async fn delete(_attributes: &HashMap<&str, &str>) -> Result<(), String> {
+        Err(UNAVAILABLE.to_string())
+    }
+}
@@ -15,31 +15,43 @@
 
 mod add_conn_card;
 mod conn_card;
+mod conn_info_dialog;
+mod conn_state_indicator;
 mod data_chart;
 mod edit_conn_list_box;
 mod key_value_row;
 mod message_detail_dialog;
 mod publish_view;
+mod replay_dialog;
 mod source_view;
 mod subscribe_view;
+mod tls_group;
 mod topic_tree;
+mod websocket_group;
 
 pub mod base_card;
 
 pub use add_conn_card::MQTTyAddConnCard;
 pub use base_card::MQTTyBaseCard;
 pub use conn_card::MQTTyConnCard;
+pub use conn_info_dialog::MQTTyConnInfoDialog;
+pub use conn_state_indicator::MQTTyConnStateIndicator;
 pub use data_chart::MQTTyDataChart;
 pub use edit_conn_list_box::MQTTyEditConnListBox;
 pub use key_value_row::MQTTyKeyValueRow;
 pub use message_detail_dialog::MQTTyMessageDetailDialog;
 pub use publish_view::{
     MQTTyPublishAuthTab, MQTTyPublishBodyTab, MQTTyPublishGeneralTab, MQTTyPublishUserPropsTab,
-    MQTTyPublishView,
+    MQTTyPublishView, MQTTyPublishViewNotebook,
 };
+pub use replay_dialog::MQTTyReplayDialog;
 pub use source_view::MQTTySourceView;
 pub use subscribe_view::{
     MQTTyMessageRow, MQTTySubscribeAuthTab, MQTTySubscribeGeneralTab, MQTTySubscribeView,
     MQTTySubscribeViewNotebook,
 };
-pub use topic_tree::{MQTTyTopicItem, MQTTyTopicTreeView};
+pub use tls_group::MQTTyTlsGroup;
+pub use topic_tree::{
+    MQTTyClearRetainedDialog, MQTTyTopicDetailPane, MQTTyTopicItem, MQTTyTopicTreeView,
+};
+pub use websocket_group::MQTTyWebSocketGroup;
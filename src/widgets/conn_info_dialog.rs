@@ -0,0 +1,161 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{OnceCell, RefCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+
+use crate::client::MQTTyClient;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyConnInfoDialog)]
+    pub struct MQTTyConnInfoDialog {
+        #[property(get, construct_only)]
+        client: OnceCell<MQTTyClient>,
+
+        /// "notify::connection-info" handler, the dialog follows reconnections
+        pub info_handler: RefCell<Option<glib::SignalHandlerId>>,
+
+        pub stack: OnceCell<gtk::Stack>,
+
+        pub info_list: OnceCell<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyConnInfoDialog {
+        const NAME: &'static str = "MQTTyConnInfoDialog";
+        type Type = super::MQTTyConnInfoDialog;
+        type ParentType = adw::Dialog;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyConnInfoDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            obj.setup_ui();
+
+            let handler = obj.client().connect_connection_info_notify(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.update_info()
+            ));
+            self.info_handler.replace(Some(handler));
+
+            obj.update_info();
+        }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.info_handler.take() {
+                self.obj().client().disconnect(handler);
+            }
+        }
+    }
+    impl WidgetImpl for MQTTyConnInfoDialog {}
+    impl AdwDialogImpl for MQTTyConnInfoDialog {}
+}
+
+glib::wrapper! {
+    /// Lists what the broker told about the connection of a client in its CONNACK
+    pub struct MQTTyConnInfoDialog(ObjectSubclass<imp::MQTTyConnInfoDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl MQTTyConnInfoDialog {
+    pub fn new(client: &MQTTyClient) -> Self {
+        glib::Object::builder().property("client", client).build()
+    }
+
+    fn setup_ui(&self) {
+        self.set_title(&gettext("Connection Info"));
+        self.set_content_width(500);
+        self.set_content_height(600);
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&adw::HeaderBar::new());
+
+        let list = gtk::ListBox::new();
+        list.add_css_class("boxed-list");
+        list.set_selection_mode(gtk::SelectionMode::None);
+        list.set_valign(gtk::Align::Start);
+        list.set_margin_top(16);
+        list.set_margin_bottom(16);
+        list.set_margin_start(16);
+        list.set_margin_end(16);
+
+        let clamp = adw::Clamp::new();
+        clamp.set_child(Some(&list));
+
+        let scrolled = gtk::ScrolledWindow::new();
+        scrolled.set_vexpand(true);
+        scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+        scrolled.set_child(Some(&clamp));
+
+        let not_connected = adw::StatusPage::builder()
+            .icon_name("network-offline-symbolic")
+            .title(gettext("Not Connected"))
+            .description(gettext(
+                "The broker sends the connection info when the client connects",
+            ))
+            .build();
+
+        let stack = gtk::Stack::new();
+        stack.add_named(&scrolled, Some("info"));
+        stack.add_named(&not_connected, Some("not-connected"));
+
+        toolbar_view.set_content(Some(&stack));
+        self.set_child(Some(&toolbar_view));
+
+        let imp = self.imp();
+        let _ = imp.info_list.set(list);
+        let _ = imp.stack.set(stack);
+    }
+
+    fn update_info(&self) {
+        let imp = self.imp();
+
+        let (Some(stack), Some(list)) = (imp.stack.get(), imp.info_list.get()) else {
+            return;
+        };
+
+        list.remove_all();
+
+        let Some(info) = self.client().connection_info() else {
+            stack.set_visible_child_name("not-connected");
+            return;
+        };
+
+        for (title, value) in info.rows() {
+            let row = adw::ActionRow::builder()
+                .title(title)
+                .subtitle(value)
+                .subtitle_selectable(true)
+                .build();
+            row.add_css_class("property");
+            list.append(&row);
+        }
+
+        stack.set_visible_child_name("info");
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, OnceCell, RefCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::client::{MQTTyClient, MQTTyClientState, MQTTyClientVersion};
+use crate::main_window::MQTTyWindow;
+use crate::toast::MQTTyToastBuilder;
+use crate::widgets::MQTTyConnInfoDialog;
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyConnStateIndicator)]
+    pub struct MQTTyConnStateIndicator {
+        /// Client whose connection state is being displayed, None means there is no
+        /// client yet, and it's displayed as disconnected
+        #[property(get, set = Self::set_client, nullable)]
+        client: RefCell<Option<MQTTyClient>>,
+
+        #[property(get, set, builder(Default::default()))]
+        state: Cell<MQTTyClientState>,
+
+        state_binding: RefCell<Option<glib::Binding>>,
+
+        /// "reconnecting", "connected" and "disconnected" handlers of the client
+        client_handlers: RefCell<Vec<glib::SignalHandlerId>>,
+
+        dot: OnceCell<gtk::Box>,
+
+        label: OnceCell<gtk::Label>,
+
+        disconnect_action: OnceCell<gio::SimpleAction>,
+
+        drop_connection_action: OnceCell<gio::SimpleAction>,
+
+        disconnect_with_will_action: OnceCell<gio::SimpleAction>,
+
+        show_info_action: OnceCell<gio::SimpleAction>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyConnStateIndicator {
+        const NAME: &'static str = "MQTTyConnStateIndicator";
+
+        type Type = super::MQTTyConnStateIndicator;
+
+        type ParentType = gtk::Box;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyConnStateIndicator {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            obj.set_spacing(8);
+            obj.set_valign(gtk::Align::Center);
+            obj.set_margin_start(8);
+            obj.set_margin_end(8);
+
+            let dot = gtk::Box::builder()
+                .css_classes(["indicator", "circular"])
+                .valign(gtk::Align::Center)
+                .build();
+
+            let label = gtk::Label::builder()
+                .css_classes(["dim-label", "caption"])
+                .build();
+
+            let menu = gio::Menu::new();
+            menu.append(
+                Some(&gettext("Connection Info")),
+                Some("indicator.show-info"),
+            );
+            menu.append(Some(&gettext("Disconnect")), Some("indicator.disconnect"));
+            // The socket of the connection is looked up in /proc
+            #[cfg(target_os = "linux")]
+            menu.append(
+                Some(&gettext("Drop Connection")),
+                Some("indicator.drop-connection"),
+            );
+            menu.append(
+                Some(&gettext("Disconnect and Publish Will")),
+                Some("indicator.disconnect-with-will"),
+            );
+
+            let menu_button = gtk::MenuButton::builder()
+                .css_classes(["flat", "circular"])
+                .icon_name("view-more-symbolic")
+                .tooltip_text(gettext("Connection Actions"))
+                .valign(gtk::Align::Center)
+                .menu_model(&menu)
+                .build();
+
+            obj.append(&dot);
+            obj.append(&label);
+            obj.append(&menu_button);
+
+            self.dot.set(dot).unwrap();
+            self.label.set(label).unwrap();
+
+            let disconnect_action = gio::SimpleAction::new("disconnect", None);
+            disconnect_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    if let Some(client) = obj.client() {
+                        client.disconnect_in_background();
+                    }
+                }
+            ));
+
+            let drop_connection_action = gio::SimpleAction::new("drop-connection", None);
+            drop_connection_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    let Some(client) = obj.client() else {
+                        return;
+                    };
+
+                    if let Err(e) = client.drop_connection() {
+                        obj.toast_error(
+                            formatx!(gettext("Failed to drop the connection: {}"), e).unwrap(),
+                        );
+                    }
+                }
+            ));
+
+            let disconnect_with_will_action = gio::SimpleAction::new("disconnect-with-will", None);
+            disconnect_with_will_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    let Some(client) = obj.client() else {
+                        return;
+                    };
+
+                    glib::spawn_future_local(async move {
+                        if let Err(e) = client.disconnect_with_will().await {
+                            obj.toast_error(
+                                formatx!(gettext("Failed to disconnect: {}"), e).unwrap(),
+                            );
+                        }
+                    });
+                }
+            ));
+
+            let show_info_action = gio::SimpleAction::new("show-info", None);
+            show_info_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    if let Some(client) = obj.client() {
+                        MQTTyConnInfoDialog::new(&client).present(Some(&obj));
+                    }
+                }
+            ));
+
+            let group = gio::SimpleActionGroup::new();
+            group.add_action(&show_info_action);
+            group.add_action(&disconnect_action);
+            group.add_action(&drop_connection_action);
+            group.add_action(&disconnect_with_will_action);
+
+            obj.insert_action_group("indicator", Some(&group));
+
+            self.disconnect_action.set(disconnect_action).unwrap();
+            self.drop_connection_action
+                .set(drop_connection_action)
+                .unwrap();
+            self.disconnect_with_will_action
+                .set(disconnect_with_will_action)
+                .unwrap();
+            self.show_info_action.set(show_info_action).unwrap();
+
+            obj.connect_state_notify(|obj| obj.imp().update_state());
+
+            self.update_state();
+        }
+    }
+    impl WidgetImpl for MQTTyConnStateIndicator {}
+    impl BoxImpl for MQTTyConnStateIndicator {}
+
+    impl MQTTyConnStateIndicator {
+        fn set_client(&self, client: Option<MQTTyClient>) {
+            let obj = self.obj();
+
+            if let Some(binding) = self.state_binding.take() {
+                binding.unbind();
+            }
+
+            let handlers = self.client_handlers.take();
+
+            if let Some(old) = self.client.borrow().as_ref() {
+                for handler in handlers {
+                    old.disconnect(handler);
+                }
+            }
+
+            match &client {
+                Some(client) => {
+                    let binding = client
+                        .bind_property("state", &*obj, "state")
+                        .sync_create()
+                        .build();
+                    self.state_binding.replace(Some(binding));
+
+                    let reconnecting_handler = client.connect_reconnecting(glib::clone!(
+                        #[weak]
+                        obj,
+                        move |_, attempt, delay| {
+                            obj.set_tooltip_text(Some(
+                                &formatx!(
+                                    gettext("Attempt {}, retrying in {} seconds"),
+                                    attempt,
+                                    delay
+                                )
+                                .unwrap(),
+                            ));
+                        }
+                    ));
+
+                    let connected_handler = client.connect_connected(glib::clone!(
+                        #[weak]
+                        obj,
+                        move |client| {
+                            let Some(info) = client.connection_info() else {
+                                return;
+                            };
+
+                            let notices = info.notices();
+
+                            let title = match notices.as_slice() {
+                                [] => return,
+                                [notice] => notice.clone(),
+                                _ => gettext(
+                                    "The broker changed several connection parameters, see the connection info",
+                                ),
+                            };
+
+                            obj.toast(
+                                MQTTyToastBuilder::new()
+                                    .title(title)
+                                    .icon(
+                                        gtk::Image::builder()
+                                            .icon_name("dialog-information-symbolic")
+                                            .build()
+                                            .as_ref(),
+                                    )
+                                    .timeout(4)
+                                    .build(),
+                            );
+                        }
+                    ));
+
+                    let disconnected_handler = client.connect_disconnected(glib::clone!(
+                        #[weak]
+                        obj,
+                        move |_, reason| {
+                            // Empty when the disconnection was requested by us
+                            if reason.is_empty() {
+                                return;
+                            }
+
+                            obj.toast(
+                                MQTTyToastBuilder::new()
+                                    .title(
+                                        formatx!(gettext("Disconnected by the broker: {}"), reason)
+                                            .unwrap(),
+                                    )
+                                    .icon(
+                                        gtk::Image::builder()
+                                            .icon_name("network-error-symbolic")
+                                            .build()
+                                            .as_ref(),
+                                    )
+                                    .timeout(4)
+                                    .build(),
+                            );
+                        }
+                    ));
+
+                    self.client_handlers.replace(vec![
+                        reconnecting_handler,
+                        connected_handler,
+                        disconnected_handler,
+                    ]);
+                }
+                None => obj.set_state(MQTTyClientState::Disconnected),
+            }
+
+            self.client.replace(client);
+
+            // Available actions depend on the client too
+            self.update_state();
+        }
+
+        fn update_state(&self) {
+            let (Some(dot), Some(label)) = (self.dot.get(), self.label.get()) else {
+                return;
+            };
+
+            let state = self.state.get();
+
+            let (color, text) = match state {
+                MQTTyClientState::Disconnected => ("disabled", gettext("Disconnected")),
+                MQTTyClientState::Connecting => ("accent", gettext("Connecting…")),
+                MQTTyClientState::Connected => ("success", gettext("Connected")),
+                MQTTyClientState::Reconnecting => ("warning", gettext("Reconnecting…")),
+                MQTTyClientState::ConnectionLost => ("error", gettext("Connection lost")),
+            };
+
+            dot.set_css_classes(&["indicator", "circular", color]);
+            label.set_label(&text);
+
+            if let (
+                Some(disconnect),
+                Some(drop_connection),
+                Some(disconnect_with_will),
+                Some(show_info),
+            ) = (
+                self.disconnect_action.get(),
+                self.drop_connection_action.get(),
+                self.disconnect_with_will_action.get(),
+                self.show_info_action.get(),
+            ) {
+                // Only MQTT v5 can ask the broker to publish the will on disconnection
+                let is_v5 = self
+                    .client
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|c| c.mqtt_version() == MQTTyClientVersion::V5);
+
+                show_info.set_enabled(self.client.borrow().is_some());
+                disconnect.set_enabled(state != MQTTyClientState::Disconnected);
+                drop_connection.set_enabled(
+                    state == MQTTyClientState::Connected
+                        && self
+                            .client
+                            .borrow()
+                            .as_ref()
+                            .is_some_and(|c| c.can_drop_connection()),
+                );
+                disconnect_with_will.set_enabled(state == MQTTyClientState::Connected && is_v5);
+            }
+
+            // Tooltip is only meaningful while reconnecting, see set_client()
+            if state != MQTTyClientState::Reconnecting {
+                self.obj().set_tooltip_text(None);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Small dot + label displaying the connection state of a MQTTyClient, uses the
+    /// `.indicator` styles
+    ///
+    /// Also has a menu for disconnecting the client, or dropping the connection so that
+    /// the broker publishes the will, abruptly or, with MQTT v5, asking for it
+    pub struct MQTTyConnStateIndicator(ObjectSubclass<imp::MQTTyConnStateIndicator>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl MQTTyConnStateIndicator {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    /// Clients are shared by several tabs, only the indicator on screen tells the user
+    fn toast(&self, toast: &adw::Toast) {
+        if !self.is_mapped() {
+            return;
+        }
+
+        if let Some(window) = self.root().and_downcast::<MQTTyWindow>() {
+            window.toast(toast);
+        }
+    }
+
+    fn toast_error(&self, title: String) {
+        self.toast(
+            &MQTTyToastBuilder::new()
+                .title(title)
+                .icon(
+                    gtk::Image::builder()
+                        .icon_name("dialog-error-symbolic")
+                        .build()
+                        .as_ref(),
+                )
+                .timeout(3)
+                .build(),
+        );
+    }
+}
+
+impl Default for MQTTyConnStateIndicator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
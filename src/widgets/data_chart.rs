@@ -99,6 +99,11 @@ impl MQTTyDataChart {
             .unwrap()
             .as_secs_f64();
 
+        self.add_point_at(timestamp, value);
+    }
+
+    /// Add a data point taken at the given time, in seconds since the Unix epoch
+    pub fn add_point_at(&self, timestamp: f64, value: f64) {
         let mut data = self.imp().data_points.borrow_mut();
         let max = *self.imp().max_points.borrow();
 
@@ -113,6 +118,23 @@ impl MQTTyDataChart {
         self.queue_draw();
     }
 
+    /// Data points as CSV, with timestamp and value columns, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("timestamp,value\n");
+
+        for point in self.imp().data_points.borrow().iter() {
+            let timestamp = glib::DateTime::from_unix_local(0)
+                .and_then(|epoch| epoch.add_seconds(point.timestamp))
+                .and_then(|timestamp| timestamp.format_iso8601())
+                .map(|timestamp| timestamp.to_string())
+                .unwrap_or_else(|_| point.timestamp.to_string());
+
+            csv.push_str(&format!("{},{}\n", timestamp, point.value));
+        }
+
+        csv
+    }
+
     /// Clear all data points
     pub fn clear(&self) {
         self.imp().data_points.borrow_mut().clear();
@@ -277,21 +299,25 @@ impl MQTTyDataChart {
 
     /// Try to parse a payload as a numeric value
     pub fn try_add_from_payload(&self, payload: &str) -> bool {
+        match Self::parse_value(payload) {
+            Some(value) => {
+                self.add_point(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Numeric value of a payload, either a plain number or a JSON document holding one
+    pub fn parse_value(payload: &str) -> Option<f64> {
         // Try to parse as plain number
         if let Ok(value) = payload.trim().parse::<f64>() {
-            self.add_point(value);
-            return true;
+            return Some(value);
         }
 
         // Try to parse as JSON and extract a numeric value
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(payload) {
-            if let Some(value) = Self::extract_numeric_value(&json) {
-                self.add_point(value);
-                return true;
-            }
-        }
-
-        false
+        let json = serde_json::from_str::<serde_json::Value>(payload).ok()?;
+        Self::extract_numeric_value(&json)
     }
 
     /// Extract a numeric value from JSON
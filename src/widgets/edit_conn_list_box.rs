@@ -21,7 +21,9 @@ use adw::subclass::prelude::*;
 use gtk::glib;
 use gtk::glib::subclass::Signal;
 
+use crate::client::{MQTTyClientQos, MQTTyClientVersion, MQTTyDropPolicy, MQTTyTransport};
 use crate::gsettings::MQTTySettingConnection;
+use crate::widgets::{MQTTyTlsGroup, MQTTyWebSocketGroup};
 
 mod imp {
 
@@ -46,12 +48,72 @@ mod imp {
         #[template_child]
         topic_row: TemplateChild<adw::EntryRow>,
 
+        #[template_child]
+        auto_reconnect_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        client_id_row: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        clean_start_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        session_expiry_interval_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        keep_alive_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        receive_maximum_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        maximum_packet_size_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        message_buffer_size_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        drop_policy_row: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        will_topic_row: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        will_payload_row: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        will_qos_0_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        will_qos_1_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        will_qos_2_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        will_retain_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        will_delay_interval_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        will_content_type_row: TemplateChild<adw::EntryRow>,
+
         #[template_child]
         username_row: TemplateChild<adw::EntryRow>,
 
         #[template_child]
         password_row: TemplateChild<adw::PasswordEntryRow>,
 
+        #[template_child]
+        tls_group: TemplateChild<MQTTyTlsGroup>,
+
+        #[template_child]
+        websocket_group: TemplateChild<MQTTyWebSocketGroup>,
+
+        #[template_child]
+        transport_row: TemplateChild<adw::ComboRow>,
+
         #[template_child]
         mqtt_3_button: TemplateChild<gtk::CheckButton>,
 
@@ -119,6 +181,124 @@ mod imp {
                     .sync_create()
                     .build();
 
+                // Transport, it's the scheme of the URL, combo row items are in the same
+                // order as the enum variants
+                conn_model
+                    .bind_property("url", &*private.transport_row, "selected")
+                    .sync_create()
+                    .transform_to(|_, url: String| Some(MQTTyTransport::from_url(&url) as u32))
+                    .build();
+
+                private.transport_row.connect_selected_notify(glib::clone!(
+                    #[weak]
+                    conn_model,
+                    move |row| {
+                        let transport = MQTTyTransport::from(row.selected());
+
+                        if MQTTyTransport::from_url(&conn_model.url()) != transport {
+                            conn_model.set_url(transport.apply_to_url(&conn_model.url()));
+                        }
+                    }
+                ));
+
+                // Auto reconnect
+                conn_model
+                    .bind_property("auto_reconnect", &*private.auto_reconnect_row, "active")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+
+                // Session
+                conn_model
+                    .bind_property("client_id", &*private.client_id_row, "text")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+
+                conn_model
+                    .bind_property("clean_start", &*private.clean_start_row, "active")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+
+                // Numeric options are u32, the spin rows transform them from and to f64
+                for (prop, row) in [
+                    (
+                        "session_expiry_interval",
+                        &private.session_expiry_interval_row,
+                    ),
+                    ("keep_alive", &private.keep_alive_row),
+                    ("receive_maximum", &private.receive_maximum_row),
+                    ("maximum_packet_size", &private.maximum_packet_size_row),
+                    ("message_buffer_size", &private.message_buffer_size_row),
+                ] {
+                    conn_model
+                        .bind_property(prop, &**row, "value")
+                        .bidirectional()
+                        .sync_create()
+                        .build();
+                }
+
+                // Combo row items are in the same order as the enum variants
+                conn_model
+                    .bind_property("drop_policy", &*private.drop_policy_row, "selected")
+                    .bidirectional()
+                    .sync_create()
+                    .transform_to(|_, policy: MQTTyDropPolicy| Some(policy as u32))
+                    .transform_from(|_, selected: u32| Some(MQTTyDropPolicy::from(selected)))
+                    .build();
+
+                // Last Will
+                for (prop, row) in [
+                    ("will_topic", &private.will_topic_row),
+                    ("will_payload", &private.will_payload_row),
+                    ("will_content_type", &private.will_content_type_row),
+                ] {
+                    conn_model
+                        .bind_property(prop, &**row, "text")
+                        .bidirectional()
+                        .sync_create()
+                        .build();
+                }
+
+                conn_model
+                    .bind_property("will_retain", &*private.will_retain_row, "active")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+
+                conn_model
+                    .bind_property(
+                        "will_delay_interval",
+                        &*private.will_delay_interval_row,
+                        "value",
+                    )
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+
+                let will_qos_buttons = [
+                    (MQTTyClientQos::Qos0, &private.will_qos_0_button),
+                    (MQTTyClientQos::Qos1, &private.will_qos_1_button),
+                    (MQTTyClientQos::Qos2, &private.will_qos_2_button),
+                ];
+
+                for (qos, button) in will_qos_buttons {
+                    if conn_model.will_qos() == qos {
+                        button.set_active(true);
+                    }
+
+                    button.connect_toggled(glib::clone!(
+                        #[weak]
+                        conn_model,
+                        move |btn| {
+                            if btn.is_active() {
+                                conn_model.set_will_qos(qos);
+                            }
+                        }
+                    ));
+                }
+
                 // Username
                 conn_model
                     .bind_property("username", &*private.username_row, "text")
@@ -133,9 +313,38 @@ mod imp {
                     .sync_create()
                     .build();
 
+                // TLS, the group properties are named after the profile ones
+                for prop in [
+                    "enable_tls",
+                    "ca_cert_path",
+                    "client_cert_path",
+                    "client_key_path",
+                    "key_passphrase",
+                    "verify_server",
+                    "verify_hostname",
+                    "alpn",
+                    "tls_version",
+                    "ciphers",
+                ] {
+                    conn_model
+                        .bind_property(prop, &*private.tls_group, prop)
+                        .bidirectional()
+                        .sync_create()
+                        .build();
+                }
+
+                // WebSocket
+                for prop in ["ws_path", "http_headers", "http_proxy", "https_proxy"] {
+                    conn_model
+                        .bind_property(prop, &*private.websocket_group, prop)
+                        .bidirectional()
+                        .sync_create()
+                        .build();
+                }
+
                 // MQTT Version
                 let mqtt_version = conn_model.mqtt_version();
-                if mqtt_version == "5" {
+                if mqtt_version == MQTTyClientVersion::V5 {
                     private.mqtt_5_button.set_active(true);
                 } else {
                     private.mqtt_3_button.set_active(true);
@@ -146,7 +355,7 @@ mod imp {
                     conn_model,
                     move |btn| {
                         if btn.is_active() {
-                            conn_model.set_mqtt_version("3".to_string());
+                            conn_model.set_mqtt_version(MQTTyClientVersion::V3X);
                         }
                     }
                 ));
@@ -156,17 +365,17 @@ mod imp {
                     conn_model,
                     move |btn| {
                         if btn.is_active() {
-                            conn_model.set_mqtt_version("5".to_string());
+                            conn_model.set_mqtt_version(MQTTyClientVersion::V5);
                         }
                     }
                 ));
 
                 // QoS
                 let qos = conn_model.qos();
-                match qos.as_str() {
-                    "1" => private.qos_1_button.set_active(true),
-                    "2" => private.qos_2_button.set_active(true),
-                    _ => private.qos_0_button.set_active(true),
+                match qos {
+                    MQTTyClientQos::Qos0 => private.qos_0_button.set_active(true),
+                    MQTTyClientQos::Qos1 => private.qos_1_button.set_active(true),
+                    MQTTyClientQos::Qos2 => private.qos_2_button.set_active(true),
                 }
 
                 private.qos_0_button.connect_toggled(glib::clone!(
@@ -174,7 +383,7 @@ mod imp {
                     conn_model,
                     move |btn| {
                         if btn.is_active() {
-                            conn_model.set_qos("0".to_string());
+                            conn_model.set_qos(MQTTyClientQos::Qos0);
                         }
                     }
                 ));
@@ -184,7 +393,7 @@ mod imp {
                     conn_model,
                     move |btn| {
                         if btn.is_active() {
-                            conn_model.set_qos("1".to_string());
+                            conn_model.set_qos(MQTTyClientQos::Qos1);
                         }
                     }
                 ));
@@ -194,7 +403,7 @@ mod imp {
                     conn_model,
                     move |btn| {
                         if btn.is_active() {
-                            conn_model.set_qos("2".to_string());
+                            conn_model.set_qos(MQTTyClientQos::Qos2);
                         }
                     }
                 ));
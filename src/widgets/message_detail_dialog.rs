@@ -13,14 +13,17 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
 use gtk::glib;
 use sourceview::prelude::*;
 
-use crate::client::MQTTyClientMessage;
+use crate::client::{MQTTyClientMessage, MQTTyClientVersion};
+use crate::export;
 
 mod imp {
     use super::*;
@@ -42,6 +45,11 @@ mod imp {
 
         pub message: RefCell<Option<MQTTyClientMessage>>,
         pub previous_message: RefCell<Option<MQTTyClientMessage>>,
+
+        /// Rows of the properties page, filled by set_message()
+        pub properties_list: OnceCell<gtk::ListBox>,
+
+        pub toast_overlay: OnceCell<adw::ToastOverlay>,
     }
 
     #[glib::object_subclass]
@@ -82,6 +90,23 @@ impl MQTTyMessageDetailDialog {
 
         // Header bar
         let header = adw::HeaderBar::new();
+
+        let save_button = gtk::Button::builder()
+            .icon_name("document-save-symbolic")
+            .tooltip_text(gettext("Save payload as file"))
+            .build();
+
+        save_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                glib::spawn_future_local(async move {
+                    obj.save_payload().await;
+                });
+            }
+        ));
+
+        header.pack_start(&save_button);
         toolbar_view.add_top_bar(&header);
 
         // Main content
@@ -92,11 +117,22 @@ impl MQTTyMessageDetailDialog {
         let body_page = self.create_body_page();
         notebook.append_page(&body_page, Some(&gtk::Label::new(Some("Body"))));
 
+        // Properties tab
+        let properties_page = self.create_properties_page();
+        notebook.append_page(
+            &properties_page,
+            Some(&gtk::Label::new(Some(&gettext("Properties")))),
+        );
+
         // Diff tab
         let diff_page = self.create_diff_page();
         notebook.append_page(&diff_page, Some(&gtk::Label::new(Some("Diff"))));
 
-        toolbar_view.set_content(Some(&notebook));
+        let toast_overlay = adw::ToastOverlay::new();
+        toast_overlay.set_child(Some(&notebook));
+        let _ = self.imp().toast_overlay.set(toast_overlay.clone());
+
+        toolbar_view.set_content(Some(&toast_overlay));
         self.set_child(Some(&toolbar_view));
     }
 
@@ -134,6 +170,119 @@ impl MQTTyMessageDetailDialog {
         scrolled.upcast()
     }
 
+    fn create_properties_page(&self) -> gtk::Widget {
+        let scrolled = gtk::ScrolledWindow::new();
+        scrolled.set_vexpand(true);
+        scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+
+        let list = gtk::ListBox::new();
+        list.add_css_class("boxed-list");
+        list.set_selection_mode(gtk::SelectionMode::None);
+        list.set_valign(gtk::Align::Start);
+        list.set_margin_top(16);
+        list.set_margin_bottom(16);
+        list.set_margin_start(16);
+        list.set_margin_end(16);
+
+        let clamp = adw::Clamp::new();
+        clamp.set_child(Some(&list));
+
+        let _ = self.imp().properties_list.set(list);
+
+        scrolled.set_child(Some(&clamp));
+        scrolled.upcast()
+    }
+
+    fn populate_properties(&self, message: &MQTTyClientMessage) {
+        if let Some(list) = self.imp().properties_list.get() {
+            Self::fill_properties(list, message);
+        }
+    }
+
+    /// Lists the MQTT v5 properties of the message, only the ones present in the packet
+    pub fn fill_properties(list: &gtk::ListBox, message: &MQTTyClientMessage) {
+        list.remove_all();
+
+        let add_row = |title: &str, value: &str| {
+            let row = adw::ActionRow::builder()
+                .title(title)
+                .subtitle(value)
+                .subtitle_selectable(true)
+                .build();
+            row.add_css_class("property");
+            list.append(&row);
+        };
+
+        add_row(&gettext("QoS"), &(message.qos() as u32).to_string());
+        add_row(
+            &gettext("Retained"),
+            &if message.retained() {
+                gettext("Yes")
+            } else {
+                gettext("No")
+            },
+        );
+
+        if message.mqtt_version() != MQTTyClientVersion::V5 {
+            add_row(
+                &gettext("MQTT v3.x"),
+                &gettext("Messages of this version carry no properties"),
+            );
+            return;
+        }
+
+        if message.payload_format_utf8() {
+            add_row(&gettext("Payload Format Indicator"), &gettext("UTF-8"));
+        }
+
+        if let Some(content_type) = message.content_type() {
+            add_row(&gettext("Content Type"), &content_type);
+        }
+
+        if message.message_expiry_interval() > 0 {
+            add_row(
+                &gettext("Message Expiry Interval"),
+                &message.message_expiry_interval().to_string(),
+            );
+        }
+
+        if let Some(response_topic) = message.response_topic() {
+            add_row(&gettext("Response Topic"), &response_topic);
+        }
+
+        if let Some(correlation_data) = message.correlation_data() {
+            // Shown as text when possible, as hexadecimal bytes otherwise
+            let value = String::from_utf8(correlation_data.clone()).unwrap_or_else(|_| {
+                correlation_data
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+            add_row(&gettext("Correlation Data"), &value);
+        }
+
+        if message.topic_alias() > 0 {
+            add_row(&gettext("Topic Alias"), &message.topic_alias().to_string());
+        }
+
+        let subscription_identifiers = message.subscription_identifiers();
+        if !subscription_identifiers.is_empty() {
+            add_row(
+                &gettext("Subscription Identifiers"),
+                &subscription_identifiers
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+
+        for (key, value) in message.user_properties() {
+            add_row(&key, &value);
+        }
+    }
+
     fn create_diff_page(&self) -> gtk::Widget {
         let scrolled = gtk::ScrolledWindow::new();
         scrolled.set_vexpand(true);
@@ -165,28 +314,11 @@ impl MQTTyMessageDetailDialog {
     pub fn set_message(&self, message: &MQTTyClientMessage, previous: Option<&MQTTyClientMessage>) {
         self.set_topic(message.topic());
 
-        let body = message.body();
-        let body_str = String::from_utf8_lossy(&body);
+        let formatted = Self::format_body(message);
 
-        // Try to format as JSON
-        let formatted = if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&body_str) {
-            serde_json::to_string_pretty(&json_value).unwrap_or_else(|_| body_str.to_string())
-        } else {
-            body_str.to_string()
-        };
         // Generate diff if previous message exists
         if let Some(prev) = previous {
-            let prev_body = prev.body();
-            let prev_str = String::from_utf8_lossy(&prev_body);
-
-            // Try to format previous as JSON too
-            let prev_formatted = if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&prev_str) {
-                serde_json::to_string_pretty(&json_value).unwrap_or_else(|_| prev_str.to_string())
-            } else {
-                prev_str.to_string()
-            };
-
-            let diff = self.generate_diff(&prev_formatted, &formatted);
+            let diff = Self::diff(&Self::format_body(prev), &formatted);
             self.set_diff_text(diff);
             self.set_show_diff(true);
         } else {
@@ -195,12 +327,50 @@ impl MQTTyMessageDetailDialog {
         }
 
         self.set_formatted_body(formatted);
+        self.populate_properties(message);
 
         self.imp().message.replace(Some(message.clone()));
         self.imp().previous_message.replace(previous.cloned());
     }
 
-    fn generate_diff(&self, old: &str, new: &str) -> String {
+    /// Writes the raw payload to a file of the user's choice
+    async fn save_payload(&self) {
+        let Some(message) = self.imp().message.borrow().clone() else {
+            return;
+        };
+
+        // Last level of the topic, e.g. "temperature" for "home/kitchen/temperature"
+        let name = message
+            .topic()
+            .rsplit('/')
+            .find(|level| !level.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| gettext("payload"));
+
+        let ret =
+            export::save_to_file(self, &gettext("Save Payload"), &name, None, message.body()).await;
+
+        if let Err(e) = ret {
+            let toast =
+                adw::Toast::new(&formatx!(gettext("Failed to save the payload: {}"), e).unwrap());
+            self.imp().toast_overlay.get().unwrap().add_toast(toast);
+        }
+    }
+
+    /// Body of the message as text, pretty printed if it's JSON
+    pub fn format_body(message: &MQTTyClientMessage) -> String {
+        let body = message.body();
+        let body_str = String::from_utf8_lossy(&body);
+
+        if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&body_str) {
+            serde_json::to_string_pretty(&json_value).unwrap_or_else(|_| body_str.to_string())
+        } else {
+            body_str.to_string()
+        }
+    }
+
+    /// Unified line diff between the formatted bodies of two messages
+    pub fn diff(old: &str, new: &str) -> String {
         use similar::{ChangeTag, TextDiff};
 
         let diff = TextDiff::from_lines(old, new);
@@ -16,12 +16,14 @@
 mod publish_auth_tab;
 mod publish_body_tab;
 mod publish_general_tab;
+mod publish_import_dialog;
 mod publish_user_props_tab;
 mod publish_view_notebook;
 
 pub use publish_auth_tab::MQTTyPublishAuthTab;
 pub use publish_body_tab::MQTTyPublishBodyTab;
 pub use publish_general_tab::MQTTyPublishGeneralTab;
+pub use publish_import_dialog::MQTTyPublishImportDialog;
 pub use publish_user_props_tab::MQTTyPublishUserPropsTab;
 pub use publish_view_notebook::MQTTyPublishViewNotebook;
 
@@ -35,13 +37,13 @@ use gtk::glib;
 
 use crate::application::MQTTyApplication;
 use crate::display_mode::{MQTTyDisplayMode, MQTTyDisplayModeIface};
+use crate::import;
 use crate::main_window::MQTTyWindow;
 use crate::subclass::prelude::*;
+use crate::toast::MQTTyToastBuilder;
 
 mod imp {
 
-    use crate::toast::MQTTyToastBuilder;
-
     use super::*;
 
     #[derive(gtk::CompositeTemplate, glib::Properties)]
@@ -84,43 +86,11 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.install_action("publish-view.new-tab", None, |this, _, _| {
-                let notebook = MQTTyPublishViewNotebook::new();
-                this.bind_property("display_mode", &notebook, "display_mode")
-                    .sync_create()
-                    .build();
+                this.append_notebook(&MQTTyPublishViewNotebook::new());
+            });
 
-                let topic_expr = notebook
-                    .property_expression_weak("topic")
-                    .chain_closure::<String>(glib::closure!(
-                        move |_: Option<glib::Object>, topic: String| {
-                            if topic.is_empty() {
-                                gettext("(untitled)")
-                            } else {
-                                topic
-                            }
-                        }
-                    ));
-
-                let page = this.imp().tab_view.append(&notebook);
-
-                topic_expr.bind(&page, "title", glib::Object::NONE);
-
-                // We create a tooltip based on topic and url values, so that users knows how to
-                // differentiate between similar messages
-                gtk::ClosureExpression::new::<String>(
-                    [
-                        topic_expr.upcast(),
-                        notebook.property_expression_weak("url").upcast(),
-                    ],
-                    glib::closure!(move |_: Option<glib::Object>, topic: String, url: String| {
-                        if url.is_empty() {
-                            topic
-                        } else {
-                            [topic, url].join("\r\n")
-                        }
-                    }),
-                )
-                .bind(&page, "tooltip", glib::Object::NONE);
+            klass.install_action_async("publish-view.import", None, |this, _, _| async move {
+                this.import_messages().await;
             });
 
             klass.install_action("publish-view.send", None, |this, _, _| {
@@ -228,3 +198,106 @@ glib::wrapper! {
         @extends gtk::Widget, adw::Bin,
         @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
 }
+
+impl MQTTyPublishView {
+    fn append_notebook(&self, notebook: &MQTTyPublishViewNotebook) -> adw::TabPage {
+        self.bind_property("display_mode", notebook, "display_mode")
+            .sync_create()
+            .build();
+
+        let topic_expr = notebook
+            .property_expression_weak("topic")
+            .chain_closure::<String>(glib::closure!(
+                move |_: Option<glib::Object>, topic: String| {
+                    if topic.is_empty() {
+                        gettext("(untitled)")
+                    } else {
+                        topic
+                    }
+                }
+            ));
+
+        let page = self.imp().tab_view.append(notebook);
+
+        topic_expr.bind(&page, "title", glib::Object::NONE);
+
+        // We create a tooltip based on topic and url values, so that users knows how to
+        // differentiate between similar messages
+        gtk::ClosureExpression::new::<String>(
+            [
+                topic_expr.upcast(),
+                notebook.property_expression_weak("url").upcast(),
+            ],
+            glib::closure!(move |_: Option<glib::Object>, topic: String, url: String| {
+                if url.is_empty() {
+                    topic
+                } else {
+                    [topic, url].join("\r\n")
+                }
+            }),
+        )
+        .bind(&page, "tooltip", glib::Object::NONE);
+
+        page
+    }
+
+    /// Appends the tab and selects it
+    pub fn open_notebook(&self, notebook: &MQTTyPublishViewNotebook) {
+        let page = self.append_notebook(notebook);
+        self.imp().tab_view.set_selected_page(&page);
+    }
+
+    /// Asks for a CSV or JSON Lines file and opens its messages in the import dialog
+    async fn import_messages(&self) {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Import Messages"))
+            .filters(&import::file_filters())
+            .modal(true)
+            .build();
+
+        let window = self.root().and_downcast::<MQTTyWindow>();
+
+        // Dismissed by the user
+        let Ok(file) = dialog.open_future(window.as_ref()).await else {
+            return;
+        };
+
+        let toast = |title: String| {
+            let toast = MQTTyToastBuilder::new()
+                .title(title)
+                .icon(
+                    gtk::Image::builder()
+                        .icon_name("dialog-error-symbolic")
+                        .build()
+                        .as_ref(),
+                )
+                .timeout(3)
+                .build();
+
+            if let Some(window) = &window {
+                window.toast(&toast);
+            }
+        };
+
+        let import = match import::read_messages(&file).await {
+            Ok(import) => import,
+            Err(e) => {
+                toast(formatx!(gettext("Failed to import the file: {}"), e).unwrap());
+                return;
+            }
+        };
+
+        if !import.skipped.is_empty() {
+            let lines = import
+                .skipped
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            toast(formatx!(gettext("Skipped lines that aren't messages: {}"), lines).unwrap());
+        }
+
+        MQTTyPublishImportDialog::new(&file, &import.messages).present(Some(self));
+    }
+}
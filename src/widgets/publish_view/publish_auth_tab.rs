@@ -19,6 +19,9 @@ use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::glib;
 
+use crate::client::TlsOptions;
+use crate::widgets::{MQTTyTlsGroup, MQTTyWebSocketGroup};
+
 mod imp {
 
     use super::*;
@@ -32,6 +35,12 @@ mod imp {
 
         #[property(get, set)]
         password: RefCell<String>,
+
+        #[template_child]
+        pub tls_group: TemplateChild<MQTTyTlsGroup>,
+
+        #[template_child]
+        pub websocket_group: TemplateChild<MQTTyWebSocketGroup>,
     }
 
     #[glib::object_subclass]
@@ -62,3 +71,21 @@ glib::wrapper! {
         @extends gtk::Widget, adw::Bin,
         @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
 }
+
+impl MQTTyPublishAuthTab {
+    pub fn tls_options(&self) -> TlsOptions {
+        let imp = self.imp();
+
+        let mut options = imp.tls_group.tls_options();
+        imp.websocket_group.fill_tls_options(&mut options);
+
+        options
+    }
+
+    pub fn set_tls_options(&self, options: &TlsOptions) {
+        let imp = self.imp();
+
+        imp.tls_group.set_tls_options(options);
+        imp.websocket_group.set_tls_options(options);
+    }
+}
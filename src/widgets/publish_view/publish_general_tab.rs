@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::sync::LazyLock;
 
 use adw::prelude::*;
@@ -44,6 +44,24 @@ mod imp {
         #[property(get, set)]
         password: RefCell<String>,
 
+        #[property(get, set)]
+        retained: Cell<bool>,
+
+        #[property(get, set)]
+        message_expiry_interval: Cell<u32>,
+
+        #[property(get, set)]
+        response_topic: RefCell<String>,
+
+        #[property(get, set)]
+        correlation_data: RefCell<String>,
+
+        #[property(get, set)]
+        payload_format_utf8: Cell<bool>,
+
+        #[property(get, set, maximum = u16::MAX as u32)]
+        topic_alias: Cell<u32>,
+
         #[template_child]
         pub profile_combo: TemplateChild<adw::ComboRow>,
 
@@ -192,4 +210,18 @@ impl MQTTyPublishGeneralTab {
             }
         ));
     }
+
+    /// Selects the profile in the combo, loading it as if the user picked it, returns
+    /// false if it's not a saved profile
+    pub fn select_profile(&self, conn: &MQTTySettingConnection) -> bool {
+        let connections = MQTTyApplication::get_singleton().settings_connections();
+
+        let Some(position) = connections.find(conn) else {
+            return false;
+        };
+
+        // The first entry of the combo is "(None)"
+        self.imp().profile_combo.set_selected(position + 1);
+        true
+    }
 }
@@ -0,0 +1,559 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, OnceCell};
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::application::MQTTyApplication;
+use crate::client::{
+    MQTTyClient, MQTTyClientError, MQTTyClientMessage, MQTTyClientQos, PAYLOAD_ENCODING_BASE64,
+};
+use crate::gsettings::MQTTySettingConnection;
+
+/// Longest uninterrupted wait between two messages, so that stopping takes effect soon
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Characters of the payload shown in the row of a message
+const PAYLOAD_PREVIEW_LEN: usize = 80;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyPublishImportDialog)]
+    pub struct MQTTyPublishImportDialog {
+        /// File the messages were imported from
+        #[property(get, construct_only)]
+        file: OnceCell<gio::File>,
+
+        /// Queue of messages to publish, in order
+        pub messages: OnceCell<gio::ListStore>,
+
+        pub running: Cell<bool>,
+
+        pub stop_requested: Cell<bool>,
+
+        pub profile_row: OnceCell<adw::ComboRow>,
+
+        pub delay_row: OnceCell<adw::SpinRow>,
+
+        pub messages_group: OnceCell<adw::PreferencesGroup>,
+
+        pub list_box: OnceCell<gtk::ListBox>,
+
+        pub status_label: OnceCell<gtk::Label>,
+
+        pub progress_bar: OnceCell<gtk::ProgressBar>,
+
+        pub start_button: OnceCell<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyPublishImportDialog {
+        const NAME: &'static str = "MQTTyPublishImportDialog";
+        type Type = super::MQTTyPublishImportDialog;
+        type ParentType = adw::Dialog;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyPublishImportDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().setup_ui();
+        }
+    }
+    impl WidgetImpl for MQTTyPublishImportDialog {}
+    impl AdwDialogImpl for MQTTyPublishImportDialog {
+        fn closed(&self) {
+            self.stop_requested.set(true);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Previews and edits the messages imported from a file, then publishes them in
+    /// order to the broker of a saved profile
+    pub struct MQTTyPublishImportDialog(ObjectSubclass<imp::MQTTyPublishImportDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl MQTTyPublishImportDialog {
+    pub fn new(file: &gio::File, messages: &[MQTTyClientMessage]) -> Self {
+        let obj = glib::Object::builder::<Self>()
+            .property("file", file)
+            .build();
+
+        obj.imp()
+            .messages
+            .get()
+            .unwrap()
+            .extend_from_slice(messages);
+
+        obj
+    }
+
+    fn setup_ui(&self) {
+        let imp = self.imp();
+
+        self.set_content_width(600);
+        self.set_content_height(700);
+
+        let title = adw::WindowTitle::new(
+            &gettext("Import Messages"),
+            &self
+                .file()
+                .basename()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        );
+
+        let header_bar = adw::HeaderBar::new();
+        header_bar.set_title_widget(Some(&title));
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&header_bar);
+
+        let page = adw::PreferencesPage::new();
+
+        // Broker
+        let profile_row = adw::ComboRow::builder()
+            .title(gettext("Profile"))
+            .subtitle(gettext(
+                "Messages are published with the client of the profile",
+            ))
+            .model(MQTTyApplication::get_singleton().settings_connections())
+            .expression(gtk::PropertyExpression::new(
+                MQTTySettingConnection::static_type(),
+                None::<&gtk::Expression>,
+                "name",
+            ))
+            .build();
+
+        let delay_row = adw::SpinRow::builder()
+            .title(gettext("Delay"))
+            .subtitle(gettext("Milliseconds between two messages"))
+            .adjustment(&gtk::Adjustment::new(
+                100.0, 0.0, 60_000.0, 10.0, 100.0, 0.0,
+            ))
+            .build();
+
+        let broker_group = adw::PreferencesGroup::builder()
+            .title(gettext("Broker"))
+            .build();
+        broker_group.add(&profile_row);
+        broker_group.add(&delay_row);
+        page.add(&broker_group);
+
+        // Progress
+        let progress_bar = gtk::ProgressBar::new();
+
+        let status_label = gtk::Label::builder()
+            .css_classes(["dim-label"])
+            .wrap(true)
+            .build();
+
+        let start_button = gtk::Button::builder()
+            .label(gettext("Publish"))
+            .css_classes(["pill", "suggested-action"])
+            .halign(gtk::Align::Center)
+            .build();
+
+        start_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| obj.on_start_clicked()
+        ));
+
+        let progress_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .build();
+        progress_box.append(&progress_bar);
+        progress_box.append(&status_label);
+        progress_box.append(&start_button);
+
+        let progress_group = adw::PreferencesGroup::new();
+        progress_group.add(&progress_box);
+        page.add(&progress_group);
+
+        // Messages
+        let messages = gio::ListStore::new::<MQTTyClientMessage>();
+
+        let list_box = gtk::ListBox::builder()
+            .css_classes(["boxed-list"])
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+
+        list_box.bind_model(
+            Some(&messages),
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or_panic]
+                move |item| obj.message_row(item.downcast_ref().unwrap())
+            ),
+        );
+
+        let messages_group = adw::PreferencesGroup::builder()
+            .title(gettext("Messages"))
+            .build();
+        messages_group.add(&list_box);
+        page.add(&messages_group);
+
+        messages.connect_items_changed(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_, _, _, _| obj.update_progress(0, 0)
+        ));
+
+        toolbar_view.set_content(Some(&page));
+        self.set_child(Some(&toolbar_view));
+
+        let _ = imp.messages.set(messages);
+        let _ = imp.profile_row.set(profile_row);
+        let _ = imp.delay_row.set(delay_row);
+        let _ = imp.messages_group.set(messages_group);
+        let _ = imp.list_box.set(list_box);
+        let _ = imp.status_label.set(status_label);
+        let _ = imp.progress_bar.set(progress_bar);
+        let _ = imp.start_button.set(start_button);
+
+        self.update_progress(0, 0);
+    }
+
+    /// Row showing the topic and a preview of the payload, expanded to edit them
+    fn message_row(&self, message: &MQTTyClientMessage) -> gtk::Widget {
+        let row = adw::ExpanderRow::builder()
+            .use_markup(false)
+            .subtitle_lines(1)
+            .build();
+
+        message
+            .bind_property("topic", &row, "title")
+            .sync_create()
+            .build();
+
+        let topic_row = adw::EntryRow::builder().title(gettext("Topic")).build();
+
+        message
+            .bind_property("topic", &topic_row, "text")
+            .bidirectional()
+            .sync_create()
+            .build();
+
+        // Binary payloads are edited as base64 text
+        let (payload, encoding) = message.encoded_body();
+
+        row.set_subtitle(&payload_preview(&payload));
+
+        let payload_row = adw::EntryRow::builder()
+            .title(if encoding == PAYLOAD_ENCODING_BASE64 {
+                gettext("Payload (base64)")
+            } else {
+                gettext("Payload")
+            })
+            .text(payload)
+            .build();
+
+        payload_row.connect_changed(glib::clone!(
+            #[weak]
+            message,
+            #[weak]
+            row,
+            move |payload_row| {
+                let text = payload_row.text();
+
+                if let Some(body) = MQTTyClientMessage::decode_body(&text, encoding) {
+                    message.set_body(&body);
+                }
+
+                row.set_subtitle(&payload_preview(&text));
+            }
+        ));
+
+        let qos_row = adw::ComboRow::builder()
+            .title(gettext("QoS"))
+            .model(&gtk::StringList::new(&["0", "1", "2"]))
+            .build();
+
+        message
+            .bind_property("qos", &qos_row, "selected")
+            .bidirectional()
+            .sync_create()
+            .transform_to(|_, qos: MQTTyClientQos| Some(qos as u32))
+            .transform_from(|_, selected: u32| {
+                Some(match selected {
+                    1 => MQTTyClientQos::Qos1,
+                    2 => MQTTyClientQos::Qos2,
+                    _ => MQTTyClientQos::Qos0,
+                })
+            })
+            .build();
+
+        let retain_row = adw::SwitchRow::builder().title(gettext("Retain")).build();
+
+        message
+            .bind_property("retained", &retain_row, "active")
+            .bidirectional()
+            .sync_create()
+            .build();
+
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        remove_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            message,
+            move |_| {
+                let messages = obj.imp().messages.get().unwrap();
+                let position = (0..messages.n_items()).find(|&i| {
+                    messages.item(i).as_ref() == Some(message.upcast_ref::<glib::Object>())
+                });
+
+                if let Some(position) = position {
+                    messages.remove(position);
+                }
+            }
+        ));
+
+        row.add_suffix(&remove_button);
+        row.add_row(&topic_row);
+        row.add_row(&payload_row);
+        row.add_row(&qos_row);
+        row.add_row(&retain_row);
+
+        row.upcast()
+    }
+
+    fn on_start_clicked(&self) {
+        let imp = self.imp();
+
+        if imp.running.get() {
+            imp.stop_requested.set(true);
+            return;
+        }
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let status = match obj.start().await {
+                    Ok(status) => status,
+                    Err(e) => formatx!(gettext("Publishing failed: {}"), e).unwrap(),
+                };
+
+                obj.imp().status_label.get().unwrap().set_label(&status);
+            }
+        ));
+    }
+
+    /// Publishes the queue, returns the report of the run
+    async fn start(&self) -> Result<String, String> {
+        let imp = self.imp();
+
+        let profile = imp
+            .profile_row
+            .get()
+            .unwrap()
+            .selected_item()
+            .and_downcast::<MQTTySettingConnection>()
+            .ok_or_else(|| gettext("There is no profile to publish with"))?;
+
+        let client = MQTTyApplication::get_singleton()
+            .connection_client(&profile)
+            .ok_or_else(|| gettext("There is no profile to publish with"))?;
+
+        let list_box = imp.list_box.get().unwrap();
+        let messages = imp
+            .messages
+            .get()
+            .unwrap()
+            .iter::<MQTTyClientMessage>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        for i in 0..messages.len() {
+            if let Some(row) = list_box.row_at_index(i as i32) {
+                row.remove_css_class("error");
+            }
+        }
+
+        imp.stop_requested.set(false);
+        self.set_running(true);
+
+        client
+            .ensure_connected()
+            .await
+            .inspect_err(|_| self.set_running(false))
+            .map_err(|e| e.to_string())?;
+
+        let (handled, failures) = self.publish_all(&client, &messages).await;
+
+        self.set_running(false);
+
+        let published = handled - failures.len();
+
+        let mut status = formatx!(
+            gettext("Published {} of {} messages"),
+            published,
+            messages.len()
+        )
+        .unwrap();
+
+        if let Some((i, e)) = failures.first() {
+            status.push('\n');
+            status.push_str(
+                &formatx!(
+                    gettext("{} failed, the first one to {}: {}"),
+                    failures.len(),
+                    messages[*i].topic(),
+                    e
+                )
+                .unwrap(),
+            );
+        }
+
+        Ok(status)
+    }
+
+    /// Publishes the messages in order, waiting the delay between them, a failed
+    /// message is marked and doesn't stop the rest
+    ///
+    /// Returns how many messages were handled before stopping, and the position of
+    /// every message that failed along with its error
+    async fn publish_all(
+        &self,
+        client: &MQTTyClient,
+        messages: &[MQTTyClientMessage],
+    ) -> (usize, Vec<(usize, MQTTyClientError)>) {
+        let imp = self.imp();
+        let list_box = imp.list_box.get().unwrap();
+
+        let delay = Duration::from_millis(imp.delay_row.get().unwrap().value() as u64);
+
+        let mut handled = 0;
+        let mut failures = vec![];
+
+        for (i, message) in messages.iter().enumerate() {
+            if imp.stop_requested.get() || (i > 0 && !self.wait(delay).await) {
+                break;
+            }
+
+            if let Err(e) = client.publish(message).await {
+                if let Some(row) = list_box.row_at_index(i as i32) {
+                    row.add_css_class("error");
+                }
+                failures.push((i, e));
+            }
+
+            handled += 1;
+            self.update_progress(handled, messages.len());
+        }
+
+        (handled, failures)
+    }
+
+    /// Returns false if publishing was stopped in the meantime
+    async fn wait(&self, delay: Duration) -> bool {
+        let mut left = delay;
+
+        while !left.is_zero() {
+            if self.imp().stop_requested.get() {
+                return false;
+            }
+
+            let step = left.min(STOP_CHECK_INTERVAL);
+            glib::timeout_future(step).await;
+            left -= step;
+        }
+
+        !self.imp().stop_requested.get()
+    }
+
+    fn set_running(&self, running: bool) {
+        let imp = self.imp();
+
+        imp.running.set(running);
+
+        // The queue can't be edited while it's published
+        imp.messages_group.get().unwrap().set_sensitive(!running);
+        imp.profile_row.get().unwrap().set_sensitive(!running);
+
+        let start_button = imp.start_button.get().unwrap();
+
+        if running {
+            start_button.set_label(&gettext("Stop"));
+            start_button.remove_css_class("suggested-action");
+            start_button.add_css_class("destructive-action");
+        } else {
+            start_button.set_label(&gettext("Publish"));
+            start_button.remove_css_class("destructive-action");
+            start_button.add_css_class("suggested-action");
+        }
+    }
+
+    /// Shows how many of the messages were handled so far
+    fn update_progress(&self, done: usize, total: usize) {
+        let imp = self.imp();
+
+        let (Some(progress_bar), Some(messages_group), Some(start_button), Some(messages)) = (
+            imp.progress_bar.get(),
+            imp.messages_group.get(),
+            imp.start_button.get(),
+            imp.messages.get(),
+        ) else {
+            return;
+        };
+
+        let n_items = messages.n_items();
+
+        messages_group.set_description(Some(
+            &formatx!(gettext("{} messages, expand one to edit it"), n_items).unwrap(),
+        ));
+        start_button.set_sensitive(n_items > 0 || imp.running.get());
+
+        progress_bar.set_fraction(if total == 0 {
+            0.0
+        } else {
+            done as f64 / total as f64
+        });
+        progress_bar.set_text(Some(&formatx!(gettext("{} of {}"), done, total).unwrap()));
+        progress_bar.set_show_text(total > 0);
+    }
+}
+
+/// First line of the payload, cut to PAYLOAD_PREVIEW_LEN characters
+fn payload_preview(payload: &str) -> String {
+    let line = payload.lines().next().unwrap_or_default();
+
+    match line.char_indices().nth(PAYLOAD_PREVIEW_LEN) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
@@ -13,18 +13,24 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::cell::{Cell, OnceCell, RefCell};
+use std::cell::{Cell, RefCell};
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
 use gtk::{gio, glib};
 
-use crate::client::{MQTTyClient, MQTTyClientMessage, MQTTyClientQos, MQTTyClientVersion};
+use crate::application::MQTTyApplication;
+use crate::client::{
+    non_empty, MQTTyClient, MQTTyClientError, MQTTyClientMessage, MQTTyClientParams,
+    MQTTyClientQos, MQTTyClientVersion,
+};
 use crate::content_type::MQTTyContentType;
 use crate::display_mode::{MQTTyDisplayMode, MQTTyDisplayModeIface};
 use crate::gsettings::MQTTySettingConnection;
 use crate::subclass::prelude::*;
-use crate::widgets::{MQTTyPublishGeneralTab, MQTTyPublishUserPropsTab};
+use crate::widgets::{MQTTyPublishAuthTab, MQTTyPublishGeneralTab, MQTTyPublishUserPropsTab};
 
 mod imp {
 
@@ -34,7 +40,17 @@ mod imp {
     #[template(resource = "/io/github/otaxhu/MQTTy/ui/publish_view/publish_view_notebook.ui")]
     #[properties(wrapper_type = super::MQTTyPublishViewNotebook)]
     pub struct MQTTyPublishViewNotebook {
-        pub client: OnceCell<MQTTyClient>,
+        /// Client owned by this tab, only used when the tab parameters doesn't match any
+        /// saved profile, it's kept alive between sends
+        pub client: RefCell<Option<MQTTyClient>>,
+
+        /// Profile last loaded into this tab, its application-managed client is used as
+        /// long as the tab parameters still match it
+        pub profile: RefCell<Option<MQTTySettingConnection>>,
+
+        /// Client used by the last send, either the profile one or the tab owned one
+        #[property(get, set, nullable)]
+        current_client: RefCell<Option<MQTTyClient>>,
 
         #[property(get, set, override_interface = MQTTyDisplayModeIface)]
         display_mode: Cell<MQTTyDisplayMode>,
@@ -51,6 +67,25 @@ mod imp {
         #[property(get, set, builder(Default::default()))]
         qos: Cell<MQTTyClientQos>,
 
+        #[property(get, set)]
+        retained: Cell<bool>,
+
+        #[property(get, set)]
+        message_expiry_interval: Cell<u32>,
+
+        #[property(get, set)]
+        response_topic: RefCell<String>,
+
+        /// Sent as the UTF-8 bytes of the text
+        #[property(get, set)]
+        correlation_data: RefCell<String>,
+
+        #[property(get, set)]
+        payload_format_utf8: Cell<bool>,
+
+        #[property(get, set, maximum = u16::MAX as u32)]
+        topic_alias: Cell<u32>,
+
         #[property(get, set)]
         body: RefCell<String>,
 
@@ -71,6 +106,9 @@ mod imp {
 
         #[template_child]
         pub general_tab: TemplateChild<MQTTyPublishGeneralTab>,
+
+        #[template_child]
+        pub auth_tab: TemplateChild<MQTTyPublishAuthTab>,
     }
 
     impl Default for MQTTyPublishViewNotebook {
@@ -81,7 +119,15 @@ mod imp {
                 topic: Default::default(),
                 url: Default::default(),
                 qos: Default::default(),
+                retained: Default::default(),
+                message_expiry_interval: Default::default(),
+                response_topic: Default::default(),
+                correlation_data: Default::default(),
+                payload_format_utf8: Default::default(),
+                topic_alias: Default::default(),
                 client: Default::default(),
+                profile: Default::default(),
+                current_client: Default::default(),
                 body: Default::default(),
                 content_type: Default::default(),
                 user_properties_tab: Default::default(),
@@ -89,6 +135,7 @@ mod imp {
                 password: Default::default(),
                 user_properties_stack: Default::default(),
                 general_tab: Default::default(),
+                auth_tab: Default::default(),
             }
         }
     }
@@ -200,28 +247,29 @@ mod imp {
                 glib::closure_local!(
                     #[weak]
                     obj,
-                    #[weak]
-                    mqtt_version_state,
-                    #[weak]
-                    qos_state,
                     move |_tab: MQTTyPublishGeneralTab, conn: MQTTySettingConnection| {
+                        obj.imp().profile.replace(Some(conn.clone()));
+
                         // Update all the notebook properties from the profile
                         obj.set_topic(conn.topic());
                         obj.set_url(conn.url());
                         obj.set_username(conn.username());
                         obj.set_password(conn.password());
+                        obj.imp().auth_tab.set_tls_options(&conn.tls_options());
 
-                        // Update MQTT version action state
-                        let version = conn.mqtt_version();
-                        mqtt_version_state.change_state(&version.into());
-
-                        // Update QoS action state
-                        let qos = conn.qos();
-                        qos_state.change_state(&qos.into());
+                        // Action states follow through their bindings
+                        obj.set_mqtt_version(conn.mqtt_version());
+                        obj.set_qos(conn.qos());
                     }
                 ),
             );
         }
+
+        fn dispose(&self) {
+            if let Some(client) = self.client.take() {
+                client.disconnect_in_background();
+            }
+        }
     }
     impl WidgetImpl for MQTTyPublishViewNotebook {}
     impl BinImpl for MQTTyPublishViewNotebook {}
@@ -239,22 +287,24 @@ impl MQTTyPublishViewNotebook {
         glib::Object::builder().build()
     }
 
-    pub async fn send(&self) -> Result<(), String> {
+    /// Loads the saved profile as if the user picked it, returns false if it's not a
+    /// saved profile
+    pub fn load_profile(&self, conn: &MQTTySettingConnection) -> bool {
+        self.imp().general_tab.select_profile(conn)
+    }
+
+    pub async fn send(&self) -> Result<(), MQTTyClientError> {
         let mqtt_version = self.mqtt_version();
 
-        let client = MQTTyClient::new(
-            &self.url(),
-            mqtt_version,
-            &self.username(),
-            &self.password(),
-        );
+        let client = self.resolve_client();
 
-        client.connect_client().await?;
+        client.ensure_connected().await?;
 
         let msg = MQTTyClientMessage::new();
 
         msg.set_topic(self.topic());
         msg.set_qos(self.qos());
+        msg.set_retained(self.retained());
         if self.content_type() != MQTTyContentType::None {
             msg.set_body(self.body().as_ref());
         }
@@ -263,6 +313,15 @@ impl MQTTyPublishViewNotebook {
         // Specific to MQTT v5
         if mqtt_version == MQTTyClientVersion::V5 {
             msg.set_content_type(self.content_type().mime_type());
+            msg.set_message_expiry_interval(self.message_expiry_interval());
+            msg.set_response_topic(non_empty(self.response_topic()).as_deref());
+            msg.set_correlation_data(
+                non_empty(self.correlation_data())
+                    .as_ref()
+                    .map(String::as_bytes),
+            );
+            msg.set_payload_format_utf8(self.payload_format_utf8());
+            msg.set_topic_alias(self.topic_alias());
             msg.set_user_properties(
                 self.imp()
                     .user_properties_tab
@@ -272,8 +331,79 @@ impl MQTTyPublishViewNotebook {
                     .collect::<Vec<_>>()
                     .as_ref(),
             );
+
+            check_v5_properties(&client, &msg)?;
         }
 
         client.publish(&msg).await
     }
+
+    /// Picks the client matching the current tab parameters, the application-managed client
+    /// of the loaded profile is preferred, so that it's shared with other tabs, otherwise
+    /// a client owned by this tab is (re)created
+    fn resolve_client(&self) -> MQTTyClient {
+        let imp = self.imp();
+
+        let params = MQTTyClientParams {
+            url: self.url(),
+            mqtt_version: self.mqtt_version(),
+            username: self.username(),
+            password: self.password(),
+            tls_options: imp.auth_tab.tls_options(),
+        };
+
+        // Bound apart, the closure below replaces it
+        let owned = imp.client.borrow().clone();
+
+        let client = MQTTyApplication::get_singleton()
+            .resolve_client(imp.profile.borrow().as_ref(), owned, &params)
+            .unwrap_or_else(|| {
+                let client = params.new_client();
+
+                if let Some(old) = imp.client.replace(Some(client.clone())) {
+                    old.disconnect_in_background();
+                }
+
+                client
+            });
+
+        self.set_current_client(Some(&client));
+
+        client
+    }
+}
+
+/// Checks the properties the broker would otherwise answer by closing the connection
+fn check_v5_properties(
+    client: &MQTTyClient,
+    msg: &MQTTyClientMessage,
+) -> Result<(), MQTTyClientError> {
+    // UTF-8 character data must not hold the null character either (MQTT v5 1.5.4)
+    let body = msg.body();
+    let utf8 = std::str::from_utf8(&body).is_ok_and(|text| !text.contains('\0'));
+
+    if msg.payload_format_utf8() && !utf8 {
+        return Err(MQTTyClientError::InvalidOptions(gettext(
+            "The payload is marked as UTF-8 but it is not valid UTF-8 text",
+        )));
+    }
+
+    // A broker that didn't send a Topic Alias Maximum accepts no alias
+    let topic_alias_maximum = client
+        .connection_info()
+        .and_then(|info| info.topic_alias_maximum)
+        .unwrap_or_default();
+
+    if msg.topic_alias() > topic_alias_maximum {
+        return Err(MQTTyClientError::InvalidOptions(
+            formatx!(
+                gettext("The topic alias {} is above the maximum of the broker, {}"),
+                msg.topic_alias(),
+                topic_alias_maximum
+            )
+            .unwrap(),
+        ));
+    }
+
+    Ok(())
 }
@@ -0,0 +1,327 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::OnceCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::application::MQTTyApplication;
+use crate::gsettings::MQTTySettingConnection;
+use crate::recording::{self, MQTTyReplay};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyReplayDialog)]
+    pub struct MQTTyReplayDialog {
+        /// Recording to replay
+        #[property(get, construct_only)]
+        file: OnceCell<gio::File>,
+
+        pub replay: MQTTyReplay,
+
+        pub profile_row: OnceCell<adw::ComboRow>,
+
+        pub status_label: OnceCell<gtk::Label>,
+
+        pub progress_bar: OnceCell<gtk::ProgressBar>,
+
+        pub start_button: OnceCell<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyReplayDialog {
+        const NAME: &'static str = "MQTTyReplayDialog";
+        type Type = super::MQTTyReplayDialog;
+        type ParentType = adw::Dialog;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyReplayDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().setup_ui();
+        }
+    }
+    impl WidgetImpl for MQTTyReplayDialog {}
+    impl AdwDialogImpl for MQTTyReplayDialog {
+        fn closed(&self) {
+            self.replay.stop();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Replays a recording to the broker of a saved profile
+    pub struct MQTTyReplayDialog(ObjectSubclass<imp::MQTTyReplayDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl MQTTyReplayDialog {
+    pub fn new(file: &gio::File) -> Self {
+        glib::Object::builder().property("file", file).build()
+    }
+
+    fn setup_ui(&self) {
+        let imp = self.imp();
+        let replay = &imp.replay;
+
+        self.set_title(&gettext("Replay Recording"));
+        self.set_content_width(500);
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&adw::HeaderBar::new());
+
+        let page = adw::PreferencesPage::new();
+
+        // Broker
+        let profile_row = adw::ComboRow::builder()
+            .title(gettext("Profile"))
+            .subtitle(gettext(
+                "Messages are published with the client of the profile",
+            ))
+            .model(MQTTyApplication::get_singleton().settings_connections())
+            .expression(gtk::PropertyExpression::new(
+                MQTTySettingConnection::static_type(),
+                None::<&gtk::Expression>,
+                "name",
+            ))
+            .build();
+
+        let broker_group = adw::PreferencesGroup::builder()
+            .title(gettext("Broker"))
+            .build();
+        broker_group.add(&profile_row);
+        page.add(&broker_group);
+
+        // Timing
+        let speed_row = adw::SpinRow::builder()
+            .title(gettext("Speed"))
+            .subtitle(gettext(
+                "Times the original pace, 0 publishes without waiting",
+            ))
+            .digits(2)
+            .adjustment(&gtk::Adjustment::new(1.0, 0.0, 1000.0, 0.25, 1.0, 0.0))
+            .build();
+
+        replay
+            .bind_property("speed", &speed_row, "value")
+            .bidirectional()
+            .sync_create()
+            .build();
+
+        let repeat_row = adw::SwitchRow::builder()
+            .title(gettext("Loop"))
+            .subtitle(gettext("Start over after the last message, until stopped"))
+            .build();
+
+        replay
+            .bind_property("repeat", &repeat_row, "active")
+            .bidirectional()
+            .sync_create()
+            .build();
+
+        let timing_group = adw::PreferencesGroup::builder()
+            .title(gettext("Timing"))
+            .build();
+        timing_group.add(&speed_row);
+        timing_group.add(&repeat_row);
+        page.add(&timing_group);
+
+        // Topics
+        let topics_group = adw::PreferencesGroup::builder()
+            .title(gettext("Topics"))
+            .description(gettext(
+                "Topics starting with the prefix get it replaced, leave it empty to prepend the new one",
+            ))
+            .build();
+
+        for (prop, title) in [
+            ("topic_filter", gettext("Only Topics Matching")),
+            ("topic_prefix", gettext("Prefix")),
+            ("new_topic_prefix", gettext("New Prefix")),
+        ] {
+            let row = adw::EntryRow::builder().title(title).build();
+
+            replay
+                .bind_property(prop, &row, "text")
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            topics_group.add(&row);
+        }
+
+        let keep_retain_row = adw::SwitchRow::builder()
+            .title(gettext("Keep Retain Flag"))
+            .subtitle(gettext(
+                "Retained messages replace the ones the broker holds for their topics",
+            ))
+            .build();
+
+        replay
+            .bind_property("keep_retain", &keep_retain_row, "active")
+            .bidirectional()
+            .sync_create()
+            .build();
+
+        topics_group.add(&keep_retain_row);
+
+        page.add(&topics_group);
+
+        // Progress
+        let progress_bar = gtk::ProgressBar::new();
+
+        let status_label = gtk::Label::builder()
+            .css_classes(["dim-label"])
+            .wrap(true)
+            .build();
+
+        let start_button = gtk::Button::builder()
+            .label(gettext("Replay"))
+            .css_classes(["pill", "suggested-action"])
+            .halign(gtk::Align::Center)
+            .build();
+
+        start_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| obj.on_start_clicked()
+        ));
+
+        let progress_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .build();
+        progress_box.append(&progress_bar);
+        progress_box.append(&status_label);
+        progress_box.append(&start_button);
+
+        let progress_group = adw::PreferencesGroup::new();
+        progress_group.add(&progress_box);
+        page.add(&progress_group);
+
+        for prop in ["published", "total", "running"] {
+            replay.connect_notify_local(
+                Some(prop),
+                glib::clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move |_, _| obj.update_progress()
+                ),
+            );
+        }
+
+        toolbar_view.set_content(Some(&page));
+        self.set_child(Some(&toolbar_view));
+
+        let _ = imp.profile_row.set(profile_row);
+        let _ = imp.status_label.set(status_label);
+        let _ = imp.progress_bar.set(progress_bar);
+        let _ = imp.start_button.set(start_button);
+
+        self.update_progress();
+    }
+
+    fn on_start_clicked(&self) {
+        let replay = &self.imp().replay;
+
+        if replay.running() {
+            replay.stop();
+            return;
+        }
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let status = match obj.start().await {
+                    Ok(published) => formatx!(gettext("Replayed {} messages"), published).unwrap(),
+                    Err(e) => formatx!(gettext("Replay failed: {}"), e).unwrap(),
+                };
+
+                obj.imp().status_label.get().unwrap().set_label(&status);
+            }
+        ));
+    }
+
+    async fn start(&self) -> Result<u32, String> {
+        let imp = self.imp();
+
+        let profile = imp
+            .profile_row
+            .get()
+            .unwrap()
+            .selected_item()
+            .and_downcast::<MQTTySettingConnection>()
+            .ok_or_else(|| gettext("There is no profile to replay with"))?;
+
+        let client = MQTTyApplication::get_singleton()
+            .connection_client(&profile)
+            .ok_or_else(|| gettext("There is no profile to replay with"))?;
+
+        let messages = recording::read_recording(&self.file())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        client.ensure_connected().await.map_err(|e| e.to_string())?;
+
+        imp.replay
+            .run(&client, &messages)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn update_progress(&self) {
+        let imp = self.imp();
+        let replay = &imp.replay;
+
+        let (Some(progress_bar), Some(start_button)) =
+            (imp.progress_bar.get(), imp.start_button.get())
+        else {
+            return;
+        };
+
+        let total = replay.total();
+        let published = replay.published();
+
+        progress_bar.set_fraction(if total == 0 {
+            0.0
+        } else {
+            published as f64 / total as f64
+        });
+        progress_bar.set_text(Some(
+            &formatx!(gettext("{} of {}"), published, total).unwrap(),
+        ));
+        progress_bar.set_show_text(replay.running());
+
+        if replay.running() {
+            start_button.set_label(&gettext("Stop"));
+            start_button.remove_css_class("suggested-action");
+            start_button.add_css_class("destructive-action");
+        } else {
+            start_button.set_label(&gettext("Replay"));
+            start_button.remove_css_class("destructive-action");
+            start_button.add_css_class("suggested-action");
+        }
+    }
+}
@@ -17,11 +17,13 @@ mod message_row;
 mod subscribe_auth_tab;
 mod subscribe_general_tab;
 mod subscribe_view_notebook;
+mod topic_filter_row;
 
 pub use message_row::MQTTyMessageRow;
 pub use subscribe_auth_tab::MQTTySubscribeAuthTab;
 pub use subscribe_general_tab::MQTTySubscribeGeneralTab;
 pub use subscribe_view_notebook::MQTTySubscribeViewNotebook;
+pub use topic_filter_row::MQTTyTopicFilterRow;
 
 use std::cell::Cell;
 
@@ -29,17 +31,17 @@ use adw::prelude::*;
 use adw::subclass::prelude::*;
 use formatx::formatx;
 use gettextrs::gettext;
-use gtk::glib;
+use gtk::{gio, glib};
 
 use crate::application::MQTTyApplication;
 use crate::display_mode::{MQTTyDisplayMode, MQTTyDisplayModeIface};
 use crate::main_window::MQTTyWindow;
+use crate::recording;
 use crate::subclass::prelude::*;
+use crate::toast::MQTTyToastBuilder;
 
 mod imp {
 
-    use crate::toast::MQTTyToastBuilder;
-
     use super::*;
 
     #[derive(gtk::CompositeTemplate, glib::Properties)]
@@ -82,44 +84,17 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.install_action("subscribe-view.new-tab", None, |this, _, _| {
-                let notebook = MQTTySubscribeViewNotebook::new();
-                this.bind_property("display_mode", &notebook, "display_mode")
-                    .sync_create()
-                    .build();
-
-                let topic_expr = notebook
-                    .property_expression_weak("topic")
-                    .chain_closure::<String>(glib::closure!(
-                        move |_: Option<glib::Object>, topic: String| {
-                            if topic.is_empty() {
-                                gettext("(untitled)")
-                            } else {
-                                topic
-                            }
-                        }
-                    ));
-
-                let page = this.imp().tab_view.append(&notebook);
-
-                topic_expr.bind(&page, "title", glib::Object::NONE);
-
-                // We create a tooltip based on topic and url values
-                gtk::ClosureExpression::new::<String>(
-                    [
-                        topic_expr.upcast(),
-                        notebook.property_expression_weak("url").upcast(),
-                    ],
-                    glib::closure!(move |_: Option<glib::Object>, topic: String, url: String| {
-                        if url.is_empty() {
-                            topic
-                        } else {
-                            [topic, url].join("\r\n")
-                        }
-                    }),
-                )
-                .bind(&page, "tooltip", glib::Object::NONE);
+                this.append_notebook(&MQTTySubscribeViewNotebook::new());
             });
 
+            klass.install_action_async(
+                "subscribe-view.open-recording",
+                None,
+                |this, _, _| async move {
+                    this.open_recording().await;
+                },
+            );
+
             klass.install_action("subscribe-view.subscribe", None, |this, _, _| {
                 let notebook = this
                     .imp()
@@ -198,9 +173,25 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
+            let obj = self.obj();
+
             let stack = &self.stack;
             let subscribe_button = &self.subscribe_button;
 
+            // Recordings can't be subscribed
+            self.tab_view.connect_selected_page_notify(glib::clone!(
+                #[weak]
+                obj,
+                move |tab_view| {
+                    let read_only = tab_view
+                        .selected_page()
+                        .and_then(|page| page.child().downcast::<MQTTySubscribeViewNotebook>().ok())
+                        .is_some_and(|notebook| notebook.read_only());
+
+                    obj.action_set_enabled("subscribe-view.subscribe", !read_only);
+                }
+            ));
+
             self.tab_view.connect_n_pages_notify(glib::clone!(
                 #[weak]
                 stack,
@@ -213,6 +204,27 @@ mod imp {
                     subscribe_button.set_visible(n_pages != 0);
                 }
             ));
+
+            // Closing waits for the tab to unsubscribe and disconnect
+            self.tab_view.connect_close_page(|tab_view, page| {
+                let notebook = page
+                    .child()
+                    .downcast::<MQTTySubscribeViewNotebook>()
+                    .unwrap();
+
+                glib::spawn_future_local(glib::clone!(
+                    #[weak]
+                    tab_view,
+                    #[weak]
+                    page,
+                    async move {
+                        notebook.close().await;
+                        tab_view.close_page_finish(&page, true);
+                    }
+                ));
+
+                glib::Propagation::Stop
+            });
         }
     }
     impl WidgetImpl for MQTTySubscribeView {}
@@ -225,3 +237,105 @@ glib::wrapper! {
         @extends gtk::Widget, adw::Bin,
         @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
 }
+
+impl MQTTySubscribeView {
+    fn append_notebook(&self, notebook: &MQTTySubscribeViewNotebook) -> adw::TabPage {
+        self.bind_property("display_mode", notebook, "display_mode")
+            .sync_create()
+            .build();
+
+        let page = self.imp().tab_view.append(notebook);
+
+        // Recordings are named after their file
+        if notebook.read_only() {
+            return page;
+        }
+
+        let topic_expr = notebook
+            .property_expression_weak("topic")
+            .chain_closure::<String>(glib::closure!(
+                move |_: Option<glib::Object>, topic: String| {
+                    if topic.is_empty() {
+                        gettext("(untitled)")
+                    } else {
+                        topic
+                    }
+                }
+            ));
+
+        topic_expr.bind(&page, "title", glib::Object::NONE);
+
+        // We create a tooltip based on topic and url values
+        gtk::ClosureExpression::new::<String>(
+            [
+                topic_expr.upcast(),
+                notebook.property_expression_weak("url").upcast(),
+            ],
+            glib::closure!(move |_: Option<glib::Object>, topic: String, url: String| {
+                if url.is_empty() {
+                    topic
+                } else {
+                    [topic, url].join("\r\n")
+                }
+            }),
+        )
+        .bind(&page, "tooltip", glib::Object::NONE);
+
+        page
+    }
+
+    /// Opens a recording in a read-only tab
+    async fn open_recording(&self) {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Open Recording"))
+            .filters(&recording::file_filters())
+            .modal(true)
+            .build();
+
+        let window = self.root().and_downcast::<MQTTyWindow>();
+
+        // Dismissed by the user
+        let Ok(file) = dialog.open_future(window.as_ref()).await else {
+            return;
+        };
+
+        let messages = match recording::read_recording(&file).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                let toast = MQTTyToastBuilder::new()
+                    .title(formatx!(gettext("Failed to open the recording: {}"), e).unwrap())
+                    .icon(
+                        gtk::Image::builder()
+                            .icon_name("dialog-error-symbolic")
+                            .build()
+                            .as_ref(),
+                    )
+                    .timeout(3)
+                    .build();
+
+                if let Some(window) = window {
+                    window.toast(&toast);
+                }
+                return;
+            }
+        };
+
+        let notebook = glib::Object::builder::<MQTTySubscribeViewNotebook>()
+            .property("read_only", true)
+            .build();
+
+        let page = self.append_notebook(&notebook);
+
+        let name = file
+            .basename()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| gettext("Recording"));
+        page.set_title(&name);
+        page.set_tooltip(&file.parse_name());
+        page.set_icon(Some(&gio::ThemedIcon::new("media-record-symbolic")));
+
+        notebook.load_recording(&file, &messages);
+
+        self.imp().tab_view.set_selected_page(&page);
+    }
+}
@@ -17,6 +17,8 @@ use std::cell::{Cell, RefCell};
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
 use gtk::glib;
 
 use crate::client::{MQTTyClientMessage, MQTTyClientQos};
@@ -44,6 +46,11 @@ mod imp {
         #[property(get, set)]
         retained: Cell<bool>,
 
+        /// Member of the share group that received the message, empty outside of
+        /// share group mode
+        #[property(get, set)]
+        member: RefCell<String>,
+
         /// Store the full message for later access
         pub message: RefCell<Option<MQTTyClientMessage>>,
     }
@@ -58,6 +65,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &glib::subclass::types::InitializingObject<Self>) {
@@ -69,6 +77,14 @@ mod imp {
     impl ObjectImpl for MQTTyMessageRow {}
     impl WidgetImpl for MQTTyMessageRow {}
     impl BoxImpl for MQTTyMessageRow {}
+
+    #[gtk::template_callbacks]
+    impl MQTTyMessageRow {
+        #[template_callback]
+        fn has_member(&self, member: &str) -> bool {
+            !member.is_empty()
+        }
+    }
 }
 
 glib::wrapper! {
@@ -82,24 +98,39 @@ impl MQTTyMessageRow {
         glib::Object::builder().build()
     }
 
-    pub fn from_message(message: &MQTTyClientMessage) -> Self {
-        let row = Self::new();
+    /// Shows the message in the row, rows are recycled by the list, so they are
+    /// rebound to other messages while scrolling
+    pub fn set_message(&self, message: Option<&MQTTyClientMessage>) {
+        self.imp().message.replace(message.cloned());
+
+        let Some(message) = message else {
+            self.set_topic("");
+            self.set_body_preview("");
+            self.set_timestamp("");
+            self.set_qos_label("");
+            self.set_retained(false);
+            self.set_member("");
+            return;
+        };
 
-        row.set_topic(message.topic());
+        self.set_topic(message.topic());
 
         // Create a preview of the body (first 100 chars)
         let body = message.body();
         let body_str = String::from_utf8_lossy(&body);
-        let preview = if body_str.len() > 100 {
-            format!("{}...", &body_str[..100])
-        } else {
-            body_str.to_string()
+        let preview = match body_str.char_indices().nth(100) {
+            Some((i, _)) => format!("{}...", &body_str[..i]),
+            None => body_str.to_string(),
         };
-        row.set_body_preview(preview);
+        self.set_body_preview(preview);
 
         // Format timestamp
-        let now = glib::DateTime::now_local().unwrap();
-        row.set_timestamp(now.format("%H:%M:%S").unwrap().to_string());
+        let timestamp = message
+            .received_at()
+            .and_then(|received_at| received_at.format("%H:%M:%S").ok())
+            .map(|timestamp| timestamp.to_string())
+            .unwrap_or_default();
+        self.set_timestamp(timestamp);
 
         // Format QoS label
         let qos_str = match message.qos() {
@@ -107,14 +138,15 @@ impl MQTTyMessageRow {
             MQTTyClientQos::Qos1 => "QoS 1",
             MQTTyClientQos::Qos2 => "QoS 2",
         };
-        row.set_qos_label(qos_str.to_string());
+        self.set_qos_label(qos_str.to_string());
 
-        row.set_retained(message.retained());
+        self.set_retained(message.retained());
 
-        // Store the full message
-        row.imp().message.replace(Some(message.clone()));
-
-        row
+        let member = match message.share_member() {
+            0 => String::new(),
+            member => formatx!(gettext("Member {}"), member).unwrap(),
+        };
+        self.set_member(member);
     }
 
     pub fn message(&self) -> Option<MQTTyClientMessage> {
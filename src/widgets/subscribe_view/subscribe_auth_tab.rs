@@ -13,12 +13,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::cell::{Cell, RefCell};
+use std::cell::RefCell;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
-use gettextrs::gettext;
-use gtk::{gio, glib};
+use gtk::glib;
+
+use crate::client::TlsOptions;
+use crate::widgets::{MQTTyTlsGroup, MQTTyWebSocketGroup};
 
 mod imp {
 
@@ -34,17 +36,11 @@ mod imp {
         #[property(get, set)]
         password: RefCell<String>,
 
-        #[property(get, set)]
-        enable_tls: Cell<bool>,
-
-        #[property(get, set)]
-        ca_cert_path: RefCell<String>,
+        #[template_child]
+        pub tls_group: TemplateChild<MQTTyTlsGroup>,
 
-        #[property(get, set)]
-        client_cert_path: RefCell<String>,
-
-        #[property(get, set)]
-        client_key_path: RefCell<String>,
+        #[template_child]
+        pub websocket_group: TemplateChild<MQTTyWebSocketGroup>,
     }
 
     #[glib::object_subclass]
@@ -57,7 +53,6 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
-            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &glib::subclass::types::InitializingObject<Self>) {
@@ -69,81 +64,28 @@ mod imp {
     impl ObjectImpl for MQTTySubscribeAuthTab {}
     impl WidgetImpl for MQTTySubscribeAuthTab {}
     impl BinImpl for MQTTySubscribeAuthTab {}
+}
 
-    #[gtk::template_callbacks]
-    impl MQTTySubscribeAuthTab {
-        #[template_callback]
-        fn on_ca_cert_clicked(&self) {
-            self.open_file_dialog("ca_cert_path", gettext("Select CA Certificate"));
-        }
-
-        #[template_callback]
-        fn on_client_cert_clicked(&self) {
-            self.open_file_dialog("client_cert_path", gettext("Select Client Certificate"));
-        }
+glib::wrapper! {
+    pub struct MQTTySubscribeAuthTab(ObjectSubclass<imp::MQTTySubscribeAuthTab>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
 
-        #[template_callback]
-        fn on_client_key_clicked(&self) {
-            self.open_file_dialog("client_key_path", gettext("Select Client Key"));
-        }
+impl MQTTySubscribeAuthTab {
+    pub fn tls_options(&self) -> TlsOptions {
+        let imp = self.imp();
 
-        #[template_callback]
-        fn on_clear_ca_cert(&self) {
-            self.obj().set_ca_cert_path("");
-        }
+        let mut options = imp.tls_group.tls_options();
+        imp.websocket_group.fill_tls_options(&mut options);
 
-        #[template_callback]
-        fn on_clear_client_cert(&self) {
-            self.obj().set_client_cert_path("");
-        }
+        options
+    }
 
-        #[template_callback]
-        fn on_clear_client_key(&self) {
-            self.obj().set_client_key_path("");
-        }
+    pub fn set_tls_options(&self, options: &TlsOptions) {
+        let imp = self.imp();
 
-        fn open_file_dialog(&self, property: &'static str, title: String) {
-            let obj = self.obj().clone();
-
-            let filter = gtk::FileFilter::new();
-            filter.add_pattern("*.pem");
-            filter.add_pattern("*.crt");
-            filter.add_pattern("*.key");
-            filter.add_pattern("*.cer");
-            filter.set_name(Some(&gettext("Certificate files")));
-
-            let filters = gio::ListStore::new::<gtk::FileFilter>();
-            filters.append(&filter);
-
-            let dialog = gtk::FileDialog::builder()
-                .title(&title)
-                .filters(&filters)
-                .modal(true)
-                .build();
-
-            if let Some(root) = obj.root() {
-                if let Some(window) = root.downcast_ref::<gtk::Window>() {
-                    dialog.open(Some(window), gio::Cancellable::NONE, move |result| {
-                        if let Ok(file) = result {
-                            if let Some(path) = file.path() {
-                                let path_str = path.to_string_lossy().to_string();
-                                match property {
-                                    "ca_cert_path" => obj.set_ca_cert_path(path_str),
-                                    "client_cert_path" => obj.set_client_cert_path(path_str),
-                                    "client_key_path" => obj.set_client_key_path(path_str),
-                                    _ => {}
-                                }
-                            }
-                        }
-                    });
-                }
-            }
-        }
+        imp.tls_group.set_tls_options(options);
+        imp.websocket_group.set_tls_options(options);
     }
 }
-
-glib::wrapper! {
-    pub struct MQTTySubscribeAuthTab(ObjectSubclass<imp::MQTTySubscribeAuthTab>)
-        @extends gtk::Widget, adw::Bin,
-        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
-}
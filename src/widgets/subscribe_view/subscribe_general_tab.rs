@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::sync::LazyLock;
 
 use adw::prelude::*;
@@ -23,6 +23,9 @@ use glib::subclass::Signal;
 
 use crate::application::MQTTyApplication;
 use crate::gsettings::MQTTySettingConnection;
+use crate::objects::MQTTyTopicFilter;
+
+use super::MQTTyTopicFilterRow;
 
 mod imp {
 
@@ -35,18 +38,27 @@ mod imp {
         #[property(get, set)]
         url: RefCell<String>,
 
-        #[property(get, set)]
-        topic: RefCell<String>,
-
         #[property(get, set)]
         username: RefCell<String>,
 
         #[property(get, set)]
         password: RefCell<String>,
 
+        #[property(get, set)]
+        share_group_enabled: Cell<bool>,
+
+        #[property(get, set)]
+        share_group: RefCell<String>,
+
+        #[property(get, set)]
+        share_group_size: Cell<u32>,
+
         #[template_child]
         pub profile_combo: TemplateChild<adw::ComboRow>,
 
+        #[template_child]
+        topic_filters_list: TemplateChild<gtk::ListBox>,
+
         #[template_child]
         mqtt_3_button: TemplateChild<gtk::CheckButton>,
         #[template_child]
@@ -123,6 +135,51 @@ glib::wrapper! {
 }
 
 impl MQTTySubscribeGeneralTab {
+    /// Shows the topic filters of the notebook, rows remove their filter from the
+    /// model when deleted
+    pub fn bind_topic_filters(&self, filters: &gio::ListStore) {
+        let imp = self.imp();
+
+        let mqtt_5_button = imp.mqtt_5_button.get();
+
+        imp.topic_filters_list.bind_model(
+            Some(filters),
+            glib::clone!(
+                #[weak]
+                filters,
+                #[weak]
+                mqtt_5_button,
+                #[upgrade_or_panic]
+                move |item| {
+                    let filter = item.downcast_ref::<MQTTyTopicFilter>().unwrap();
+
+                    let row = MQTTyTopicFilterRow::new(filter);
+
+                    mqtt_5_button
+                        .bind_property("active", &row, "mqtt_5")
+                        .sync_create()
+                        .build();
+
+                    row.connect_closure(
+                        "deleted",
+                        false,
+                        glib::closure_local!(
+                            #[weak]
+                            filters,
+                            move |row: MQTTyTopicFilterRow| {
+                                if let Some(idx) = filters.find(&row.filter()) {
+                                    filters.remove(idx);
+                                }
+                            }
+                        ),
+                    );
+
+                    row.upcast()
+                }
+            ),
+        );
+    }
+
     pub fn setup_profile_combo(&self) {
         let app = MQTTyApplication::get_singleton();
         let connections = app.settings_connections();
@@ -15,18 +15,39 @@
 
 use std::cell::{Cell, OnceCell, RefCell};
 use std::collections::HashMap;
+use std::time::Duration;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
 use gtk::{gio, glib};
 
-use crate::client::{MQTTyClient, MQTTyClientMessage, MQTTyClientQos, MQTTyClientVersion, TlsOptions};
+use crate::application::MQTTyApplication;
+use crate::client::{
+    shared_subscription, topic_matches_filter, MQTTyClient, MQTTyClientError, MQTTyClientMessage,
+    MQTTyClientParams, MQTTyClientQos, MQTTyClientState, MQTTyClientVersion, MQTTyDisconnectReason,
+    MQTTyReason,
+};
 use crate::display_mode::{MQTTyDisplayMode, MQTTyDisplayModeIface};
+use crate::export::{self, MQTTyExportFormat};
 use crate::gsettings::MQTTySettingConnection;
+use crate::main_window::MQTTyWindow;
+use crate::objects::{MQTTyMessageStore, MQTTyTopicFilter};
+use crate::recording::{self, Recorder};
 use crate::subclass::prelude::*;
+use crate::toast::MQTTyToastBuilder;
 
-use super::{MQTTyMessageRow, MQTTySubscribeGeneralTab};
-use crate::widgets::{MQTTyDataChart, MQTTyMessageDetailDialog, MQTTyTopicTreeView};
+use super::{MQTTyMessageRow, MQTTySubscribeAuthTab, MQTTySubscribeGeneralTab};
+use crate::widgets::{
+    MQTTyClearRetainedDialog, MQTTyDataChart, MQTTyMessageDetailDialog, MQTTyPublishView,
+    MQTTyPublishViewNotebook, MQTTyReplayDialog, MQTTyTopicDetailPane, MQTTyTopicItem,
+    MQTTyTopicTreeView,
+};
+
+/// Chart points are added in batches at most this often, about once per frame, as the
+/// messages list does
+const CHART_FLUSH_INTERVAL: Duration = Duration::from_millis(16);
 
 mod imp {
 
@@ -36,20 +57,74 @@ mod imp {
     #[template(resource = "/io/github/otaxhu/MQTTy/ui/subscribe_view/subscribe_view_notebook.ui")]
     #[properties(wrapper_type = super::MQTTySubscribeViewNotebook)]
     pub struct MQTTySubscribeViewNotebook {
+        /// Client owned by this tab, only used when the tab parameters doesn't match any
+        /// saved profile
         pub client: RefCell<Option<MQTTyClient>>,
 
+        /// Profile last loaded into this tab, its application-managed client is used as
+        /// long as the tab parameters still match it
+        pub profile: RefCell<Option<MQTTySettingConnection>>,
+
+        /// Client holding the subscription of this tab, either the profile one or the
+        /// tab owned one
+        #[property(get, set, nullable)]
+        current_client: RefCell<Option<MQTTyClient>>,
+
+        /// "message" handlers connected to the clients of the tab, the client may be
+        /// shared with other tabs, so they must be disconnected when this tab stops
+        /// using it
+        pub message_handlers: RefCell<Vec<(MQTTyClient, glib::SignalHandlerId)>>,
+
+        /// Subscribe with several clients in the same shared subscription group
+        #[property(get, set)]
+        share_group_enabled: Cell<bool>,
+
+        #[property(get, set)]
+        share_group: RefCell<String>,
+
+        /// Number of members of the share group, current_client included
+        #[property(get, set, minimum = 2, maximum = 16)]
+        share_group_size: Cell<u32>,
+
+        /// Members of the share group besides current_client, owned by this tab
+        pub share_members: RefCell<Vec<MQTTyClient>>,
+
+        /// Messages received by each member of the share group
+        pub member_counts: RefCell<Vec<u32>>,
+
+        #[property(get)]
+        share_group_stats: RefCell<String>,
+
         #[property(get, set, override_interface = MQTTyDisplayModeIface)]
         display_mode: Cell<MQTTyDisplayMode>,
 
         #[property(get, set, builder(Default::default()))]
         mqtt_version: Cell<MQTTyClientVersion>,
 
-        #[property(get, set)]
+        /// Topic filters of the tab joined, for titles and notifications
+        #[property(get)]
         topic: RefCell<String>,
 
+        /// The type of the items is MQTTyTopicFilter
+        pub topic_filters: OnceCell<gio::ListStore>,
+
+        /// Index mapped 1-to-1 to topic_filters, so that removed filters can still be
+        /// unsubscribed
+        pub topic_filter_items: RefCell<Vec<MQTTyTopicFilter>>,
+
+        /// Whether the tab has a message handler on current_client
+        #[property(get)]
+        subscribed: Cell<bool>,
+
+        /// The filters are unsubscribed while paused, the message handler is kept so that
+        /// resuming only needs to subscribe them again
+        #[property(get)]
+        paused: Cell<bool>,
+
         #[property(get, set)]
         url: RefCell<String>,
 
+        /// QoS of new topic filters
         #[property(get, set, builder(Default::default()))]
         qos: Cell<MQTTyClientQos>,
 
@@ -60,19 +135,21 @@ mod imp {
         password: RefCell<String>,
 
         #[property(get, set)]
-        enable_tls: Cell<bool>,
+        pub message_count: Cell<u32>,
 
-        #[property(get, set)]
-        ca_cert_path: RefCell<String>,
+        /// Tab showing a recording, it has no client and can't subscribe
+        #[property(get, set, construct_only)]
+        read_only: Cell<bool>,
 
-        #[property(get, set)]
-        client_cert_path: RefCell<String>,
+        /// Recording shown by a read-only tab
+        #[property(get, nullable)]
+        recording_file: RefCell<Option<gio::File>>,
 
-        #[property(get, set)]
-        client_key_path: RefCell<String>,
+        /// Received messages are appended to a recording file while set
+        pub recorder: RefCell<Option<Recorder>>,
 
-        #[property(get, set)]
-        pub message_count: Cell<u32>,
+        #[property(get)]
+        recording: Cell<bool>,
 
         #[template_child]
         messages_list: TemplateChild<gtk::ListView>,
@@ -80,16 +157,26 @@ mod imp {
         #[template_child]
         pub topic_tree_view: TemplateChild<MQTTyTopicTreeView>,
 
-        pub messages_model: OnceCell<gio::ListStore>,
+        #[template_child]
+        pub topic_detail_pane: TemplateChild<MQTTyTopicDetailPane>,
+
+        /// Received messages, newest first
+        pub messages_model: OnceCell<MQTTyMessageStore>,
+
+        #[template_child]
+        pub history_limit_row: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        pub history_size_row: TemplateChild<adw::SpinRow>,
 
         #[template_child]
         pub general_tab: TemplateChild<MQTTySubscribeGeneralTab>,
 
         #[template_child]
-        pub search_entry: TemplateChild<gtk::SearchEntry>,
+        pub auth_tab: TemplateChild<MQTTySubscribeAuthTab>,
 
-        /// Store previous messages per topic for diff view
-        pub message_history: RefCell<HashMap<String, MQTTyClientMessage>>,
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
 
         #[property(get, set)]
         pub search_text: RefCell<String>,
@@ -102,6 +189,12 @@ mod imp {
 
         /// Store charts per topic
         pub topic_charts: RefCell<HashMap<String, MQTTyDataChart>>,
+
+        /// Values of the messages received since the last chart flush, along with
+        /// their topic and their time in seconds since the Unix epoch
+        pub pending_points: RefCell<Vec<(String, f64, f64)>>,
+
+        pub chart_flush_source: RefCell<Option<glib::SourceId>>,
     }
 
     impl Default for MQTTySubscribeViewNotebook {
@@ -110,27 +203,45 @@ mod imp {
                 display_mode: Cell::new(MQTTyDisplayMode::Desktop),
                 mqtt_version: Default::default(),
                 topic: Default::default(),
+                topic_filters: Default::default(),
+                topic_filter_items: Default::default(),
+                subscribed: Cell::new(false),
+                paused: Cell::new(false),
                 url: Default::default(),
                 qos: Default::default(),
                 client: Default::default(),
+                profile: Default::default(),
+                current_client: Default::default(),
+                message_handlers: Default::default(),
+                share_group_enabled: Cell::new(false),
+                share_group: RefCell::new(String::from("mqtty")),
+                share_group_size: Cell::new(2),
+                share_members: Default::default(),
+                member_counts: Default::default(),
+                share_group_stats: Default::default(),
                 username: Default::default(),
                 password: Default::default(),
-                enable_tls: Default::default(),
-                ca_cert_path: Default::default(),
-                client_cert_path: Default::default(),
-                client_key_path: Default::default(),
                 message_count: Cell::new(0),
+                read_only: Cell::new(false),
+                recording_file: Default::default(),
+                recorder: Default::default(),
+                recording: Cell::new(false),
                 messages_list: Default::default(),
                 topic_tree_view: Default::default(),
+                topic_detail_pane: Default::default(),
                 messages_model: Default::default(),
+                history_limit_row: Default::default(),
+                history_size_row: Default::default(),
                 general_tab: Default::default(),
+                auth_tab: Default::default(),
                 search_entry: Default::default(),
-                message_history: RefCell::new(HashMap::new()),
                 search_text: Default::default(),
                 filter_model: Default::default(),
                 custom_filter: Default::default(),
                 chart_box: Default::default(),
                 topic_charts: RefCell::new(HashMap::new()),
+                pending_points: Default::default(),
+                chart_flush_source: Default::default(),
             }
         }
     }
@@ -152,6 +263,60 @@ mod imp {
             klass.install_action("subscribe-notebook.clear-messages", None, |this, _, _| {
                 this.clear_messages();
             });
+
+            klass.install_action("subscribe-notebook.add-topic-filter", None, |this, _, _| {
+                this.topic_filters()
+                    .append(&MQTTyTopicFilter::new("", this.qos()));
+            });
+
+            klass.install_action_async(
+                "subscribe-notebook.toggle-recording",
+                None,
+                |this, _, _| async move {
+                    if this.recording() {
+                        this.stop_recording();
+                    } else {
+                        this.choose_recording_file().await;
+                    }
+                },
+            );
+
+            klass.install_action_async(
+                "subscribe-notebook.export",
+                Some(glib::VariantTy::STRING),
+                |this, _, format| async move {
+                    let Some(format) = format
+                        .and_then(|format| format.str().and_then(MQTTyExportFormat::from_name))
+                    else {
+                        return;
+                    };
+
+                    this.export_messages(format).await;
+                },
+            );
+
+            klass.install_action("subscribe-notebook.replay", None, |this, _, _| {
+                if let Some(file) = this.recording_file() {
+                    MQTTyReplayDialog::new(&file).present(Some(this));
+                }
+            });
+
+            klass.install_action_async(
+                "subscribe-notebook.toggle-pause",
+                None,
+                |this, _, _| async move {
+                    if this.paused() {
+                        if let Err(e) = this.resume().await {
+                            this.toast_error(
+                                formatx!(gettext("Failed to resume the subscription: {}"), e)
+                                    .unwrap(),
+                            );
+                        }
+                    } else {
+                        this.pause().await;
+                    }
+                },
+            );
         }
 
         fn instance_init(obj: &glib::subclass::types::InitializingObject<Self>) {
@@ -166,10 +331,72 @@ mod imp {
 
             let obj = self.obj();
 
-            // Setup messages list
-            let messages_model = gio::ListStore::new::<MQTTyMessageRow>();
+            // Topic filters, the ones applied while subscribed are subscribed right away
+            let topic_filters = gio::ListStore::new::<MQTTyTopicFilter>();
+
+            topic_filters.connect_items_changed(glib::clone!(
+                #[weak]
+                obj,
+                move |filters, position, removed, added| {
+                    let added = (position..position + added)
+                        .map(|i| filters.item(i).and_downcast::<MQTTyTopicFilter>().unwrap())
+                        .collect::<Vec<_>>();
+
+                    for filter in &added {
+                        filter.connect_topic_filter_notify(glib::clone!(
+                            #[weak]
+                            obj,
+                            move |filter| obj.on_topic_filter_changed(filter)
+                        ));
+                    }
+
+                    let removed = obj
+                        .imp()
+                        .topic_filter_items
+                        .borrow_mut()
+                        .splice(position as usize..(position + removed) as usize, added)
+                        .collect::<Vec<_>>();
+
+                    for filter in removed {
+                        obj.release_filter(&filter);
+                    }
+
+                    obj.update_topic();
+                }
+            ));
+
+            self.topic_filters.set(topic_filters.clone()).unwrap();
+
+            // Nothing to pause until subscribed
+            obj.action_set_enabled("subscribe-notebook.toggle-pause", false);
+            obj.action_set_enabled("subscribe-notebook.toggle-recording", !obj.read_only());
+            self.general_tab.bind_topic_filters(&topic_filters);
+
+            topic_filters.append(&MQTTyTopicFilter::new("", obj.qos()));
+
+            // Setup messages list, the caps of the kept messages are shared by every tab
+            let messages_model = MQTTyMessageStore::new();
             self.messages_model.set(messages_model.clone()).unwrap();
 
+            let settings = MQTTyApplication::get_singleton().settings();
+
+            for (key, row) in [
+                ("message-history-limit", &*self.history_limit_row),
+                ("message-history-size", &*self.history_size_row),
+            ] {
+                settings.bind(key, row, "value").build();
+            }
+
+            settings
+                .bind("message-history-limit", &messages_model, "max-messages")
+                .flags(gio::SettingsBindFlags::GET)
+                .build();
+
+            settings
+                .bind("message-history-size", &messages_model, "max-size")
+                .flags(gio::SettingsBindFlags::GET)
+                .build();
+
             // Create custom filter for search
             let search_text_ref = self.search_text.clone();
             let custom_filter = gtk::CustomFilter::new(move |item| {
@@ -178,9 +405,9 @@ mod imp {
                     return true;
                 }
 
-                if let Some(row) = item.downcast_ref::<MQTTyMessageRow>() {
-                    let topic = row.topic().to_lowercase();
-                    let body = row.body_preview().to_lowercase();
+                if let Some(message) = item.downcast_ref::<MQTTyClientMessage>() {
+                    let topic = message.topic().to_lowercase();
+                    let body = String::from_utf8_lossy(&message.body()).to_lowercase();
                     topic.contains(search.as_str()) || body.contains(search.as_str())
                 } else {
                     true
@@ -192,18 +419,25 @@ mod imp {
             let filter_model = gtk::FilterListModel::new(Some(messages_model), Some(custom_filter));
             self.filter_model.set(filter_model.clone()).unwrap();
 
+            // Rows are only created for the visible messages, and recycled while
+            // scrolling
             let factory = gtk::SignalListItemFactory::new();
             factory.connect_setup(|_, list_item| {
                 let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-                // The child will be set in bind
-                list_item.set_child(None::<&gtk::Widget>);
+                list_item.set_child(Some(&MQTTyMessageRow::new()));
             });
 
             factory.connect_bind(|_, list_item| {
                 let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-                let item = list_item.item().and_downcast::<MQTTyMessageRow>();
-                if let Some(row) = item {
-                    list_item.set_child(Some(&row));
+                let row = list_item.child().and_downcast::<MQTTyMessageRow>().unwrap();
+                let message = list_item.item().and_downcast::<MQTTyClientMessage>();
+                row.set_message(message.as_ref());
+            });
+
+            factory.connect_unbind(|_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                if let Some(row) = list_item.child().and_downcast::<MQTTyMessageRow>() {
+                    row.set_message(None);
                 }
             });
 
@@ -298,6 +532,42 @@ mod imp {
                 }),
             );
 
+            self.topic_tree_view.connect_closure(
+                "clear-retained-recursive-requested",
+                false,
+                glib::closure_local!(
+                    #[weak]
+                    obj,
+                    move |_tree_view: MQTTyTopicTreeView, item: MQTTyTopicItem| {
+                        obj.clear_retained_recursive(&item);
+                    }
+                ),
+            );
+
+            self.topic_tree_view.connect_closure(
+                "subscribe-requested",
+                false,
+                glib::closure_local!(
+                    #[weak]
+                    obj,
+                    move |_tree_view: MQTTyTopicTreeView, topic_filter: String| {
+                        obj.add_topic_filter(&topic_filter);
+                    }
+                ),
+            );
+
+            self.topic_tree_view.connect_closure(
+                "publish-requested",
+                false,
+                glib::closure_local!(
+                    #[weak]
+                    obj,
+                    move |_tree_view: MQTTyTopicTreeView, topic: String| {
+                        obj.publish_to_topic(&topic);
+                    }
+                ),
+            );
+
             // Handle profile selection
             self.general_tab.connect_closure(
                 "profile-selected",
@@ -305,28 +575,47 @@ mod imp {
                 glib::closure_local!(
                     #[weak]
                     obj,
-                    #[weak]
-                    mqtt_version_state,
-                    #[weak]
-                    qos_state,
                     move |_tab: MQTTySubscribeGeneralTab, conn: MQTTySettingConnection| {
+                        obj.imp().profile.replace(Some(conn.clone()));
+
                         // Update all the notebook properties from the profile
-                        obj.set_topic(conn.topic());
                         obj.set_url(conn.url());
                         obj.set_username(conn.username());
                         obj.set_password(conn.password());
+                        obj.imp().auth_tab.set_tls_options(&conn.tls_options());
 
-                        // Update MQTT version action state
-                        let version = conn.mqtt_version();
-                        mqtt_version_state.change_state(&version.into());
+                        // Action states follow through their bindings
+                        obj.set_mqtt_version(conn.mqtt_version());
+                        obj.set_qos(conn.qos());
 
-                        // Update QoS action state
-                        let qos = conn.qos();
-                        qos_state.change_state(&qos.into());
+                        // The profile topic replaces the filters of the tab
+                        let filters = obj.topic_filters();
+                        filters.remove_all();
+                        filters.append(&MQTTyTopicFilter::new(&conn.topic(), conn.qos()));
                     }
                 ),
             );
         }
+
+        fn dispose(&self) {
+            // Tabs are closed through close(), this is the fallback when the whole window
+            // goes away
+            self.obj().disconnect_message_handler();
+            self.recorder.take();
+
+            if let Some(source) = self.chart_flush_source.take() {
+                source.remove();
+            }
+
+            for client in self
+                .client
+                .take()
+                .into_iter()
+                .chain(self.share_members.take())
+            {
+                client.disconnect_in_background();
+            }
+        }
     }
     impl WidgetImpl for MQTTySubscribeViewNotebook {}
     impl BinImpl for MQTTySubscribeViewNotebook {}
@@ -335,27 +624,116 @@ mod imp {
     #[gtk::template_callbacks]
     impl MQTTySubscribeViewNotebook {
         #[template_callback]
-        fn on_message_activated(&self, position: u32, _list_view: &gtk::ListView) {
-            if let Some(model) = self.messages_model.get() {
-                if let Some(item) = model.item(position) {
-                    let row = item.downcast_ref::<MQTTyMessageRow>().unwrap();
-                    if let Some(msg) = row.message() {
-                        let dialog = MQTTyMessageDetailDialog::new();
-
-                        // Get previous message for diff
-                        let history = self.message_history.borrow();
-                        let previous = history.get(&msg.topic());
-
-                        dialog.set_message(&msg, previous);
-
-                        if let Some(root) = self.obj().root() {
-                            if let Some(window) = root.downcast_ref::<gtk::Window>() {
-                                dialog.present(Some(window));
-                            }
+        fn on_message_activated(&self, position: u32, list_view: &gtk::ListView) {
+            // The position is relative to the search results
+            let Some(msg) = list_view
+                .model()
+                .and_then(|model| model.item(position))
+                .and_downcast::<MQTTyClientMessage>()
+            else {
+                return;
+            };
+
+            let dialog = MQTTyMessageDetailDialog::new();
+
+            // Diff against the message of the same topic received right before it
+            let previous = self
+                .topic_tree_view
+                .topic_item(&msg.topic())
+                .and_then(|item| item.previous_message(&msg));
+
+            dialog.set_message(&msg, previous.as_ref());
+
+            if let Some(root) = self.obj().root() {
+                if let Some(window) = root.downcast_ref::<gtk::Window>() {
+                    dialog.present(Some(window));
+                }
+            }
+        }
+
+        #[template_callback]
+        fn connection_banner_revealed(&self, state: MQTTyClientState) -> bool {
+            matches!(
+                state,
+                MQTTyClientState::Reconnecting | MQTTyClientState::ConnectionLost
+            )
+        }
+
+        #[template_callback]
+        fn connection_banner_title(&self, state: MQTTyClientState) -> String {
+            match state {
+                MQTTyClientState::Reconnecting => {
+                    gettext("Connection to the broker lost, reconnecting…")
+                }
+                _ => gettext("Connection to the broker lost"),
+            }
+        }
+
+        #[template_callback]
+        fn pause_icon_name(&self, paused: bool) -> String {
+            if paused {
+                String::from("media-playback-start-symbolic")
+            } else {
+                String::from("media-playback-pause-symbolic")
+            }
+        }
+
+        #[template_callback]
+        fn pause_tooltip(&self, paused: bool) -> String {
+            if paused {
+                gettext("Resume the subscription")
+            } else {
+                gettext("Pause the subscription")
+            }
+        }
+
+        #[template_callback]
+        fn recording_icon_name(&self, recording: bool) -> String {
+            if recording {
+                String::from("media-playback-stop-symbolic")
+            } else {
+                String::from("media-record-symbolic")
+            }
+        }
+
+        #[template_callback]
+        fn recording_tooltip(&self, recording: bool) -> String {
+            if recording {
+                gettext("Stop recording")
+            } else {
+                gettext("Record messages to a file")
+            }
+        }
+
+        #[template_callback]
+        fn has_dropped_messages(&self, dropped: u64) -> bool {
+            dropped > 0
+        }
+
+        #[template_callback]
+        fn dropped_messages_label(&self, dropped: u64) -> String {
+            formatx!(gettext("{} dropped"), dropped).unwrap()
+        }
+
+        #[template_callback]
+        fn on_reconnect_clicked(&self) {
+            let obj = self.obj();
+            let clients = obj.clients();
+
+            // Subscriptions are restored by the clients themselves once connected
+            glib::spawn_future_local(glib::clone!(
+                #[weak]
+                obj,
+                async move {
+                    for client in clients {
+                        if let Err(e) = client.ensure_connected().await {
+                            obj.toast_error(
+                                formatx!(gettext("Failed to reconnect: {}"), e).unwrap(),
+                            );
                         }
                     }
                 }
-            }
+            ));
         }
 
         #[template_callback]
@@ -382,128 +760,914 @@ impl MQTTySubscribeViewNotebook {
         glib::Object::builder().build()
     }
 
+    /// The type of the items is MQTTyTopicFilter
+    pub fn topic_filters(&self) -> gio::ListStore {
+        self.imp().topic_filters.get().unwrap().clone()
+    }
+
+    /// Subscribes every topic filter of the tab, each one gets its own SUBACK, the
+    /// subscription fails only if every filter is rejected
     pub async fn subscribe(&self) -> Result<(), String> {
-        let mqtt_version = self.mqtt_version();
+        if self.read_only() {
+            return Err(gettext("Recordings can't be subscribed"));
+        }
 
-        let client = MQTTyClient::new(
-            &self.url(),
-            mqtt_version,
-            &self.username(),
-            &self.password(),
-        );
+        let clients = self.resolve_clients();
 
-        // Set TLS options
-        let tls_options = TlsOptions {
-            enabled: self.enable_tls(),
-            ca_cert_path: {
-                let path = self.ca_cert_path();
-                if path.is_empty() { None } else { Some(path) }
-            },
-            client_cert_path: {
-                let path = self.client_cert_path();
-                if path.is_empty() { None } else { Some(path) }
-            },
-            client_key_path: {
-                let path = self.client_key_path();
-                if path.is_empty() { None } else { Some(path) }
-            },
-        };
-        client.set_tls_options(tls_options);
+        for client in &clients {
+            client.ensure_connected().await.map_err(|e| e.to_string())?;
+        }
+
+        // The client may be shared with other tabs, so the handler of a previous
+        // subscription of this tab is replaced, and messages are filtered by topic
+        self.disconnect_message_handler();
+
+        let share_group = self.share_group_enabled();
 
-        client.connect_client().await?;
+        let handlers = clients
+            .iter()
+            .enumerate()
+            .map(|(i, client)| {
+                let member = share_group.then_some(i);
+                (client.clone(), self.connect_messages(client, member))
+            })
+            .collect::<Vec<_>>();
+
+        self.imp().message_handlers.replace(handlers);
+        self.reset_member_counts(if share_group { clients.len() } else { 0 });
+        self.set_subscribed(true);
+        self.set_paused(false);
+
+        self.subscribe_filters().await
+    }
+
+    /// `member` is the index of the client in the share group, if any
+    fn connect_messages(
+        &self,
+        client: &MQTTyClient,
+        member: Option<usize>,
+    ) -> glib::SignalHandlerId {
+        // Messages are matched against the live list of filters, so that filters
+        // added or removed afterwards are taken into account
+        let topic_filters = self.topic_filters();
 
-        // Setup message handler
-        let messages_model = self.imp().messages_model.get().unwrap().clone();
         let obj_weak = self.downgrade();
 
         client.connect_message(move |_client, message| {
-            let row = MQTTyMessageRow::from_message(message);
-            messages_model.insert(0, &row);
-
-            // Update message count
-            if let Some(obj) = obj_weak.upgrade() {
-                let count = obj.imp().message_count.get();
-                obj.imp().message_count.set(count + 1);
-                obj.notify("message-count");
-
-                // Update topic tree
-                let topic = message.topic();
-                let body = message.body();
-                let body_str = String::from_utf8_lossy(&body);
-                obj.imp().topic_tree_view.process_message(&topic, &body_str);
-
-                // Update chart with numeric data
-                obj.update_chart(&topic, &body_str);
-
-                // Store current message as previous for next diff
-                obj.imp().message_history.borrow_mut().insert(topic, message.clone());
+            let topic = message.topic();
+
+            let matches = topic_filters
+                .iter::<MQTTyTopicFilter>()
+                .flatten()
+                .filter_map(|filter| filter.subscribed_filter())
+                .any(|filter| topic_matches_filter(&filter, &topic));
+
+            if !matches {
+                return;
             }
-        });
 
-        // Subscribe to topic
-        client.subscribe(&self.topic(), self.qos()).await?;
+            let Some(obj) = obj_weak.upgrade() else {
+                return;
+            };
+
+            if let Some(member) = member {
+                message.set_share_member(member as u32 + 1);
+                obj.count_member_message(member);
+            }
+
+            if let Some(recorder) = obj.imp().recorder.borrow().as_ref() {
+                recorder.record(message);
+            }
+
+            obj.show_message(message);
+        })
+    }
+
+    /// Adds the message to the list, the topic tree and the charts
+    fn show_message(&self, message: &MQTTyClientMessage) {
+        let imp = self.imp();
+
+        imp.messages_model.get().unwrap().push(message);
+
+        // Update message count
+        imp.message_count.set(imp.message_count.get() + 1);
+        self.notify("message-count");
+
+        // Update topic tree
+        imp.topic_tree_view.process_message(message);
+
+        // Update chart with numeric data
+        self.update_chart(message);
+    }
+
+    /// Shows the messages of a recording, in the order they were recorded
+    pub fn load_recording(&self, file: &gio::File, messages: &[MQTTyClientMessage]) {
+        self.imp().recording_file.replace(Some(file.clone()));
+        self.notify_recording_file();
+
+        for message in messages {
+            self.show_message(message);
+        }
+
+        self.imp().messages_model.get().unwrap().flush();
+    }
+
+    async fn choose_recording_file(&self) {
+        let name = glib::DateTime::now_local()
+            .and_then(|now| now.format("%Y-%m-%d %H-%M-%S"))
+            .map(|now| formatx!(gettext("Recording {}"), now).unwrap())
+            .unwrap_or_else(|_| gettext("Recording"));
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Record Messages"))
+            .filters(&recording::file_filters())
+            .initial_name(format!("{}.{}", name, recording::RECORDING_EXTENSION))
+            .modal(true)
+            .build();
+
+        let window = self.root().and_downcast::<gtk::Window>();
+
+        // Dismissed by the user
+        let Some(path) = dialog
+            .save_future(window.as_ref())
+            .await
+            .ok()
+            .and_then(|file| file.path())
+        else {
+            return;
+        };
+
+        if let Err(e) = self.start_recording(&path) {
+            self.toast_error(formatx!(gettext("Failed to start recording: {}"), e).unwrap());
+        }
+    }
+
+    /// Appends the messages received from now on to the file
+    pub fn start_recording(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let recorder = Recorder::create(
+            path,
+            glib::clone!(
+                #[weak(rename_to = this)]
+                self,
+                #[to_owned]
+                path,
+                move |e| this.on_recording_failed(&path, e)
+            ),
+        )?;
+
+        tracing::info!("Recording messages to {}", recorder.path().display());
 
-        // Store the client to keep connection alive
-        self.imp().client.replace(Some(client));
+        self.imp().recorder.replace(Some(recorder));
+        self.set_recording(true);
 
         Ok(())
     }
 
+    pub fn stop_recording(&self) {
+        self.imp().recorder.take();
+        self.set_recording(false);
+    }
+
+    fn on_recording_failed(&self, path: &std::path::Path, e: std::io::Error) {
+        // A recorder stopped before failing is already gone
+        let current = self
+            .imp()
+            .recorder
+            .borrow()
+            .as_ref()
+            .is_some_and(|recorder| recorder.path() == path);
+
+        if !current {
+            return;
+        }
+
+        self.stop_recording();
+        self.toast_error(formatx!(gettext("Recording stopped: {}"), e).unwrap());
+    }
+
+    fn set_recording(&self, recording: bool) {
+        if self.imp().recording.replace(recording) != recording {
+            self.notify_recording();
+        }
+    }
+
+    /// Saves the messages listed, the ones matching the search if any, oldest first
+    async fn export_messages(&self, format: MQTTyExportFormat) {
+        let Some(filter_model) = self.imp().filter_model.get() else {
+            return;
+        };
+
+        let mut messages = filter_model
+            .iter::<MQTTyClientMessage>()
+            .flatten()
+            .collect::<Vec<_>>();
+        messages.reverse();
+
+        let contents = format.format(&messages);
+
+        let ret = export::save_to_file(
+            self,
+            &gettext("Export Messages"),
+            &format!("{}.{}", gettext("Messages"), format.extension()),
+            Some(&format.file_filter()),
+            contents.into_bytes(),
+        )
+        .await;
+
+        match ret {
+            Ok(Some(_)) => self.toast(
+                formatx!(gettext("Exported {} messages"), messages.len()).unwrap(),
+                "object-select-symbolic",
+            ),
+            Ok(None) => (),
+            Err(e) => self.toast_error(formatx!(gettext("Failed to export: {}"), e).unwrap()),
+        }
+    }
+
+    async fn export_chart(&self, chart: &MQTTyDataChart) {
+        let name = chart.topic().replace('/', "_");
+
+        let ret = export::save_to_file(
+            self,
+            &gettext("Export Chart"),
+            &format!("{}.{}", name, MQTTyExportFormat::Csv.extension()),
+            Some(&MQTTyExportFormat::Csv.file_filter()),
+            chart.to_csv().into_bytes(),
+        )
+        .await;
+
+        if let Err(e) = ret {
+            self.toast_error(formatx!(gettext("Failed to export: {}"), e).unwrap());
+        }
+    }
+
+    fn toast(&self, title: String, icon_name: &str) {
+        let toast = MQTTyToastBuilder::new()
+            .title(title)
+            .icon(gtk::Image::builder().icon_name(icon_name).build().as_ref())
+            .timeout(2)
+            .build();
+
+        if let Some(window) = self.root().and_downcast::<MQTTyWindow>() {
+            window.toast(&toast);
+        }
+    }
+
+    fn toast_error(&self, title: String) {
+        let toast = MQTTyToastBuilder::new()
+            .title(title)
+            .icon(
+                gtk::Image::builder()
+                    .icon_name("dialog-error-symbolic")
+                    .build()
+                    .as_ref(),
+            )
+            .timeout(3)
+            .build();
+
+        if let Some(window) = self.root().and_downcast::<MQTTyWindow>() {
+            window.toast(&toast);
+        }
+    }
+
+    async fn subscribe_filters(&self) -> Result<(), String> {
+        let filters = self
+            .topic_filters()
+            .iter::<MQTTyTopicFilter>()
+            .flatten()
+            .filter(|filter| !filter.topic_filter().is_empty())
+            .collect::<Vec<_>>();
+
+        if filters.is_empty() {
+            return Err(gettext("There are no topic filters to subscribe to"));
+        }
+
+        let mut errors = Vec::new();
+
+        for filter in &filters {
+            if let Err(e) = self.subscribe_filter(filter).await {
+                errors.push(format!("{}: {}", filter.topic_filter(), e));
+            }
+        }
+
+        if errors.len() == filters.len() {
+            return Err(errors.join(", "));
+        }
+
+        Ok(())
+    }
+
+    /// Subscribes the filter with every client of the tab, the new filter is
+    /// subscribed before releasing the previous one, so that subscribing the same
+    /// filter again doesn't go through an UNSUBSCRIBE
+    ///
+    /// Members of a share group failing to subscribe are only logged, the filter
+    /// counts as subscribed as long as one of them succeeds
+    async fn subscribe_filter(
+        &self,
+        filter: &MQTTyTopicFilter,
+    ) -> Result<MQTTyReason, MQTTyClientError> {
+        let topic_filter = self.effective_filter(&filter.topic_filter());
+        let previous = filter.subscribed_filter();
+        let previous_options = filter.subscribed_options();
+        let options = filter.options();
+
+        let mut results = Vec::new();
+
+        for client in self.clients() {
+            let ret = client.subscribe(&topic_filter, &options).await;
+
+            if let Err(e) = &ret {
+                tracing::warn!(
+                    "{} failed to subscribe to {}: {}",
+                    client.url(),
+                    topic_filter,
+                    e
+                );
+            }
+
+            if let Some(previous) = &previous {
+                if let Err(e) = client.unsubscribe(previous, &previous_options).await {
+                    tracing::warn!("Failed to unsubscribe from {}: {}", previous, e);
+                }
+            }
+
+            results.push(ret);
+        }
+
+        let ret = match results.iter().position(Result::is_ok) {
+            Some(i) => results.swap_remove(i),
+            None if results.is_empty() => Err(MQTTyClientError::Connection(gettext(
+                "There is no client to subscribe with",
+            ))),
+            None => results.swap_remove(0),
+        };
+
+        filter.set_suback(&topic_filter, &options, &ret);
+
+        ret
+    }
+
+    /// Unsubscribes a filter that is no longer part of the tab
+    fn release_filter(&self, filter: &MQTTyTopicFilter) {
+        let Some(topic_filter) = filter.subscribed_filter() else {
+            return;
+        };
+        let options = filter.subscribed_options();
+
+        filter.reset_suback();
+
+        let clients = self.clients();
+
+        glib::spawn_future_local(async move {
+            for client in clients {
+                if let Err(e) = client.unsubscribe(&topic_filter, &options).await {
+                    tracing::warn!("Failed to unsubscribe from {}: {}", topic_filter, e);
+                }
+            }
+        });
+    }
+
+    fn on_topic_filter_changed(&self, filter: &MQTTyTopicFilter) {
+        self.update_topic();
+
+        // Not subscribed yet, the filter is subscribed along with the others
+        if !self.subscribed() || self.paused() {
+            return;
+        }
+
+        if filter.topic_filter().is_empty() {
+            self.release_filter(filter);
+            return;
+        }
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            filter,
+            async move {
+                if let Err(e) = obj.subscribe_filter(&filter).await {
+                    obj.toast_error(
+                        formatx!(
+                            gettext("Failed to subscribe to {}: {}"),
+                            filter.topic_filter(),
+                            e
+                        )
+                        .unwrap(),
+                    );
+                }
+            }
+        ));
+    }
+
+    /// Unsubscribes every filter of the tab, messages already on their way are still
+    /// shown
+    pub async fn pause(&self) {
+        let clients = self.clients();
+
+        if clients.is_empty() {
+            return;
+        }
+
+        self.set_paused(true);
+
+        for filter in self.topic_filters().iter::<MQTTyTopicFilter>().flatten() {
+            let Some(topic_filter) = filter.subscribed_filter() else {
+                continue;
+            };
+            let options = filter.subscribed_options();
+
+            let mut results = Vec::new();
+
+            for client in &clients {
+                let ret = client.unsubscribe(&topic_filter, &options).await;
+
+                if let Err(e) = &ret {
+                    tracing::warn!("Failed to unsubscribe from {}: {}", topic_filter, e);
+                }
+
+                results.push(ret);
+            }
+
+            filter.set_unsuback(&results.swap_remove(0));
+        }
+    }
+
+    /// Subscribes again the filters unsubscribed by pause()
+    pub async fn resume(&self) -> Result<(), String> {
+        let clients = self.clients();
+
+        if clients.is_empty() {
+            return Ok(());
+        }
+
+        self.set_paused(false);
+
+        for client in &clients {
+            client.ensure_connected().await.map_err(|e| e.to_string())?;
+        }
+
+        self.subscribe_filters().await
+    }
+
+    /// Orderly teardown before the tab is closed, every filter of the tab is
+    /// unsubscribed, and the clients owned by the tab are disconnected. Profile
+    /// clients are shared with other tabs, so they stay connected
+    pub async fn close(&self) {
+        let imp = self.imp();
+
+        self.disconnect_message_handler();
+        self.stop_recording();
+
+        let clients = self.clients();
+
+        for filter in self.topic_filters().iter::<MQTTyTopicFilter>().flatten() {
+            let Some(topic_filter) = filter.subscribed_filter() else {
+                continue;
+            };
+            let options = filter.subscribed_options();
+
+            filter.reset_suback();
+
+            for client in &clients {
+                if let Err(e) = client.unsubscribe(&topic_filter, &options).await {
+                    tracing::warn!("Failed to unsubscribe from {}: {}", topic_filter, e);
+                }
+            }
+        }
+
+        let owned = imp
+            .client
+            .take()
+            .into_iter()
+            .chain(imp.share_members.take());
+
+        for client in owned {
+            if client.state() == MQTTyClientState::Disconnected {
+                continue;
+            }
+
+            if let Err(e) = client
+                .disconnect_with_reason(MQTTyDisconnectReason::Normal)
+                .await
+            {
+                tracing::warn!("Failed to disconnect client {}: {}", client.url(), e);
+            }
+        }
+
+        self.set_current_client(None::<&MQTTyClient>);
+    }
+
+    fn set_subscribed(&self, subscribed: bool) {
+        if self.imp().subscribed.replace(subscribed) != subscribed {
+            self.action_set_enabled("subscribe-notebook.toggle-pause", subscribed);
+            self.notify_subscribed();
+        }
+    }
+
+    fn set_paused(&self, paused: bool) {
+        if self.imp().paused.replace(paused) != paused {
+            self.notify_paused();
+        }
+    }
+
+    /// current_client followed by the other members of the share group, if any
+    fn clients(&self) -> Vec<MQTTyClient> {
+        self.current_client()
+            .into_iter()
+            .chain(self.imp().share_members.borrow().iter().cloned())
+            .collect()
+    }
+
+    /// Topic filter as subscribed, filters are put in the share group while the tab
+    /// has one, unless they are shared subscriptions already
+    fn effective_filter(&self, topic_filter: &str) -> String {
+        let in_group = !self.imp().share_members.borrow().is_empty();
+
+        if !in_group || shared_subscription(topic_filter).is_some() {
+            return topic_filter.to_string();
+        }
+
+        let group = self.share_group();
+        let group = if group.is_empty() {
+            "mqtty"
+        } else {
+            group.as_str()
+        };
+
+        format!("$share/{group}/{topic_filter}")
+    }
+
+    fn reset_member_counts(&self, members: usize) {
+        self.imp().member_counts.replace(vec![0; members]);
+        self.update_share_group_stats();
+    }
+
+    fn count_member_message(&self, member: usize) {
+        if let Some(count) = self.imp().member_counts.borrow_mut().get_mut(member) {
+            *count += 1;
+        }
+
+        self.update_share_group_stats();
+    }
+
+    fn update_share_group_stats(&self) {
+        let stats = self
+            .imp()
+            .member_counts
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(i, count)| formatx!(gettext("Member {}: {}"), i + 1, count).unwrap())
+            .collect::<Vec<_>>()
+            .join(" · ");
+
+        if *self.imp().share_group_stats.borrow() != stats {
+            self.imp().share_group_stats.replace(stats);
+            self.notify_share_group_stats();
+        }
+    }
+
+    fn update_topic(&self) {
+        let topic = self
+            .topic_filters()
+            .iter::<MQTTyTopicFilter>()
+            .flatten()
+            .map(|filter| filter.topic_filter())
+            .filter(|topic_filter| !topic_filter.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if *self.imp().topic.borrow() != topic {
+            self.imp().topic.replace(topic);
+            self.notify_topic();
+        }
+    }
+
+    /// Picks the clients matching the current tab parameters, the first one becomes
+    /// current_client. The application-managed client of the loaded profile is
+    /// preferred, so that it's shared with other tabs, otherwise a client owned by this
+    /// tab is (re)created
+    ///
+    /// Members of a share group need connections of their own, so they are always
+    /// owned by this tab
+    fn resolve_clients(&self) -> Vec<MQTTyClient> {
+        let imp = self.imp();
+
+        let params = MQTTyClientParams {
+            url: self.url(),
+            mqtt_version: self.mqtt_version(),
+            username: self.username(),
+            password: self.password(),
+            tls_options: imp.auth_tab.tls_options(),
+        };
+        let share_group = self.share_group_enabled();
+
+        let previous = self.clients();
+
+        let mut replaced = Vec::new();
+
+        // Bound apart, the closure below replaces it
+        let owned = imp.client.borrow().clone();
+
+        let client = MQTTyApplication::get_singleton()
+            .resolve_client(
+                imp.profile.borrow().as_ref().filter(|_| !share_group),
+                owned,
+                &params,
+            )
+            .unwrap_or_else(|| {
+                let client = params.new_client();
+                replaced.extend(imp.client.replace(Some(client.clone())));
+                client
+            });
+
+        let members = if share_group {
+            let members = imp.share_members.borrow().clone();
+            let size = self.share_group_size() as usize - 1;
+
+            if members.len() == size && members.iter().all(|m| params.matches(m)) {
+                members
+            } else {
+                (0..size).map(|_| params.new_client()).collect()
+            }
+        } else {
+            Vec::new()
+        };
+
+        let old_members = imp.share_members.replace(members.clone());
+        replaced.extend(old_members.into_iter().filter(|m| !members.contains(m)));
+
+        let clients = std::iter::once(client).chain(members).collect::<Vec<_>>();
+
+        if previous != clients {
+            self.disconnect_message_handler();
+            self.set_current_client(Some(&clients[0]));
+
+            // Subscriptions belong to the previous clients
+            let topic_filters = self
+                .topic_filters()
+                .iter::<MQTTyTopicFilter>()
+                .flatten()
+                .filter_map(|filter| {
+                    let topic_filter = filter.subscribed_filter();
+                    let options = filter.subscribed_options();
+                    filter.reset_suback();
+                    topic_filter.map(|topic_filter| (topic_filter, options))
+                })
+                .collect::<Vec<_>>();
+
+            glib::spawn_future_local(async move {
+                for previous in previous {
+                    for (topic_filter, options) in &topic_filters {
+                        if let Err(e) = previous.unsubscribe(topic_filter, options).await {
+                            tracing::warn!("Failed to unsubscribe from {}: {}", topic_filter, e);
+                        }
+                    }
+                }
+
+                // Disconnected only once their subscriptions are gone
+                for replaced in replaced {
+                    replaced.disconnect_in_background();
+                }
+            });
+        }
+
+        clients
+    }
+
+    fn disconnect_message_handler(&self) {
+        for (client, handler) in self.imp().message_handlers.take() {
+            client.disconnect(handler);
+        }
+
+        self.set_subscribed(false);
+    }
+
     pub fn clear_messages(&self) {
         if let Some(model) = self.imp().messages_model.get() {
-            model.remove_all();
+            model.clear();
             self.imp().message_count.set(0);
             self.notify("message-count");
         }
+
+        self.imp().topic_tree_view.clear_history();
+
+        let members = self.imp().member_counts.borrow().len();
+        self.reset_member_counts(members);
     }
 
-    /// Update chart with numeric data from a message
-    fn update_chart(&self, topic: &str, payload: &str) {
+    /// Queues the value of the message for its topic chart, messages that aren't
+    /// numeric are left out of the charts
+    fn update_chart(&self, message: &MQTTyClientMessage) {
+        let imp = self.imp();
+
+        let body = message.body();
+
+        let Some(value) = MQTTyDataChart::parse_value(&String::from_utf8_lossy(&body)) else {
+            return;
+        };
+
+        let timestamp = match message.received_at() {
+            Some(received_at) => {
+                received_at.to_unix() as f64 + received_at.microsecond() as f64 / 1e6
+            }
+            None => glib::real_time() as f64 / 1e6,
+        };
+
+        imp.pending_points
+            .borrow_mut()
+            .push((message.topic(), timestamp, value));
+
+        if imp.chart_flush_source.borrow().is_some() {
+            return;
+        }
+
+        let source = glib::timeout_add_local_once(
+            CHART_FLUSH_INTERVAL,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.imp().chart_flush_source.take();
+                    obj.flush_charts();
+                }
+            ),
+        );
+
+        imp.chart_flush_source.replace(Some(source));
+    }
+
+    /// Adds the queued values to the charts, creating the charts of new topics
+    fn flush_charts(&self) {
+        let pending = self.imp().pending_points.take();
+
+        for (topic, timestamp, value) in pending {
+            self.topic_chart(&topic).add_point_at(timestamp, value);
+        }
+    }
+
+    /// Chart of the topic, it's created along with its frame the first time
+    fn topic_chart(&self, topic: &str) -> MQTTyDataChart {
         let mut charts = self.imp().topic_charts.borrow_mut();
 
-        // Get or create chart for this topic
-        let chart = charts.entry(topic.to_string()).or_insert_with(|| {
-            let chart = MQTTyDataChart::new();
-            chart.set_topic(topic);
-            chart.set_hexpand(true);
-            chart.set_height_request(150);
-
-            // Add to chart box with a frame
-            let frame = gtk::Frame::new(Some(topic));
-            frame.set_child(Some(&chart));
-            frame.set_margin_start(4);
-            frame.set_margin_end(4);
-            frame.set_margin_top(4);
-            frame.set_margin_bottom(4);
-
-            self.imp().chart_box.append(&frame);
-            chart
+        charts
+            .entry(topic.to_string())
+            .or_insert_with(|| {
+                let chart = MQTTyDataChart::new();
+                chart.set_topic(topic);
+                chart.set_hexpand(true);
+                chart.set_height_request(150);
+
+                // Add to chart box with a frame, titled by the topic along with an export
+                // button
+                let export_button = gtk::Button::builder()
+                    .icon_name("document-save-symbolic")
+                    .tooltip_text(gettext("Export as CSV"))
+                    .css_classes(["flat", "circular"])
+                    .build();
+
+                export_button.connect_clicked(glib::clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    #[weak]
+                    chart,
+                    move |_| {
+                        glib::spawn_future_local(async move {
+                            obj.export_chart(&chart).await;
+                        });
+                    }
+                ));
+
+                let label = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+                label.append(&gtk::Label::new(Some(topic)));
+                label.append(&export_button);
+
+                let frame = gtk::Frame::new(None);
+                frame.set_label_widget(Some(&label));
+                frame.set_child(Some(&chart));
+                frame.set_margin_start(4);
+                frame.set_margin_end(4);
+                frame.set_margin_top(4);
+                frame.set_margin_bottom(4);
+
+                self.imp().chart_box.append(&frame);
+                chart
+            })
+            .clone()
+    }
+
+    /// Adds the filter to the tab, it's subscribed right away if the tab is
+    pub fn add_topic_filter(&self, topic_filter: &str) {
+        let filters = self.topic_filters();
+
+        if filters
+            .iter::<MQTTyTopicFilter>()
+            .flatten()
+            .any(|filter| filter.topic_filter() == topic_filter)
+        {
+            self.toast(
+                formatx!(gettext("The tab already has the filter {}"), topic_filter).unwrap(),
+                "dialog-information-symbolic",
+            );
+            return;
+        }
+
+        // A row left empty is filled rather than adding another one
+        let empty = filters
+            .iter::<MQTTyTopicFilter>()
+            .flatten()
+            .find(|filter| filter.topic_filter().is_empty());
+
+        let filter = empty.unwrap_or_else(|| {
+            let filter = MQTTyTopicFilter::new("", self.qos());
+            filters.append(&filter);
+            filter
         });
 
-        // Try to add the payload as a data point
-        chart.try_add_from_payload(payload);
+        // Setting the filter subscribes it, see on_topic_filter_changed()
+        filter.set_topic_filter(topic_filter);
+    }
+
+    /// Opens a tab of the publish view publishing to the topic, with the connection of
+    /// this tab
+    pub fn publish_to_topic(&self, topic: &str) {
+        // The publish view is another page of the view stack holding this tab
+        let Some(view_stack) = self
+            .ancestor(adw::ViewStack::static_type())
+            .and_downcast::<adw::ViewStack>()
+        else {
+            return;
+        };
+
+        let Some(publish_view) = view_stack
+            .child_by_name("publish")
+            .and_downcast::<MQTTyPublishView>()
+        else {
+            return;
+        };
+
+        let notebook = MQTTyPublishViewNotebook::new();
+        publish_view.open_notebook(&notebook);
+
+        let profile = self.imp().profile.borrow().clone();
+
+        if !profile.is_some_and(|profile| notebook.load_profile(&profile)) {
+            notebook.set_url(self.url());
+            notebook.set_username(self.username());
+            notebook.set_password(self.password());
+            notebook.set_mqtt_version(self.mqtt_version());
+            notebook.set_qos(self.qos());
+        }
+
+        // Set last, loading the profile sets the topic of the profile
+        notebook.set_topic(topic);
+
+        view_stack.set_visible_child(&publish_view);
+    }
+
+    /// Opens the dialog deleting every retained message of the branch
+    pub fn clear_retained_recursive(&self, item: &MQTTyTopicItem) {
+        let Some(client) = self.current_client() else {
+            self.toast_error(gettext("Cannot delete retained messages: not connected"));
+            return;
+        };
+
+        let dialog =
+            MQTTyClearRetainedDialog::new(item, &client, &self.imp().topic_tree_view, self.qos());
+        dialog.present(Some(self));
     }
 
     /// Clear a retained message by publishing an empty payload with retain flag
     pub async fn clear_retained_message(&self, topic: &str) {
-        let client_ref = self.imp().client.borrow();
-        if let Some(client) = client_ref.as_ref() {
-            let message = MQTTyClientMessage::new();
-            message.set_topic(topic);
-            message.set_body(&[]);
-            message.set_retained(true);
-            message.set_qos(self.qos());
-
-            match client.publish(&message).await {
-                Ok(_) => {
-                    tracing::info!("Cleared retained message for topic: {}", topic);
-                }
-                Err(e) => {
-                    tracing::error!("Failed to clear retained message for {}: {}", topic, e);
-                }
+        let Some(client) = self.current_client() else {
+            self.toast_error(gettext("Cannot delete the retained message: not connected"));
+            return;
+        };
+
+        let message = MQTTyClientMessage::new();
+        message.set_topic(topic);
+        message.set_body(&[]);
+        message.set_retained(true);
+        message.set_qos(self.qos());
+
+        match client.publish(&message).await {
+            Ok(_) => {
+                self.imp().topic_tree_view.forget_retained(topic);
+                self.toast(
+                    formatx!(gettext("Deleted the retained message of {}"), topic).unwrap(),
+                    "object-select-symbolic",
+                );
             }
-        } else {
-            tracing::warn!("Cannot clear retained message: not connected");
+            Err(e) => self.toast_error(
+                formatx!(
+                    gettext("Failed to delete the retained message of {}: {}"),
+                    topic,
+                    e
+                )
+                .unwrap(),
+            ),
         }
     }
 }
@@ -0,0 +1,249 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, OnceCell};
+use std::sync::LazyLock;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use gtk::glib::subclass::Signal;
+
+use crate::client::{MQTTyClientQos, MQTTyRetainHandling};
+use crate::objects::MQTTyTopicFilter;
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/github/otaxhu/MQTTy/ui/subscribe_view/topic_filter_row.ui")]
+    #[properties(wrapper_type = super::MQTTyTopicFilterRow)]
+    pub struct MQTTyTopicFilterRow {
+        #[property(get, construct_only)]
+        filter: OnceCell<MQTTyTopicFilter>,
+
+        /// MQTT v5 subscribe options are insensitive otherwise
+        #[property(get, set)]
+        mqtt_5: Cell<bool>,
+
+        #[template_child]
+        status_icon: TemplateChild<gtk::Image>,
+
+        #[template_child]
+        topic_filter_row: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        qos_0_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        qos_1_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        qos_2_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        no_local_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        retain_as_published_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        retain_handling_row: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        subscription_identifier_row: TemplateChild<adw::SpinRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyTopicFilterRow {
+        const NAME: &'static str = "MQTTyTopicFilterRow";
+
+        type Type = super::MQTTyTopicFilterRow;
+
+        type ParentType = adw::ExpanderRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::types::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyTopicFilterRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let filter = obj.filter();
+
+            filter
+                .bind_property("topic_filter", &*obj, "title")
+                .sync_create()
+                .transform_to(|_, topic_filter: String| {
+                    Some(if topic_filter.is_empty() {
+                        gettext("(empty filter)")
+                    } else {
+                        topic_filter
+                    })
+                })
+                .build();
+
+            filter
+                .bind_property("status", &*obj, "subtitle")
+                .sync_create()
+                .build();
+
+            // The filter is only changed when applied, so that it's not subscribed
+            // while typing
+            self.topic_filter_row.set_text(&filter.topic_filter());
+
+            let qos_buttons = [
+                (MQTTyClientQos::Qos0, &self.qos_0_button),
+                (MQTTyClientQos::Qos1, &self.qos_1_button),
+                (MQTTyClientQos::Qos2, &self.qos_2_button),
+            ];
+
+            for (qos, button) in qos_buttons {
+                if filter.qos() == qos {
+                    button.set_active(true);
+                }
+
+                button.connect_toggled(glib::clone!(
+                    #[weak]
+                    filter,
+                    move |btn| {
+                        if btn.is_active() {
+                            filter.set_qos(qos);
+                        }
+                    }
+                ));
+            }
+
+            filter
+                .bind_property("no_local", &*self.no_local_row, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            filter
+                .bind_property(
+                    "retain_as_published",
+                    &*self.retain_as_published_row,
+                    "active",
+                )
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            // Combo row items are in the same order as the enum variants
+            filter
+                .bind_property("retain_handling", &*self.retain_handling_row, "selected")
+                .bidirectional()
+                .sync_create()
+                .transform_to(|_, retain_handling: MQTTyRetainHandling| {
+                    Some(retain_handling as u32)
+                })
+                .transform_from(|_, selected: u32| Some(MQTTyRetainHandling::from(selected)))
+                .build();
+
+            filter
+                .bind_property(
+                    "subscription_identifier",
+                    &*self.subscription_identifier_row,
+                    "value",
+                )
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            filter.connect_status_notify(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.imp().update_status_icon()
+            ));
+
+            self.update_status_icon();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: LazyLock<Vec<Signal>> =
+                LazyLock::new(|| vec![Signal::builder("deleted").build()]);
+            &*SIGNALS
+        }
+    }
+    impl WidgetImpl for MQTTyTopicFilterRow {}
+    impl ListBoxRowImpl for MQTTyTopicFilterRow {}
+    impl PreferencesRowImpl for MQTTyTopicFilterRow {}
+    impl ExpanderRowImpl for MQTTyTopicFilterRow {}
+
+    #[gtk::template_callbacks]
+    impl MQTTyTopicFilterRow {
+        #[template_callback]
+        fn on_topic_filter_applied(&self) {
+            let text = self.topic_filter_row.text();
+
+            let filter = self.obj().filter();
+
+            if filter.topic_filter() != text {
+                filter.set_topic_filter(text);
+            }
+        }
+
+        #[template_callback]
+        fn on_delete_clicked(&self) {
+            self.obj().emit_by_name::<()>("deleted", &[]);
+        }
+
+        /// Shows the SUBACK result next to the subtitle
+        fn update_status_icon(&self) {
+            let filter = self.obj().filter();
+
+            let icon = &self.status_icon;
+
+            icon.set_visible(!filter.status().is_empty());
+
+            if filter.failed() {
+                icon.set_icon_name(Some("dialog-error-symbolic"));
+                icon.remove_css_class("success");
+                icon.add_css_class("error");
+            } else {
+                icon.set_icon_name(Some("object-select-symbolic"));
+                icon.remove_css_class("error");
+                icon.add_css_class("success");
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Emits "deleted" when the delete button is pressed, the filter is removed by the
+    /// upper layers
+    pub struct MQTTyTopicFilterRow(ObjectSubclass<imp::MQTTyTopicFilterRow>)
+        @extends gtk::Widget, gtk::ListBoxRow, adw::PreferencesRow, adw::ExpanderRow,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Actionable;
+}
+
+impl MQTTyTopicFilterRow {
+    pub fn new(filter: &MQTTyTopicFilter) -> Self {
+        glib::Object::builder().property("filter", filter).build()
+    }
+}
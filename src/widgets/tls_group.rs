@@ -0,0 +1,226 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::{Cell, RefCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::client::{non_empty, MQTTyTlsVersion, TlsOptions};
+
+mod imp {
+
+    use super::*;
+
+    #[derive(gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/github/otaxhu/MQTTy/ui/tls_group.ui")]
+    #[properties(wrapper_type = super::MQTTyTlsGroup)]
+    pub struct MQTTyTlsGroup {
+        #[property(get, set)]
+        enable_tls: Cell<bool>,
+
+        #[property(get, set)]
+        ca_cert_path: RefCell<String>,
+
+        #[property(get, set)]
+        client_cert_path: RefCell<String>,
+
+        #[property(get, set)]
+        client_key_path: RefCell<String>,
+
+        #[property(get, set)]
+        key_passphrase: RefCell<String>,
+
+        #[property(get, set)]
+        verify_server: Cell<bool>,
+
+        #[property(get, set)]
+        verify_hostname: Cell<bool>,
+
+        /// Comma separated
+        #[property(get, set)]
+        alpn: RefCell<String>,
+
+        #[property(get, set, builder(Default::default()))]
+        tls_version: Cell<MQTTyTlsVersion>,
+
+        #[property(get, set)]
+        ciphers: RefCell<String>,
+
+        #[template_child]
+        tls_version_row: TemplateChild<adw::ComboRow>,
+    }
+
+    impl Default for MQTTyTlsGroup {
+        fn default() -> Self {
+            Self {
+                enable_tls: Default::default(),
+                ca_cert_path: Default::default(),
+                client_cert_path: Default::default(),
+                client_key_path: Default::default(),
+                key_passphrase: Default::default(),
+                verify_server: Cell::new(true),
+                verify_hostname: Cell::new(true),
+                alpn: Default::default(),
+                tls_version: Default::default(),
+                ciphers: Default::default(),
+                tls_version_row: Default::default(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyTlsGroup {
+        const NAME: &'static str = "MQTTyTlsGroup";
+
+        type Type = super::MQTTyTlsGroup;
+
+        type ParentType = adw::PreferencesGroup;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::types::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyTlsGroup {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            // Combo row items are in the same order as the enum variants
+            obj.bind_property("tls_version", &*self.tls_version_row, "selected")
+                .bidirectional()
+                .sync_create()
+                .transform_to(|_, version: MQTTyTlsVersion| Some(version as u32))
+                .transform_from(|_, selected: u32| Some(MQTTyTlsVersion::from(selected)))
+                .build();
+        }
+    }
+    impl WidgetImpl for MQTTyTlsGroup {}
+    impl PreferencesGroupImpl for MQTTyTlsGroup {}
+
+    #[gtk::template_callbacks]
+    impl MQTTyTlsGroup {
+        #[template_callback]
+        fn on_ca_cert_clicked(&self) {
+            self.open_file_dialog("ca_cert_path", gettext("Select CA Certificate"));
+        }
+
+        #[template_callback]
+        fn on_client_cert_clicked(&self) {
+            self.open_file_dialog("client_cert_path", gettext("Select Client Certificate"));
+        }
+
+        #[template_callback]
+        fn on_client_key_clicked(&self) {
+            self.open_file_dialog("client_key_path", gettext("Select Client Key"));
+        }
+
+        #[template_callback]
+        fn on_clear_ca_cert(&self) {
+            self.obj().set_ca_cert_path("");
+        }
+
+        #[template_callback]
+        fn on_clear_client_cert(&self) {
+            self.obj().set_client_cert_path("");
+        }
+
+        #[template_callback]
+        fn on_clear_client_key(&self) {
+            self.obj().set_client_key_path("");
+        }
+
+        fn open_file_dialog(&self, property: &'static str, title: String) {
+            let obj = self.obj().clone();
+
+            let filter = gtk::FileFilter::new();
+            filter.add_pattern("*.pem");
+            filter.add_pattern("*.crt");
+            filter.add_pattern("*.key");
+            filter.add_pattern("*.cer");
+            filter.set_name(Some(&gettext("Certificate files")));
+
+            let filters = gio::ListStore::new::<gtk::FileFilter>();
+            filters.append(&filter);
+
+            let dialog = gtk::FileDialog::builder()
+                .title(&title)
+                .filters(&filters)
+                .modal(true)
+                .build();
+
+            let window = obj.root().and_downcast::<gtk::Window>();
+
+            dialog.open(window.as_ref(), gio::Cancellable::NONE, move |result| {
+                if let Some(path) = result.ok().and_then(|file| file.path()) {
+                    obj.set_property(property, path.to_string_lossy().to_string());
+                }
+            });
+        }
+    }
+}
+
+glib::wrapper! {
+    /// TLS settings rows, shared by the authentication tabs and the connection profiles
+    pub struct MQTTyTlsGroup(ObjectSubclass<imp::MQTTyTlsGroup>)
+        @extends gtk::Widget, adw::PreferencesGroup,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl MQTTyTlsGroup {
+    /// TLS fields of the options, the WebSocket ones are left to MQTTyWebSocketGroup
+    pub fn tls_options(&self) -> TlsOptions {
+        if !self.enable_tls() {
+            return TlsOptions::default();
+        }
+
+        TlsOptions {
+            enabled: true,
+            ca_cert_path: non_empty(self.ca_cert_path()),
+            client_cert_path: non_empty(self.client_cert_path()),
+            client_key_path: non_empty(self.client_key_path()),
+            key_passphrase: non_empty(self.key_passphrase()),
+            verify_server: self.verify_server(),
+            verify_hostname: self.verify_hostname(),
+            alpn: TlsOptions::parse_alpn(&self.alpn()),
+            tls_version: self.tls_version(),
+            ciphers: non_empty(self.ciphers()),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_tls_options(&self, options: &TlsOptions) {
+        self.set_enable_tls(options.enabled);
+        self.set_ca_cert_path(options.ca_cert_path.clone().unwrap_or_default());
+        self.set_client_cert_path(options.client_cert_path.clone().unwrap_or_default());
+        self.set_client_key_path(options.client_key_path.clone().unwrap_or_default());
+        self.set_key_passphrase(options.key_passphrase.clone().unwrap_or_default());
+        self.set_verify_server(options.verify_server);
+        self.set_verify_hostname(options.verify_hostname);
+        self.set_alpn(options.alpn.join(", "));
+        self.set_tls_version(options.tls_version);
+        self.set_ciphers(options.ciphers.clone().unwrap_or_default());
+    }
+}
@@ -0,0 +1,356 @@
+use std::cell::{Cell, OnceCell};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::glib;
+
+use super::{MQTTyTopicItem, MQTTyTopicTreeView};
+use crate::client::{MQTTyClient, MQTTyClientError, MQTTyClientMessage, MQTTyClientQos};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyClearRetainedDialog)]
+    pub struct MQTTyClearRetainedDialog {
+        /// Branch whose retained messages are deleted
+        #[property(get, construct_only)]
+        item: OnceCell<MQTTyTopicItem>,
+
+        /// Client of the tab the tree belongs to
+        #[property(get, construct_only)]
+        client: OnceCell<MQTTyClient>,
+
+        /// Tree told about the topics once they're cleared
+        #[property(get, construct_only)]
+        tree_view: OnceCell<MQTTyTopicTreeView>,
+
+        #[property(get, construct_only, builder(Default::default()))]
+        qos: Cell<MQTTyClientQos>,
+
+        /// Topics holding a retained message when the dialog was opened
+        pub topics: OnceCell<Vec<String>>,
+
+        pub running: Cell<bool>,
+
+        pub stop_requested: Cell<bool>,
+
+        pub list_box: OnceCell<gtk::ListBox>,
+
+        pub status_label: OnceCell<gtk::Label>,
+
+        pub progress_bar: OnceCell<gtk::ProgressBar>,
+
+        pub start_button: OnceCell<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyClearRetainedDialog {
+        const NAME: &'static str = "MQTTyClearRetainedDialog";
+        type Type = super::MQTTyClearRetainedDialog;
+        type ParentType = adw::Dialog;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyClearRetainedDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().setup_ui();
+        }
+    }
+    impl WidgetImpl for MQTTyClearRetainedDialog {}
+    impl AdwDialogImpl for MQTTyClearRetainedDialog {
+        fn closed(&self) {
+            self.stop_requested.set(true);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Lists the retained messages of a branch of the topic tree and, once confirmed,
+    /// deletes them by publishing an empty retained message to every topic
+    pub struct MQTTyClearRetainedDialog(ObjectSubclass<imp::MQTTyClearRetainedDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl MQTTyClearRetainedDialog {
+    pub fn new(
+        item: &MQTTyTopicItem,
+        client: &MQTTyClient,
+        tree_view: &MQTTyTopicTreeView,
+        qos: MQTTyClientQos,
+    ) -> Self {
+        glib::Object::builder()
+            .property("item", item)
+            .property("client", client)
+            .property("tree-view", tree_view)
+            .property("qos", qos)
+            .build()
+    }
+
+    fn setup_ui(&self) {
+        let imp = self.imp();
+
+        self.set_content_width(500);
+        self.set_content_height(600);
+
+        let mut topics = self
+            .item()
+            .retained_descendants()
+            .iter()
+            .map(|item| item.full_topic())
+            .collect::<Vec<_>>();
+        topics.sort();
+
+        let title = adw::WindowTitle::new(
+            &gettext("Delete Retained Messages"),
+            &self.item().full_topic(),
+        );
+
+        let header_bar = adw::HeaderBar::new();
+        header_bar.set_title_widget(Some(&title));
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&header_bar);
+
+        let page = adw::PreferencesPage::new();
+
+        // Progress
+        let progress_bar = gtk::ProgressBar::new();
+
+        let status_label = gtk::Label::builder()
+            .css_classes(["dim-label"])
+            .wrap(true)
+            .build();
+
+        let start_button = gtk::Button::builder()
+            .css_classes(["pill"])
+            .halign(gtk::Align::Center)
+            .sensitive(!topics.is_empty())
+            .build();
+
+        start_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| obj.on_start_clicked()
+        ));
+
+        let progress_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .build();
+        progress_box.append(&progress_bar);
+        progress_box.append(&status_label);
+        progress_box.append(&start_button);
+
+        let progress_group = adw::PreferencesGroup::new();
+        progress_group.add(&progress_box);
+        page.add(&progress_group);
+
+        // Topics
+        let list_box = gtk::ListBox::builder()
+            .css_classes(["boxed-list"])
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+
+        for topic in &topics {
+            let row = adw::ActionRow::builder()
+                .title(topic)
+                .use_markup(false)
+                .build();
+            list_box.append(&row);
+        }
+
+        let topics_group = adw::PreferencesGroup::builder()
+            .title(gettext("Topics"))
+            .description(if topics.is_empty() {
+                gettext("No topic of this branch holds a retained message")
+            } else {
+                formatx!(
+                    gettext("{} retained messages will be deleted from the broker"),
+                    topics.len()
+                )
+                .unwrap()
+            })
+            .build();
+        topics_group.add(&list_box);
+        page.add(&topics_group);
+
+        toolbar_view.set_content(Some(&page));
+        self.set_child(Some(&toolbar_view));
+
+        let _ = imp.topics.set(topics);
+        let _ = imp.list_box.set(list_box);
+        let _ = imp.status_label.set(status_label);
+        let _ = imp.progress_bar.set(progress_bar);
+        let _ = imp.start_button.set(start_button);
+
+        self.set_running(false);
+        self.update_progress(0);
+    }
+
+    fn on_start_clicked(&self) {
+        let imp = self.imp();
+
+        if imp.running.get() {
+            imp.stop_requested.set(true);
+            return;
+        }
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let status = match obj.start().await {
+                    Ok(status) => status,
+                    Err(e) => formatx!(gettext("Deleting failed: {}"), e).unwrap(),
+                };
+
+                obj.imp().status_label.get().unwrap().set_label(&status);
+            }
+        ));
+    }
+
+    /// Deletes the retained messages, returns the report of the run
+    async fn start(&self) -> Result<String, String> {
+        let imp = self.imp();
+
+        let client = self.client();
+        let topics = imp.topics.get().unwrap();
+        let list_box = imp.list_box.get().unwrap();
+
+        for i in 0..topics.len() {
+            if let Some(row) = list_box.row_at_index(i as i32) {
+                row.remove_css_class("error");
+            }
+        }
+
+        imp.stop_requested.set(false);
+        self.set_running(true);
+
+        client
+            .ensure_connected()
+            .await
+            .inspect_err(|_| self.set_running(false))
+            .map_err(|e| e.to_string())?;
+
+        let (handled, failures) = self.clear_all(&client, topics).await;
+
+        self.set_running(false);
+
+        let cleared = handled - failures.len();
+
+        // Nothing is left to delete, running it again would only repeat the same
+        if cleared == topics.len() {
+            imp.start_button.get().unwrap().set_sensitive(false);
+        }
+
+        let mut status = formatx!(
+            gettext("Deleted {} of {} retained messages"),
+            cleared,
+            topics.len()
+        )
+        .unwrap();
+
+        if let Some((i, e)) = failures.first() {
+            status.push('\n');
+            status.push_str(
+                &formatx!(
+                    gettext("{} failed, the first one on {}: {}"),
+                    failures.len(),
+                    topics[*i],
+                    e
+                )
+                .unwrap(),
+            );
+        }
+
+        Ok(status)
+    }
+
+    /// Publishes an empty retained message to every topic in order, a failed topic is
+    /// marked and doesn't stop the rest
+    ///
+    /// Returns how many topics were handled before stopping, and the position of every
+    /// topic that failed along with its error
+    async fn clear_all(
+        &self,
+        client: &MQTTyClient,
+        topics: &[String],
+    ) -> (usize, Vec<(usize, MQTTyClientError)>) {
+        let imp = self.imp();
+        let list_box = imp.list_box.get().unwrap();
+        let tree_view = self.tree_view();
+
+        let mut handled = 0;
+        let mut failures = vec![];
+
+        for (i, topic) in topics.iter().enumerate() {
+            if imp.stop_requested.get() {
+                break;
+            }
+
+            let message = MQTTyClientMessage::new();
+            message.set_topic(topic.as_str());
+            message.set_body(&[]);
+            message.set_retained(true);
+            message.set_qos(self.qos());
+
+            match client.publish(&message).await {
+                Ok(()) => tree_view.forget_retained(topic),
+                Err(e) => {
+                    if let Some(row) = list_box.row_at_index(i as i32) {
+                        row.add_css_class("error");
+                    }
+                    failures.push((i, e));
+                }
+            }
+
+            handled += 1;
+            self.update_progress(handled);
+        }
+
+        (handled, failures)
+    }
+
+    fn set_running(&self, running: bool) {
+        let imp = self.imp();
+
+        imp.running.set(running);
+
+        let start_button = imp.start_button.get().unwrap();
+
+        if running {
+            start_button.set_label(&gettext("Stop"));
+            start_button.remove_css_class("destructive-action");
+            start_button.add_css_class("suggested-action");
+        } else {
+            start_button.set_label(&gettext("Delete"));
+            start_button.remove_css_class("suggested-action");
+            start_button.add_css_class("destructive-action");
+        }
+    }
+
+    /// Shows how many of the topics were handled so far
+    fn update_progress(&self, done: usize) {
+        let imp = self.imp();
+
+        let (Some(progress_bar), Some(topics)) = (imp.progress_bar.get(), imp.topics.get()) else {
+            return;
+        };
+
+        let total = topics.len();
+
+        progress_bar.set_fraction(if total == 0 {
+            0.0
+        } else {
+            done as f64 / total as f64
+        });
+        progress_bar.set_text(Some(&formatx!(gettext("{} of {}"), done, total).unwrap()));
+        progress_bar.set_show_text(total > 0);
+    }
+}
@@ -0,0 +1,434 @@
+use std::cell::{OnceCell, RefCell};
+use std::time::Duration;
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib};
+use sourceview::prelude::*;
+
+use super::MQTTyTopicItem;
+use crate::client::MQTTyClientMessage;
+use crate::widgets::{MQTTyDataChart, MQTTyMessageDetailDialog};
+
+/// Busy topics refresh the pane at most this often
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::MQTTyTopicDetailPane)]
+    pub struct MQTTyTopicDetailPane {
+        /// Topic shown by the pane, the pane is hidden while there's none
+        #[property(get, set = Self::set_item, nullable)]
+        item: RefCell<Option<MQTTyTopicItem>>,
+
+        pub item_handler: RefCell<Option<glib::SignalHandlerId>>,
+
+        pub refresh_source: RefCell<Option<glib::SourceId>>,
+
+        /// Last messages of the topic, newest first
+        pub history: OnceCell<gio::ListStore>,
+
+        pub title_label: OnceCell<gtk::Label>,
+
+        pub subtitle_label: OnceCell<gtk::Label>,
+
+        pub body_buffer: OnceCell<sourceview::Buffer>,
+
+        pub properties_list: OnceCell<gtk::ListBox>,
+
+        pub diff_buffer: OnceCell<sourceview::Buffer>,
+
+        pub chart: OnceCell<MQTTyDataChart>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyTopicDetailPane {
+        const NAME: &'static str = "MQTTyTopicDetailPane";
+        type Type = super::MQTTyTopicDetailPane;
+        type ParentType = gtk::Box;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyTopicDetailPane {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().setup_ui();
+        }
+
+        fn dispose(&self) {
+            self.disconnect_item();
+
+            if let Some(source) = self.refresh_source.take() {
+                source.remove();
+            }
+        }
+    }
+
+    impl WidgetImpl for MQTTyTopicDetailPane {}
+    impl BoxImpl for MQTTyTopicDetailPane {}
+
+    impl MQTTyTopicDetailPane {
+        fn set_item(&self, item: Option<MQTTyTopicItem>) {
+            if *self.item.borrow() == item {
+                return;
+            }
+
+            self.disconnect_item();
+
+            let obj = self.obj();
+
+            if let Some(item) = &item {
+                let handler = item.connect_latest_message_notify(glib::clone!(
+                    #[weak]
+                    obj,
+                    move |_| obj.queue_refresh()
+                ));
+
+                self.item_handler.replace(Some(handler));
+            }
+
+            obj.set_visible(item.is_some());
+            self.item.replace(item);
+
+            obj.refresh();
+            obj.notify_item();
+        }
+
+        fn disconnect_item(&self) {
+            if let (Some(item), Some(handler)) =
+                (self.item.borrow().as_ref(), self.item_handler.take())
+            {
+                item.disconnect(handler);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Latest message of a topic of the tree, with its properties, its last messages,
+    /// the changes from the previous one and its chart
+    pub struct MQTTyTopicDetailPane(ObjectSubclass<imp::MQTTyTopicDetailPane>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl MQTTyTopicDetailPane {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    fn setup_ui(&self) {
+        let imp = self.imp();
+
+        self.set_orientation(gtk::Orientation::Vertical);
+        // Nothing to show until a topic is selected
+        self.set_visible(false);
+
+        // Header
+        let title_label = gtk::Label::builder()
+            .css_classes(["heading"])
+            .xalign(0.0)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .selectable(true)
+            .build();
+
+        let subtitle_label = gtk::Label::builder()
+            .css_classes(["dim-label", "caption"])
+            .xalign(0.0)
+            .wrap(true)
+            .build();
+
+        let header = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(2)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(6)
+            .margin_bottom(6)
+            .build();
+        header.append(&title_label);
+        header.append(&subtitle_label);
+
+        self.append(&header);
+        self.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+
+        let notebook = gtk::Notebook::builder()
+            .show_border(false)
+            .scrollable(true)
+            .vexpand(true)
+            .build();
+
+        // Latest message
+        let (body_view, body_buffer) = source_view("json");
+        notebook.append_page(
+            &body_view,
+            Some(&gtk::Label::new(Some(&gettext("Message")))),
+        );
+
+        // Properties of the latest message
+        let properties_list = gtk::ListBox::builder()
+            .css_classes(["boxed-list"])
+            .selection_mode(gtk::SelectionMode::None)
+            .valign(gtk::Align::Start)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(8)
+            .margin_bottom(8)
+            .build();
+
+        let properties_page = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&properties_list)
+            .build();
+        notebook.append_page(
+            &properties_page,
+            Some(&gtk::Label::new(Some(&gettext("Properties")))),
+        );
+
+        // Last messages
+        let history = gio::ListStore::new::<MQTTyClientMessage>();
+
+        let history_list = gtk::ListBox::builder()
+            .css_classes(["navigation-sidebar"])
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+
+        history_list.bind_model(Some(&history), |item| {
+            history_row(item.downcast_ref().unwrap()).upcast()
+        });
+
+        history_list.connect_row_activated(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_, row| obj.on_history_activated(row.index())
+        ));
+
+        let history_page = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&history_list)
+            .build();
+        notebook.append_page(
+            &history_page,
+            Some(&gtk::Label::new(Some(&gettext("History")))),
+        );
+
+        // Changes from the previous message
+        let (diff_view, diff_buffer) = source_view("diff");
+        notebook.append_page(&diff_view, Some(&gtk::Label::new(Some(&gettext("Diff")))));
+
+        // Numeric values of the last messages
+        let chart = MQTTyDataChart::new();
+        chart.set_vexpand(true);
+        chart.set_margin_start(8);
+        chart.set_margin_end(8);
+        chart.set_margin_top(8);
+        chart.set_margin_bottom(8);
+        notebook.append_page(&chart, Some(&gtk::Label::new(Some(&gettext("Chart")))));
+
+        self.append(&notebook);
+
+        let _ = imp.history.set(history);
+        let _ = imp.title_label.set(title_label);
+        let _ = imp.subtitle_label.set(subtitle_label);
+        let _ = imp.body_buffer.set(body_buffer);
+        let _ = imp.properties_list.set(properties_list);
+        let _ = imp.diff_buffer.set(diff_buffer);
+        let _ = imp.chart.set(chart);
+
+        self.refresh();
+    }
+
+    fn queue_refresh(&self) {
+        let imp = self.imp();
+
+        if imp.refresh_source.borrow().is_some() {
+            return;
+        }
+
+        let source = glib::timeout_add_local_once(
+            REFRESH_INTERVAL,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.imp().refresh_source.take();
+                    obj.refresh();
+                }
+            ),
+        );
+
+        imp.refresh_source.replace(Some(source));
+    }
+
+    /// Shows the current state of the topic
+    fn refresh(&self) {
+        let imp = self.imp();
+
+        let (
+            Some(history_store),
+            Some(title_label),
+            Some(subtitle_label),
+            Some(body_buffer),
+            Some(properties_list),
+            Some(diff_buffer),
+            Some(chart),
+        ) = (
+            imp.history.get(),
+            imp.title_label.get(),
+            imp.subtitle_label.get(),
+            imp.body_buffer.get(),
+            imp.properties_list.get(),
+            imp.diff_buffer.get(),
+            imp.chart.get(),
+        )
+        else {
+            return;
+        };
+
+        let item = self.item();
+        let history = item.as_ref().map(|item| item.history()).unwrap_or_default();
+
+        title_label.set_label(
+            &item
+                .as_ref()
+                .map(|item| item.full_topic())
+                .unwrap_or_default(),
+        );
+        subtitle_label.set_label(&match history.len() {
+            0 => gettext("No messages on this very topic"),
+            n => formatx::formatx!(gettext("Last {} messages"), n).unwrap(),
+        });
+
+        history_store.splice(0, history_store.n_items(), &history);
+
+        let latest = history.first();
+        let previous = history.get(1);
+
+        body_buffer.set_text(
+            &latest
+                .map(MQTTyMessageDetailDialog::format_body)
+                .unwrap_or_default(),
+        );
+
+        match latest {
+            Some(latest) => MQTTyMessageDetailDialog::fill_properties(properties_list, latest),
+            None => properties_list.remove_all(),
+        }
+
+        diff_buffer.set_text(&match (previous, latest) {
+            (Some(previous), Some(latest)) => MQTTyMessageDetailDialog::diff(
+                &MQTTyMessageDetailDialog::format_body(previous),
+                &MQTTyMessageDetailDialog::format_body(latest),
+            ),
+            _ => gettext("No previous message to compare"),
+        });
+
+        chart.clear();
+        chart.set_topic(&title_label.label());
+
+        for message in history.iter().rev() {
+            let body = message.body();
+
+            let (Some(value), Some(received_at)) = (
+                MQTTyDataChart::parse_value(&String::from_utf8_lossy(&body)),
+                message.received_at(),
+            ) else {
+                continue;
+            };
+
+            let timestamp = received_at.to_unix() as f64 + received_at.microsecond() as f64 / 1e6;
+            chart.add_point_at(timestamp, value);
+        }
+    }
+
+    /// Opens the message of the history in the detail dialog, diffed against the one
+    /// received before it
+    fn on_history_activated(&self, index: i32) {
+        let Some(history) = self.imp().history.get() else {
+            return;
+        };
+
+        let Some(message) = history
+            .item(index as u32)
+            .and_downcast::<MQTTyClientMessage>()
+        else {
+            return;
+        };
+
+        let previous = history
+            .item(index as u32 + 1)
+            .and_downcast::<MQTTyClientMessage>();
+
+        let dialog = MQTTyMessageDetailDialog::new();
+        dialog.set_message(&message, previous.as_ref());
+        dialog.present(Some(self));
+    }
+}
+
+impl Default for MQTTyTopicDetailPane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read-only source view highlighting the given language, inside a scrolled window
+fn source_view(language: &str) -> (gtk::ScrolledWindow, sourceview::Buffer) {
+    let buffer = sourceview::Buffer::new(None);
+
+    if let Some(language) = sourceview::LanguageManager::default().language(language) {
+        buffer.set_language(Some(&language));
+    }
+
+    let view = sourceview::View::builder()
+        .buffer(&buffer)
+        .editable(false)
+        .monospace(true)
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .build();
+
+    let scrolled = gtk::ScrolledWindow::builder()
+        .vexpand(true)
+        .child(&view)
+        .build();
+
+    (scrolled, buffer)
+}
+
+/// Row of a message of the history, its time along with the start of its payload
+fn history_row(message: &MQTTyClientMessage) -> gtk::ListBoxRow {
+    let time = message
+        .received_at()
+        .and_then(|received_at| received_at.format("%X").ok())
+        .map(|received_at| received_at.to_string())
+        .unwrap_or_default();
+
+    let body = message.body();
+    let payload = String::from_utf8_lossy(&body);
+    let payload = payload.lines().next().unwrap_or_default();
+
+    let time_label = gtk::Label::builder()
+        .label(time)
+        .css_classes(["dim-label", "caption", "numeric"])
+        .build();
+
+    let payload_label = gtk::Label::builder()
+        .label(payload)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+
+    let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    row_box.append(&time_label);
+    row_box.append(&payload_label);
+
+    gtk::ListBoxRow::builder()
+        .child(&row_box)
+        .activatable(true)
+        .build()
+}
@@ -1,9 +1,20 @@
-use std::cell::{OnceCell, RefCell};
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
 use gtk::{gio, glib};
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
+use super::pattern::MQTTyTopicPattern;
+use crate::client::{MQTTyClientMessage, MQTTyClientQos};
+
+/// Messages kept by every topic, the same number of points the charts show
+///
+/// The messages are owned by the messages list of the tab, so they're gone from the
+/// histories as soon as the list evicts them, keeping the histories within its limits
+pub const TOPIC_HISTORY_LENGTH: usize = 100;
+
 mod imp {
     use super::*;
 
@@ -19,7 +30,70 @@ mod imp {
         #[property(get, set)]
         payload: RefCell<String>,
 
+        /// Latest message received by this very topic, None for branches that never
+        /// received one
+        #[property(get, nullable)]
+        pub latest_message: RefCell<Option<MQTTyClientMessage>>,
+
+        /// Last messages received by this very topic, newest first, see
+        /// TOPIC_HISTORY_LENGTH
+        pub history: RefCell<VecDeque<glib::WeakRef<MQTTyClientMessage>>>,
+
+        /// Messages received by this topic and its descendants
+        #[property(get)]
+        pub message_count: Cell<u64>,
+
+        /// Messages per second of this topic and its descendants, over the last tick
+        #[property(get)]
+        pub rate: Cell<f64>,
+
+        /// Size of the messages received by this topic and its descendants
+        #[property(get)]
+        pub bytes: Cell<u64>,
+
+        /// When the latest message of this topic or its descendants was received
+        #[property(get, nullable)]
+        pub last_received: RefCell<Option<glib::DateTime>>,
+
+        /// QoS of the latest message of this topic or its descendants
+        #[property(get, builder(Default::default()))]
+        pub qos: Cell<MQTTyClientQos>,
+
+        /// Whether the broker holds a retained message for this very topic, as far as
+        /// the received messages tell
+        #[property(get)]
+        pub retained: Cell<bool>,
+
+        /// Topics of this subtree, this one included, the broker holds a retained
+        /// message for
+        #[property(get)]
+        pub retained_count: Cell<u32>,
+
+        /// Set while the topic keeps receiving messages, until a tick goes by without
+        /// any
+        #[property(get)]
+        pub highlighted: Cell<bool>,
+
+        /// Messages received since the last tick
+        pub tick_count: Cell<u32>,
+
+        /// Whether the topic matches the filter of the tree
+        #[property(get)]
+        pub matched: Cell<bool>,
+
+        /// Set when neither the topic nor any of its descendants match the filter of
+        /// the tree
+        pub filtered_out: Cell<bool>,
+
+        /// Set when any of the descendants matches the filter of the tree
+        pub matched_below: Cell<bool>,
+
+        /// Children in the order they were added, the model of the tree rows
         pub children: OnceCell<gio::ListStore>,
+
+        /// Same children as the store, by name, so that topics are found without
+        /// scanning every sibling
+        pub children_index: RefCell<HashMap<String, super::MQTTyTopicItem>>,
     }
 
     #[glib::object_subclass]
@@ -53,19 +127,265 @@ impl MQTTyTopicItem {
         self.imp().children.get().cloned().unwrap_or_else(|| gio::ListStore::new::<MQTTyTopicItem>())
     }
 
+    /// Appends the item to the children, replacing the child of the same name if any
     pub fn add_child(&self, item: &MQTTyTopicItem) {
-        self.children().append(item);
+        let children = self.children();
+
+        let previous = self
+            .imp()
+            .children_index
+            .borrow_mut()
+            .insert(item.name(), item.clone());
+
+        if let Some(position) = previous.and_then(|previous| children.find(&previous)) {
+            children.remove(position);
+        }
+
+        children.append(item);
     }
-    
+
     pub fn find_child(&self, name: &str) -> Option<MQTTyTopicItem> {
-        let children = self.children();
-        for i in 0..children.n_items() {
-            if let Some(item) = children.item(i).and_downcast::<MQTTyTopicItem>() {
-                if item.name() == name {
-                    return Some(item);
-                }
+        self.imp().children_index.borrow().get(name).cloned()
+    }
+
+    /// Child of the given name, it's created if it doesn't exist yet
+    pub fn ensure_child(&self, name: &str, full_topic: &str) -> MQTTyTopicItem {
+        if let Some(item) = self.find_child(name) {
+            return item;
+        }
+
+        let item = MQTTyTopicItem::new(name, full_topic);
+        self.add_child(&item);
+        item
+    }
+
+    /// Adds a message received by this very topic to its history
+    pub fn push_message(&self, message: &MQTTyClientMessage) {
+        let imp = self.imp();
+
+        {
+            let mut history = imp.history.borrow_mut();
+            history.retain(|m| m.upgrade().is_some());
+            history.push_front(message.downgrade());
+            history.truncate(TOPIC_HISTORY_LENGTH);
+        }
+
+        let body = message.body();
+        self.set_payload(String::from_utf8_lossy(&body));
+
+        imp.latest_message.replace(Some(message.clone()));
+        self.notify_latest_message();
+    }
+
+    /// Last messages of this very topic, newest first
+    pub fn history(&self) -> Vec<MQTTyClientMessage> {
+        self.imp()
+            .history
+            .borrow()
+            .iter()
+            .filter_map(|m| m.upgrade())
+            .collect()
+    }
+
+    /// Message of this topic received right before the given one, None if it's the
+    /// first one or it's no longer in the history
+    pub fn previous_message(&self, message: &MQTTyClientMessage) -> Option<MQTTyClientMessage> {
+        let history = self.history();
+
+        let position = history.iter().position(|m| m == message)?;
+
+        history.get(position + 1).cloned()
+    }
+
+    /// Forgets the history of every topic of the subtree, the latest message of each
+    /// topic is kept, it's what the tree shows
+    pub fn clear_history_recursive(&self) {
+        for child in self.children().iter::<MQTTyTopicItem>().flatten() {
+            child.clear_history_recursive();
+        }
+
+        if self.imp().history.take().is_empty() {
+            return;
+        }
+
+        // Tells the detail pane to refresh
+        self.notify_latest_message();
+    }
+
+    /// Accounts a message received by this topic or one of its descendants
+    ///
+    /// Returns true if the item wasn't highlighted yet, that is, if it has to be ticked
+    /// from now on
+    pub fn record(&self, message: &MQTTyClientMessage) -> bool {
+        let imp = self.imp();
+
+        imp.message_count.set(imp.message_count.get() + 1);
+        self.notify_message_count();
+
+        imp.bytes.set(imp.bytes.get() + message.size() as u64);
+        self.notify_bytes();
+
+        let received_at = message
+            .received_at()
+            .or_else(|| glib::DateTime::now_local().ok());
+        imp.last_received.replace(received_at);
+        self.notify_last_received();
+
+        if imp.qos.replace(message.qos()) != message.qos() {
+            self.notify_qos();
+        }
+
+        imp.tick_count.set(imp.tick_count.get() + 1);
+
+        if imp.highlighted.replace(true) {
+            return false;
+        }
+
+        self.notify_highlighted();
+        true
+    }
+
+    /// Sets whether the broker holds a retained message for this very topic, returns
+    /// the change of retained_count it makes, to be applied to every ancestor
+    pub fn set_retained(&self, retained: bool) -> i32 {
+        if self.imp().retained.replace(retained) == retained {
+            return 0;
+        }
+
+        self.notify_retained();
+
+        let delta = if retained { 1 } else { -1 };
+        self.add_retained_count(delta);
+        delta
+    }
+
+    /// This topic and its descendants the broker holds a retained message for,
+    /// branches without any aren't walked
+    pub fn retained_descendants(&self) -> Vec<MQTTyTopicItem> {
+        let mut items = vec![];
+        let mut pending = vec![self.clone()];
+
+        while let Some(item) = pending.pop() {
+            if item.retained_count() == 0 {
+                continue;
             }
+
+            if item.retained() {
+                items.push(item.clone());
+            }
+
+            pending.extend(item.children().iter::<MQTTyTopicItem>().flatten());
+        }
+
+        items
+    }
+
+    pub fn add_retained_count(&self, delta: i32) {
+        if delta == 0 {
+            return;
+        }
+
+        let imp = self.imp();
+        imp.retained_count
+            .set(imp.retained_count.get().saturating_add_signed(delta));
+        self.notify_retained_count();
+    }
+
+    pub fn filtered_out(&self) -> bool {
+        self.imp().filtered_out.get()
+    }
+
+    /// Checks the topic against the filter of the tree, it's kept visible if it
+    /// matches or if any of its children is visible, so children must be filtered
+    /// first. A None pattern shows every topic
+    pub fn filter(&self, pattern: Option<&MQTTyTopicPattern>) {
+        let imp = self.imp();
+
+        let matched = pattern.is_some_and(|pattern| pattern.matches(&self.full_topic()));
+
+        if imp.matched.replace(matched) != matched {
+            self.notify_matched();
+        }
+
+        let matched_below = pattern.is_some()
+            && self
+                .children()
+                .iter::<MQTTyTopicItem>()
+                .flatten()
+                .any(|child| child.matched() || child.matched_below());
+
+        imp.matched_below.set(matched_below);
+        imp.filtered_out
+            .set(pattern.is_some() && !matched && !matched_below);
+    }
+
+    /// Whether the branch has to be expanded to see the topics matching the filter
+    pub fn matched_below(&self) -> bool {
+        self.imp().matched_below.get()
+    }
+
+    /// Same as filter(), for every topic of the subtree
+    pub fn filter_recursive(&self, pattern: Option<&MQTTyTopicPattern>) {
+        for child in self.children().iter::<MQTTyTopicItem>().flatten() {
+            child.filter_recursive(pattern);
+        }
+
+        self.filter(pattern);
+    }
+
+    /// Turns the messages received since the last tick into the rate, returns false
+    /// once the item is idle, that is, when it doesn't need to be ticked anymore
+    pub fn tick(&self, elapsed: Duration) -> bool {
+        let imp = self.imp();
+
+        let count = imp.tick_count.replace(0);
+        let rate = count as f64 / elapsed.as_secs_f64();
+
+        if imp.rate.replace(rate) != rate {
+            self.notify_rate();
         }
-        None
+
+        if count == 0 && imp.highlighted.replace(false) {
+            self.notify_highlighted();
+        }
+
+        count > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHILDREN: usize = 100_000;
+
+    #[test]
+    fn children_index_follows_the_store() {
+        let parent = MQTTyTopicItem::new("parent", "parent");
+
+        for i in 0..CHILDREN {
+            let name = format!("child{i}");
+            let child = parent.ensure_child(&name, &format!("parent/{name}"));
+
+            assert_eq!(parent.find_child(&name), Some(child));
+        }
+
+        // Existing children are found, not created again
+        let existing = parent.find_child("child7").unwrap();
+        assert_eq!(parent.ensure_child("child7", "parent/child7"), existing);
+
+        assert_eq!(parent.children().n_items() as usize, CHILDREN);
+        assert_eq!(parent.imp().children_index.borrow().len(), CHILDREN);
+
+        // A child of the same name replaces the previous one instead of duplicating it
+        let replaced = parent.find_child("child42").unwrap();
+        let replacement = MQTTyTopicItem::new("child42", "parent/child42");
+        parent.add_child(&replacement);
+
+        assert_eq!(parent.children().n_items() as usize, CHILDREN);
+        assert_eq!(parent.imp().children_index.borrow().len(), CHILDREN);
+        assert_eq!(parent.find_child("child42"), Some(replacement.clone()));
+        assert!(parent.children().find(&replacement).is_some());
+        assert!(parent.children().find(&replaced).is_none());
     }
 }
@@ -1,5 +1,10 @@
+mod clear_retained_dialog;
+mod detail_pane;
 mod item;
+mod pattern;
 mod view;
 
+pub use clear_retained_dialog::MQTTyClearRetainedDialog;
+pub use detail_pane::MQTTyTopicDetailPane;
 pub use item::MQTTyTopicItem;
 pub use view::MQTTyTopicTreeView;
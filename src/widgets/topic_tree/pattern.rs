@@ -0,0 +1,108 @@
+use gtk::glib;
+
+use crate::client::topic_matches_filter;
+
+/// What the filter of the topic tree looks for in the topics
+pub enum MQTTyTopicPattern {
+    /// Text found anywhere in the topic, ignoring case
+    Text(String),
+    /// Regular expression found anywhere in the topic, `(?i)` makes it ignore case
+    Regex(glib::Regex),
+    /// Topic filter as subscribed, with the `+` and `#` wildcards
+    Wildcard(String),
+}
+
+impl MQTTyTopicPattern {
+    /// None if the text is empty, that is, if every topic is shown
+    pub fn text(text: &str) -> Option<Self> {
+        (!text.is_empty()).then(|| Self::Text(text.to_lowercase()))
+    }
+
+    /// None if the expression is empty, fails if it's not a valid one
+    pub fn regex(expression: &str) -> Result<Option<Self>, glib::Error> {
+        if expression.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(glib::Regex::new(
+            expression,
+            glib::RegexCompileFlags::OPTIMIZE,
+            glib::RegexMatchFlags::empty(),
+        )?
+        .map(Self::Regex))
+    }
+
+    /// None if the filter is empty
+    pub fn wildcard(filter: &str) -> Option<Self> {
+        (!filter.is_empty()).then(|| Self::Wildcard(filter.to_string()))
+    }
+
+    pub fn matches(&self, topic: &str) -> bool {
+        match self {
+            Self::Text(text) => topic.to_lowercase().contains(text.as_str()),
+            Self::Regex(regex) => regex
+                .match_(
+                    glib::GString::from(topic).as_gstr(),
+                    glib::RegexMatchFlags::empty(),
+                )
+                .is_some(),
+            Self::Wildcard(filter) => topic_matches_filter(filter, topic),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_patterns_match_everything() {
+        assert!(MQTTyTopicPattern::text("").is_none());
+        assert!(MQTTyTopicPattern::regex("").unwrap().is_none());
+        assert!(MQTTyTopicPattern::wildcard("").is_none());
+    }
+
+    #[test]
+    fn text_is_case_insensitive_substring() {
+        let pattern = MQTTyTopicPattern::text("Temp").unwrap();
+
+        assert!(pattern.matches("home/temperature"));
+        assert!(pattern.matches("HOME/TEMP"));
+        assert!(!pattern.matches("home/humidity"));
+    }
+
+    #[test]
+    fn regex_is_found_anywhere_unless_anchored() {
+        let pattern = MQTTyTopicPattern::regex(r"^sensors/\d+/temp$")
+            .unwrap()
+            .unwrap();
+
+        assert!(pattern.matches("sensors/42/temp"));
+        assert!(!pattern.matches("sensors/x/temp"));
+        assert!(!pattern.matches("home/sensors/42/temp"));
+
+        let pattern = MQTTyTopicPattern::regex("temp").unwrap().unwrap();
+        assert!(pattern.matches("home/temperature"));
+        assert!(!pattern.matches("home/TEMPERATURE"));
+    }
+
+    #[test]
+    fn invalid_regex_fails() {
+        assert!(MQTTyTopicPattern::regex("sensors/(").is_err());
+        assert!(MQTTyTopicPattern::regex("[a-").is_err());
+    }
+
+    #[test]
+    fn wildcard_follows_mqtt_filters() {
+        let pattern = MQTTyTopicPattern::wildcard("sensors/+/temp").unwrap();
+
+        assert!(pattern.matches("sensors/42/temp"));
+        assert!(!pattern.matches("sensors/42/humidity"));
+        assert!(!pattern.matches("sensors/a/b/temp"));
+
+        let pattern = MQTTyTopicPattern::wildcard("sensors/#").unwrap();
+        assert!(pattern.matches("sensors"));
+        assert!(pattern.matches("sensors/a/b"));
+        assert!(!pattern.matches("$SYS/sensors"));
+    }
+}
@@ -1,5 +1,6 @@
 use std::cell::{OnceCell, RefCell};
 use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
 use gettextrs::gettext;
 use gtk::{gio, glib};
@@ -7,7 +8,12 @@ use gtk::glib::subclass::Signal;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
+use super::pattern::MQTTyTopicPattern;
 use super::MQTTyTopicItem;
+use crate::client::{shared_subscription, MQTTyClientMessage, MQTTyClientQos};
+
+/// Rates are computed and highlights cleared once per tick
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
 
 mod imp {
     use super::*;
@@ -17,6 +23,55 @@ mod imp {
     <interface>
       <template class="MQTTyTopicTreeView" parent="GtkBox">
         <property name="orientation">vertical</property>
+        <child>
+          <object class="GtkBox">
+            <property name="spacing">6</property>
+            <property name="margin-start">6</property>
+            <property name="margin-end">6</property>
+            <property name="margin-top">6</property>
+            <property name="margin-bottom">6</property>
+            <child>
+              <object class="GtkSearchEntry" id="filter_entry">
+                <property name="hexpand">true</property>
+                <property name="placeholder-text" translatable="yes">Filter topics</property>
+              </object>
+            </child>
+            <child>
+              <object class="GtkDropDown" id="filter_mode_dropdown">
+                <property name="tooltip-text" translatable="yes">How the filter is matched against the topics</property>
+                <property name="model">
+                  <object class="GtkStringList">
+                    <items>
+                      <item translatable="yes">Text</item>
+                      <item translatable="yes">Regex</item>
+                      <item translatable="yes">Wildcard</item>
+                    </items>
+                  </object>
+                </property>
+              </object>
+            </child>
+            <child>
+              <object class="GtkButton">
+                <property name="icon-name">go-down-symbolic</property>
+                <property name="tooltip-text" translatable="yes">Expand All</property>
+                <property name="action-name">topic-tree.expand-all</property>
+                <style>
+                  <class name="flat"/>
+                </style>
+              </object>
+            </child>
+            <child>
+              <object class="GtkButton">
+                <property name="icon-name">go-up-symbolic</property>
+                <property name="tooltip-text" translatable="yes">Collapse All</property>
+                <property name="action-name">topic-tree.collapse-all</property>
+                <style>
+                  <class name="flat"/>
+                </style>
+              </object>
+            </child>
+          </object>
+        </child>
         <child>
           <object class="GtkScrolledWindow">
             <property name="vexpand">true</property>
@@ -25,20 +80,46 @@ mod imp {
                 <property name="show-row-separators">true</property>
                 <property name="show-column-separators">true</property>
                 <child>
-                    <object class="GtkColumnViewColumn">
-                        <property name="title">Topic</property>
+                    <object class="GtkColumnViewColumn" id="topic_column">
+                        <property name="title" translatable="yes">Topic</property>
                         <property name="expand">true</property>
-                        <property name="factory">
-                            <object class="GtkSignalListItemFactory" id="topic_factory"/>
-                        </property>
+                        <property name="resizable">true</property>
                     </object>
                 </child>
                 <child>
-                    <object class="GtkColumnViewColumn">
-                        <property name="title">Value</property>
-                        <property name="factory">
-                            <object class="GtkSignalListItemFactory" id="value_factory"/>
-                        </property>
+                    <object class="GtkColumnViewColumn" id="value_column">
+                        <property name="title" translatable="yes">Value</property>
+                        <property name="resizable">true</property>
+                    </object>
+                </child>
+                <child>
+                    <object class="GtkColumnViewColumn" id="count_column">
+                        <property name="title" translatable="yes">Messages</property>
+                    </object>
+                </child>
+                <child>
+                    <object class="GtkColumnViewColumn" id="rate_column">
+                        <property name="title" translatable="yes">Rate</property>
+                    </object>
+                </child>
+                <child>
+                    <object class="GtkColumnViewColumn" id="bytes_column">
+                        <property name="title" translatable="yes">Size</property>
+                    </object>
+                </child>
+                <child>
+                    <object class="GtkColumnViewColumn" id="last_received_column">
+                        <property name="title" translatable="yes">Last Update</property>
+                    </object>
+                </child>
+                <child>
+                    <object class="GtkColumnViewColumn" id="qos_column">
+                        <property name="title" translatable="yes">QoS</property>
+                    </object>
+                </child>
+                <child>
+                    <object class="GtkColumnViewColumn" id="retained_column">
+                        <property name="title" translatable="yes">Retained</property>
                     </object>
                 </child>
               </object>
@@ -50,11 +131,60 @@ mod imp {
     "#)]
     #[properties(wrapper_type = super::MQTTyTopicTreeView)]
     pub struct MQTTyTopicTreeView {
+        #[template_child]
+        pub filter_entry: TemplateChild<gtk::SearchEntry>,
+
+        #[template_child]
+        pub filter_mode_dropdown: TemplateChild<gtk::DropDown>,
+
         #[template_child]
         pub column_view: TemplateChild<gtk::ColumnView>,
 
-        pub root_model: OnceCell<gio::ListStore>,
+        #[template_child]
+        pub topic_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub value_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub count_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub rate_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub bytes_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub last_received_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub qos_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        #[template_child]
+        pub retained_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        /// Parent of the first level of the topics, it's not shown itself, its children
+        /// are the model of the tree
+        pub root: OnceCell<MQTTyTopicItem>,
+
+        /// Item of the selected row
+        #[property(get, nullable)]
+        pub selected_item: RefCell<Option<MQTTyTopicItem>>,
+
         pub tree_model: RefCell<Option<gtk::TreeListModel>>,
+
+        /// Filter of the topics typed by the user, None while every topic is shown
+        pub pattern: RefCell<Option<MQTTyTopicPattern>>,
+
+        /// Hides the topics of every level of the tree filtered out by the pattern
+        pub filter: OnceCell<gtk::CustomFilter>,
+
+        /// Items that received messages since they were last idle, see
+        /// MQTTyTopicItem::tick()
+        pub ticked_items: RefCell<Vec<MQTTyTopicItem>>,
+
+        pub tick_source: RefCell<Option<glib::SourceId>>,
     }
 
     #[glib::object_subclass]
@@ -80,6 +210,15 @@ mod imp {
                     Signal::builder("clear-retained-requested")
                         .param_types([String::static_type()])
                         .build(),
+                    Signal::builder("clear-retained-recursive-requested")
+                        .param_types([MQTTyTopicItem::static_type()])
+                        .build(),
+                    Signal::builder("subscribe-requested")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("publish-requested")
+                        .param_types([String::static_type()])
+                        .build(),
                 ]
             });
             &SIGNALS
@@ -88,78 +227,192 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
-            let obj = self.obj();
-            let root = gio::ListStore::new::<MQTTyTopicItem>();
-            let _ = self.root_model.set(root.clone());
+            let root = MQTTyTopicItem::new("", "");
+            let _ = self.root.set(root.clone());
+
+            // Every level is filtered on its own, branches are kept as long as one of
+            // their descendants is visible
+            let filter = gtk::CustomFilter::new(|item| {
+                let item = item.downcast_ref::<MQTTyTopicItem>().unwrap();
+                !item.filtered_out()
+            });
+            let _ = self.filter.set(filter.clone());
 
             let tree_model = gtk::TreeListModel::new(
-                root,
+                gtk::FilterListModel::new(Some(root.children()), Some(filter.clone())),
                 false,
                 false,
-                |item| {
+                move |item| {
                     let item = item.downcast_ref::<MQTTyTopicItem>().unwrap();
-                    Some(item.children().upcast())
+                    let children =
+                        gtk::FilterListModel::new(Some(item.children()), Some(filter.clone()));
+                    Some(children.upcast())
                 }
             );
-            
-            let selection = gtk::SingleSelection::new(Some(tree_model.clone()));
+
+            // Siblings are sorted by the column the user picked, children stay below
+            // their parent
+            let sorter = gtk::TreeListRowSorter::new(self.column_view.sorter());
+            let sort_model = gtk::SortListModel::new(Some(tree_model.clone()), Some(sorter));
+
+            let obj = self.obj();
+
+            let selection = gtk::SingleSelection::new(Some(sort_model));
+            selection.connect_selected_item_notify(glib::clone!(
+                #[weak]
+                obj,
+                move |selection| {
+                    let item = selection
+                        .selected_item()
+                        .and_downcast::<gtk::TreeListRow>()
+                        .and_then(|row| row.item())
+                        .and_downcast::<MQTTyTopicItem>();
+
+                    obj.imp().selected_item.replace(item);
+                    obj.notify_selected_item();
+                }
+            ));
             self.column_view.set_model(Some(&selection));
             self.tree_model.replace(Some(tree_model));
 
-            // Setup Factories
+            self.setup_columns();
+
+            let dropdown = &self.filter_mode_dropdown;
+
+            self.filter_entry.connect_search_changed(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.update_filter()
+            ));
+            dropdown.connect_selected_notify(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.update_filter()
+            ));
+
+            // Setup context menu for right-click
+            self.setup_context_menu();
+        }
+
+        fn dispose(&self) {
+            if let Some(source) = self.tick_source.take() {
+                source.remove();
+            }
+        }
+    }
+
+    impl MQTTyTopicTreeView {
+        fn setup_columns(&self) {
             // Topic Column (with TreeExpander)
-            let topic_factory = obj.imp().column_view.columns().item(0).unwrap().downcast::<gtk::ColumnViewColumn>().unwrap().factory().unwrap().downcast::<gtk::SignalListItemFactory>().unwrap();
-            
+            let topic_factory = gtk::SignalListItemFactory::new();
+
             topic_factory.connect_setup(|_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+
+                let label = gtk::Label::builder().xalign(0.0).build();
+
                 let expander = gtk::TreeExpander::new();
-                let label = gtk::Label::new(None);
-                label.set_halign(gtk::Align::Start);
                 expander.set_child(Some(&label));
-                list_item.downcast_ref::<gtk::ListItem>().unwrap().set_child(Some(&expander));
-            });
 
-            topic_factory.connect_bind(|_, list_item| {
-                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-                let expander = list_item.child().unwrap().downcast::<gtk::TreeExpander>().unwrap();
-                let row = list_item.item().and_downcast::<gtk::TreeListRow>().unwrap();
-                let item = row.item().and_downcast::<MQTTyTopicItem>().unwrap();
+                list_item.property_expression_weak("item").bind(
+                    &expander,
+                    "list-row",
+                    gtk::Widget::NONE,
+                );
+                bind_label(list_item, &label, "name", format_string);
+
+                // Topics matching the filter stand out from the ancestors shown along
+                // with them
+                list_item
+                    .property_expression_weak("item")
+                    .chain_property::<gtk::TreeListRow>("item")
+                    .chain_property::<MQTTyTopicItem>("matched")
+                    .chain_closure_with_callback(|args| {
+                        if args[1].get::<bool>().unwrap() {
+                            vec![String::from("topic-match")]
+                        } else {
+                            vec![]
+                        }
+                    })
+                    .bind(&expander, "css-classes", gtk::Widget::NONE);
 
-                expander.set_list_row(Some(&row));
-                let label = expander.child().unwrap().downcast::<gtk::Label>().unwrap();
-                item.bind_property("name", &label, "label").sync_create().build();
+                list_item.set_child(Some(&expander));
             });
 
-            // Value Column
-            let value_factory = obj.imp().column_view.columns().item(1).unwrap().downcast::<gtk::ColumnViewColumn>().unwrap().factory().unwrap().downcast::<gtk::SignalListItemFactory>().unwrap();
-            
-            value_factory.connect_setup(|_, list_item| {
-                let label = gtk::Label::new(None);
-                label.set_halign(gtk::Align::Start);
-                label.set_ellipsize(gtk::pango::EllipsizeMode::End);
-                list_item.downcast_ref::<gtk::ListItem>().unwrap().set_child(Some(&label));
-            });
+            self.topic_column.set_factory(Some(&topic_factory));
+            self.topic_column.set_sorter(Some(&string_sorter("name")));
 
-            value_factory.connect_bind(|_, list_item| {
-                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-                let label = list_item.child().unwrap().downcast::<gtk::Label>().unwrap();
-                let row = list_item.item().and_downcast::<gtk::TreeListRow>().unwrap();
-                let item = row.item().and_downcast::<MQTTyTopicItem>().unwrap();
-
-                item.bind_property("payload", &label, "label").sync_create().build();
-            });
+            // Value Column
+            self.value_column
+                .set_factory(Some(&label_factory(0.0, "payload", format_string)));
+
+            // Statistics Columns, aggregated by branches
+            for (column, property, format) in [
+                (&self.count_column, "message-count", format_count as Format),
+                (&self.rate_column, "rate", format_rate),
+                (&self.bytes_column, "bytes", format_bytes),
+                (&self.retained_column, "retained-count", format_retained),
+            ] {
+                column.set_factory(Some(&label_factory(1.0, property, format)));
+                column.set_sorter(Some(&numeric_sorter(property)));
+            }
 
-            // Setup context menu for right-click
-            self.setup_context_menu();
+            self.last_received_column.set_factory(Some(&label_factory(
+                0.0,
+                "last-received",
+                format_time,
+            )));
+            self.last_received_column
+                .set_sorter(Some(&gtk::CustomSorter::new(|a, b| {
+                    let last_received = |item: &glib::Object| {
+                        item.downcast_ref::<MQTTyTopicItem>()
+                            .unwrap()
+                            .last_received()
+                    };
+
+                    last_received(a).cmp(&last_received(b)).into()
+                })));
+
+            self.qos_column
+                .set_factory(Some(&label_factory(0.5, "qos", format_qos)));
         }
-    }
 
-    impl MQTTyTopicTreeView {
         fn setup_context_menu(&self) {
             let obj = self.obj();
 
             // Create popover menu
+            let topic_section = gio::Menu::new();
+            topic_section.append(
+                Some(&gettext("Subscribe to This Branch")),
+                Some("topic-tree.subscribe-branch"),
+            );
+            topic_section.append(
+                Some(&gettext("Publish to This Topic")),
+                Some("topic-tree.publish-topic"),
+            );
+
+            let retained_section = gio::Menu::new();
+            retained_section.append(
+                Some(&gettext("Clear Retained Message")),
+                Some("topic-tree.clear-retained"),
+            );
+            retained_section.append(
+                Some(&gettext("Delete Retained Messages Recursively")),
+                Some("topic-tree.clear-retained-recursive"),
+            );
+
+            let tree_section = gio::Menu::new();
+            for (label, action) in [
+                (gettext("Expand All"), "topic-tree.expand-all"),
+                (gettext("Collapse All"), "topic-tree.collapse-all"),
+            ] {
+                tree_section.append(Some(&label), Some(action));
+            }
+
             let menu = gio::Menu::new();
-            menu.append(Some(&gettext("Clear Retained Message")), Some("topic-tree.clear-retained"));
+            menu.append_section(None, &topic_section);
+            menu.append_section(None, &retained_section);
+            menu.append_section(None, &tree_section);
 
             let popover = gtk::PopoverMenu::from_model(Some(&menu));
             popover.set_parent(&*self.column_view);
@@ -184,6 +437,60 @@ mod imp {
                 }
             });
             action_group.add_action(&clear_action);
+
+            let clear_recursive_action = gio::SimpleAction::new("clear-retained-recursive", None);
+            clear_recursive_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    if let Some(item) = obj.selected_item() {
+                        obj.emit_by_name::<()>("clear-retained-recursive-requested", &[&item]);
+                    }
+                }
+            ));
+            action_group.add_action(&clear_recursive_action);
+
+            // A branch is subscribed along with the topic of the branch itself
+            let subscribe_action = gio::SimpleAction::new("subscribe-branch", None);
+            subscribe_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    if let Some(item) = obj.selected_item() {
+                        let filter = format!("{}/#", item.full_topic());
+                        obj.emit_by_name::<()>("subscribe-requested", &[&filter]);
+                    }
+                }
+            ));
+            action_group.add_action(&subscribe_action);
+
+            let publish_action = gio::SimpleAction::new("publish-topic", None);
+            publish_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    if let Some(item) = obj.selected_item() {
+                        obj.emit_by_name::<()>("publish-requested", &[&item.full_topic()]);
+                    }
+                }
+            ));
+            action_group.add_action(&publish_action);
+
+            let expand_action = gio::SimpleAction::new("expand-all", None);
+            expand_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| obj.expand_all()
+            ));
+            action_group.add_action(&expand_action);
+
+            let collapse_action = gio::SimpleAction::new("collapse-all", None);
+            collapse_action.connect_activate(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| obj.collapse_all()
+            ));
+            action_group.add_action(&collapse_action);
             obj.insert_action_group("topic-tree", Some(&action_group));
 
             // Setup gesture for right-click
@@ -215,44 +522,461 @@ impl MQTTyTopicTreeView {
         glib::Object::builder().build()
     }
 
-    pub fn process_message(&self, topic: &str, payload: &str) {
-        let parts: Vec<&str> = topic.split('/').collect();
-        let Some(root_model) = self.imp().root_model.get() else {
+    /// Adds the topic of the message to the tree, the statistics of every level of
+    /// the topic are updated along with it
+    pub fn process_message(&self, message: &MQTTyClientMessage) {
+        let topic = message.topic();
+        let topic = tree_topic(&topic);
+
+        let Some(root) = self.imp().root.get() else {
+            return;
+        };
+
+        // Every level is looked up by name in the index of its parent, so that the
+        // cost doesn't grow with the number of siblings
+        let mut parent = root.clone();
+        let mut path = Vec::new();
+        let mut end = 0;
+
+        for part in topic.split('/') {
+            end += part.len();
+            let item = parent.ensure_child(part, &topic[..end]);
+            end += 1;
+
+            parent = item.clone();
+            path.push(item);
+        }
+
+        let Some((leaf, ancestors)) = path.split_last() else {
             return;
         };
 
-        let mut current_model = root_model.clone();
-        let mut full_path = String::new();
+        // Topics that never received a message were just added to the tree
+        let new_topic = leaf.message_count() == 0;
+
+        leaf.push_message(message);
+
+        let body = message.body();
+
+        // Live messages are delivered without the retain flag, so only a retained
+        // delivery tells whether the broker holds one, an empty one means it was
+        // cleared
+        if message.retained() {
+            let delta = leaf.set_retained(!body.is_empty());
 
-        for (i, part) in parts.iter().enumerate() {
-            if i > 0 {
-                full_path.push('/');
+            for item in ancestors {
+                item.add_retained_count(delta);
             }
-            full_path.push_str(part);
-
-            let mut found = None;
-            for j in 0..current_model.n_items() {
-                if let Some(item) = current_model.item(j).and_downcast::<MQTTyTopicItem>() {
-                    if item.name() == *part {
-                        found = Some(item);
-                        break;
-                    }
-                }
+        }
+
+        self.record(&path, message);
+
+        if new_topic {
+            self.filter_path(&path);
+        }
+    }
+
+    /// Expands every branch of the tree
+    pub fn expand_all(&self) {
+        let Some(tree_model) = self.imp().tree_model.borrow().clone() else {
+            return;
+        };
+
+        // Expanding a row inserts its children right after it, so they are expanded
+        // along the way
+        let mut position = 0;
+
+        while let Some(row) = tree_model.row(position) {
+            row.set_expanded(true);
+            position += 1;
+        }
+    }
+
+    /// Collapses every branch of the tree, down to the first level
+    pub fn collapse_all(&self) {
+        let Some(tree_model) = self.imp().tree_model.borrow().clone() else {
+            return;
+        };
+
+        // Collapsing a row removes its descendants, so the next row is the next
+        // topic of the first level
+        let mut position = 0;
+
+        while let Some(row) = tree_model.row(position) {
+            row.set_expanded(false);
+            position += 1;
+        }
+    }
+
+    /// Expands the branches leading to the topics matching the filter, the rest are
+    /// left as they are
+    fn expand_matches(&self) {
+        let Some(tree_model) = self.imp().tree_model.borrow().clone() else {
+            return;
+        };
+
+        // Same as expand_all(), the children of an expanded row are checked next
+        let mut position = 0;
+
+        while let Some(row) = tree_model.row(position) {
+            if row
+                .item()
+                .and_downcast::<MQTTyTopicItem>()
+                .is_some_and(|item| item.matched_below())
+            {
+                row.set_expanded(true);
             }
+            position += 1;
+        }
+    }
 
-            let item = if let Some(item) = found {
-                item
-            } else {
-                let new_item = MQTTyTopicItem::new(part, &full_path);
-                current_model.append(&new_item);
-                new_item
+    /// Filters the tree by the text of the filter entry, matched as picked in the
+    /// drop down. The branches holding matching topics are expanded so that they're
+    /// seen
+    fn update_filter(&self) {
+        let imp = self.imp();
+
+        let text = imp.filter_entry.text();
+
+        let pattern = match imp.filter_mode_dropdown.selected() {
+            1 => MQTTyTopicPattern::regex(&text),
+            2 => Ok(MQTTyTopicPattern::wildcard(&text)),
+            _ => Ok(MQTTyTopicPattern::text(&text)),
+        };
+
+        // Every topic is shown until the expression is fixed
+        let pattern = match pattern {
+            Ok(pattern) => {
+                imp.filter_entry.remove_css_class("error");
+                imp.filter_entry.set_tooltip_text(None);
+                pattern
+            }
+            Err(e) => {
+                imp.filter_entry.add_css_class("error");
+                imp.filter_entry.set_tooltip_text(Some(e.message()));
+                None
+            }
+        };
+
+        let expand = pattern.is_some();
+
+        if let Some(root) = imp.root.get() {
+            root.filter_recursive(pattern.as_ref());
+        }
+
+        imp.pattern.replace(pattern);
+
+        if let Some(filter) = imp.filter.get() {
+            filter.changed(gtk::FilterChange::Different);
+        }
+
+        if expand {
+            self.expand_matches();
+        }
+    }
+
+    /// Filters the topics of the path after a new topic was added at its end, the
+    /// rest of the tree is unchanged
+    fn filter_path(&self, path: &[MQTTyTopicItem]) {
+        let imp = self.imp();
+
+        let pattern = imp.pattern.borrow();
+
+        if pattern.is_none() {
+            return;
+        }
+
+        // Children first, branches are visible as long as one of them is
+        for item in path.iter().rev() {
+            item.filter(pattern.as_ref());
+        }
+
+        if let Some(filter) = imp.filter.get() {
+            filter.changed(gtk::FilterChange::Different);
+        }
+    }
+
+    /// Marks the topic as no longer holding a retained message, once it was cleared
+    ///
+    /// Clearing it doesn't deliver a retained message to the subscribers, so the tree
+    /// can't tell by itself
+    pub fn forget_retained(&self, topic: &str) {
+        let Some(root) = self.imp().root.get() else {
+            return;
+        };
+
+        let mut path = vec![];
+        let mut parent = root.clone();
+
+        for part in tree_topic(topic).split('/') {
+            let Some(item) = parent.find_child(part) else {
+                return;
             };
 
-            if i == parts.len() - 1 {
-                item.set_payload(payload);
+            path.push(item.clone());
+            parent = item;
+        }
+
+        let Some((leaf, ancestors)) = path.split_last() else {
+            return;
+        };
+
+        let delta = leaf.set_retained(false);
+
+        for item in ancestors {
+            item.add_retained_count(delta);
+        }
+    }
+
+    /// Forgets the message history of every topic, e.g. when the messages of the tab
+    /// are cleared
+    pub fn clear_history(&self) {
+        if let Some(root) = self.imp().root.get() {
+            root.clear_history_recursive();
+        }
+    }
+
+    /// Item of the topic, None if no message of that topic was received yet
+    pub fn topic_item(&self, topic: &str) -> Option<MQTTyTopicItem> {
+        let root = self.imp().root.get()?;
+
+        tree_topic(topic)
+            .split('/')
+            .try_fold(root.clone(), |parent, part| parent.find_child(part))
+    }
+
+    fn record(&self, path: &[MQTTyTopicItem], message: &MQTTyClientMessage) {
+        let imp = self.imp();
+
+        let mut ticked_items = imp.ticked_items.borrow_mut();
+
+        for item in path {
+            if item.record(message) {
+                ticked_items.push(item.clone());
+            }
+        }
+
+        if ticked_items.is_empty() || imp.tick_source.borrow().is_some() {
+            return;
+        }
+
+        let mut last_tick = Instant::now();
+
+        let source = glib::timeout_add_local(
+            TICK_INTERVAL,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    let elapsed = last_tick.elapsed();
+                    last_tick = Instant::now();
+
+                    let imp = obj.imp();
+
+                    let mut ticked_items = imp.ticked_items.borrow_mut();
+                    ticked_items.retain(|item| item.tick(elapsed));
+
+                    if ticked_items.is_empty() {
+                        imp.tick_source.take();
+                        glib::ControlFlow::Break
+                    } else {
+                        glib::ControlFlow::Continue
+                    }
+                }
+            ),
+        );
+
+        imp.tick_source.replace(Some(source));
+    }
+}
+
+/// Shared subscriptions deliver the real topic, but a `$share/group/` prefix must
+/// never become a branch of the tree
+fn tree_topic(topic: &str) -> &str {
+    shared_subscription(topic).map_or(topic, |(_, topic)| topic)
+}
+
+/// Expression of a property of the MQTTyTopicItem held by the tree rows
+fn item_property(property: &str) -> gtk::PropertyExpression {
+    gtk::PropertyExpression::new(
+        MQTTyTopicItem::static_type(),
+        None::<&gtk::Expression>,
+        property,
+    )
+}
+
+fn string_sorter(property: &str) -> gtk::StringSorter {
+    gtk::StringSorter::new(Some(item_property(property)))
+}
+
+fn numeric_sorter(property: &str) -> gtk::NumericSorter {
+    gtk::NumericSorter::new(Some(item_property(property)))
+}
+
+/// Formats the value of a property of MQTTyTopicItem as the text of a cell
+type Format = fn(&glib::Value) -> String;
+
+fn format_string(value: &glib::Value) -> String {
+    value.get::<String>().unwrap()
+}
+
+fn format_count(value: &glib::Value) -> String {
+    value.get::<u64>().unwrap().to_string()
+}
+
+fn format_rate(value: &glib::Value) -> String {
+    match value.get::<f64>().unwrap() {
+        rate if rate > 0.0 => format!("{:.1}/s", rate),
+        _ => String::new(),
+    }
+}
+
+fn format_bytes(value: &glib::Value) -> String {
+    glib::format_size(value.get::<u64>().unwrap()).to_string()
+}
+
+fn format_time(value: &glib::Value) -> String {
+    value
+        .get::<Option<glib::DateTime>>()
+        .unwrap()
+        .and_then(|time| time.format("%X").ok())
+        .map(|time| time.to_string())
+        .unwrap_or_default()
+}
+
+fn format_qos(value: &glib::Value) -> String {
+    match value.get::<MQTTyClientQos>().unwrap() {
+        MQTTyClientQos::Qos0 => String::from("0"),
+        MQTTyClientQos::Qos1 => String::from("1"),
+        MQTTyClientQos::Qos2 => String::from("2"),
+    }
+}
+
+/// Branches show how many of their topics hold a retained message
+fn format_retained(value: &glib::Value) -> String {
+    match value.get::<u32>().unwrap() {
+        0 => String::new(),
+        count => count.to_string(),
+    }
+}
+
+/// Factory of cells labeled with a property of the item of the row, formatted by
+/// `format`
+fn label_factory(
+    xalign: f32,
+    property: &'static str,
+    format: Format,
+) -> gtk::SignalListItemFactory {
+    let factory = gtk::SignalListItemFactory::new();
+
+    factory.connect_setup(move |_, list_item| {
+        let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+
+        let label = gtk::Label::builder()
+            .xalign(xalign)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build();
+
+        bind_label(list_item, &label, property, format);
+
+        list_item.set_child(Some(&label));
+    });
+
+    factory
+}
+
+/// Binds the label to the property of the item of the row, the label is highlighted
+/// along with the item
+///
+/// Expressions follow the list item as it's recycled for other rows
+fn bind_label(list_item: &gtk::ListItem, label: &gtk::Label, property: &str, format: Format) {
+    let item = list_item
+        .property_expression_weak("item")
+        .chain_property::<gtk::TreeListRow>("item");
+
+    item.chain_property::<MQTTyTopicItem>(property)
+        .chain_closure_with_callback(move |args| format(&args[1]))
+        .bind(label, "label", gtk::Widget::NONE);
+
+    item.chain_property::<MQTTyTopicItem>("highlighted")
+        .chain_closure_with_callback(|args| {
+            if args[1].get::<bool>().unwrap() {
+                vec![String::from("topic-updated")]
+            } else {
+                vec![]
             }
+        })
+        .bind(label, "css-classes", gtk::Widget::NONE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITES: usize = 10;
+    const FLOORS: usize = 10;
+    const SENSORS: usize = 20;
 
-            current_model = item.children();
+    fn message(topic: &str) -> MQTTyClientMessage {
+        let message = MQTTyClientMessage::new();
+        message.set_topic(topic);
+        message.set_body(b"21.5");
+        message
+    }
+
+    #[test]
+    fn process_message_builds_the_tree() {
+        // The view is a widget, GTK needs a display
+        if gtk::init().is_err() {
+            eprintln!("Skipping, GTK could not be initialized");
+            return;
         }
+
+        let view = MQTTyTopicTreeView::new();
+
+        // Kept alive, the history of the topics only holds weak references
+        let mut messages = Vec::new();
+
+        for _ in 0..2 {
+            for site in 0..SITES {
+                for floor in 0..FLOORS {
+                    for sensor in 0..SENSORS {
+                        let message = message(&format!("site{site}/floor{floor}/sensor{sensor}"));
+                        view.process_message(&message);
+                        messages.push(message);
+                    }
+                }
+            }
+        }
+
+        // Shared subscriptions land on the real topic
+        let shared = message("$share/group/site0/floor0/sensor0");
+        view.process_message(&shared);
+
+        let root = view.imp().root.get().unwrap();
+        assert_eq!(root.children().n_items() as usize, SITES);
+
+        let site = view.topic_item("site3").unwrap();
+        assert_eq!(site.children().n_items() as usize, FLOORS);
+        assert_eq!(site.message_count() as usize, 2 * FLOORS * SENSORS);
+
+        let floor = view.topic_item("site3/floor4").unwrap();
+        assert_eq!(floor.children().n_items() as usize, SENSORS);
+        assert_eq!(floor.message_count() as usize, 2 * SENSORS);
+        assert_eq!(floor.full_topic(), "site3/floor4");
+
+        let sensor = view.topic_item("site3/floor4/sensor5").unwrap();
+        assert_eq!(sensor.children().n_items(), 0);
+        assert_eq!(sensor.message_count(), 2);
+        assert_eq!(sensor.history().len(), 2);
+
+        let shared_sensor = view.topic_item("site0/floor0/sensor0").unwrap();
+        assert_eq!(shared_sensor.message_count(), 3);
+        assert_eq!(shared_sensor.latest_message(), Some(shared));
+        assert!(view.topic_item("$share").is_none());
+
+        assert!(view.topic_item("site3/floor4/sensor99").is_none());
     }
 }
@@ -0,0 +1,92 @@
+// Copyright (c) 2025 Oscar Pernia
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+
+use crate::client::{non_empty, TlsOptions};
+
+mod imp {
+
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/io/github/otaxhu/MQTTy/ui/websocket_group.ui")]
+    #[properties(wrapper_type = super::MQTTyWebSocketGroup)]
+    pub struct MQTTyWebSocketGroup {
+        #[property(get, set)]
+        ws_path: RefCell<String>,
+
+        /// Semicolon separated "Name: value" pairs
+        #[property(get, set)]
+        http_headers: RefCell<String>,
+
+        #[property(get, set)]
+        http_proxy: RefCell<String>,
+
+        #[property(get, set)]
+        https_proxy: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MQTTyWebSocketGroup {
+        const NAME: &'static str = "MQTTyWebSocketGroup";
+
+        type Type = super::MQTTyWebSocketGroup;
+
+        type ParentType = adw::PreferencesGroup;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::types::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for MQTTyWebSocketGroup {}
+    impl WidgetImpl for MQTTyWebSocketGroup {}
+    impl PreferencesGroupImpl for MQTTyWebSocketGroup {}
+}
+
+glib::wrapper! {
+    /// WebSocket transport rows, shared by the authentication tabs and the connection
+    /// profiles, they are kept in TlsOptions along with the TLS settings
+    pub struct MQTTyWebSocketGroup(ObjectSubclass<imp::MQTTyWebSocketGroup>)
+        @extends gtk::Widget, adw::PreferencesGroup,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl MQTTyWebSocketGroup {
+    /// Fills the WebSocket fields of the options, leaving the TLS ones untouched
+    pub fn fill_tls_options(&self, options: &mut TlsOptions) {
+        options.ws_path = non_empty(self.ws_path());
+        options.http_headers = TlsOptions::parse_http_headers(&self.http_headers());
+        options.http_proxy = non_empty(self.http_proxy());
+        options.https_proxy = non_empty(self.https_proxy());
+    }
+
+    pub fn set_tls_options(&self, options: &TlsOptions) {
+        self.set_ws_path(options.ws_path.clone().unwrap_or_default());
+        self.set_http_headers(TlsOptions::format_http_headers(&options.http_headers));
+        self.set_http_proxy(options.http_proxy.clone().unwrap_or_default());
+        self.set_https_proxy(options.https_proxy.clone().unwrap_or_default());
+    }
+}